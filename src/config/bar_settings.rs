@@ -2,10 +2,45 @@
 
 use crate::errors::Error;
 
-use super::WmResult;
+use super::{quote, Repr, WmResult};
+
+use std::fmt::Write;
 
 const POSITIONS: [&str; 3] = ["left", "right", "middle"];
 
+/// Names of the settings of a widget, which end the widget's command.
+const WIDGET_KEYS: [&str; 23] = [
+    "icon",
+    "icon_fg",
+    "icon_foreground",
+    "value_fg",
+    "value_foreground",
+    "separator_fg",
+    "separator_foreground",
+    "bg",
+    "bg_color",
+    "background_color",
+    "command",
+    "source",
+    "show_when",
+    "update_time",
+    "font",
+    "separator",
+    "format",
+    "style",
+    "graph_width",
+    "graph_fg",
+    "graph_foreground",
+    "graph_bg",
+    "graph_background",
+];
+
+/// Ways in which the value of a widget can be shown.
+const WIDGET_STYLES: [&str; 2] = ["text", "graph"];
+
+/// Built-in sources of the value of a widget, which don't run a command.
+const WIDGET_SOURCES: [&str; 3] = ["battery", "cpu", "memory"];
+
 #[derive(Clone, Debug)]
 /// Settings for a single widget.
 pub struct WidgetSettings {
@@ -13,8 +48,8 @@ pub struct WidgetSettings {
     pub id: String,
     /// How the widget should be displayed.
     /// Can be a string of characters for example, 'BATTERY' or '', or it can be a string that
-    /// starts with 'file:' followed by a path to an image wich will be attempted to be loaded and
-    /// used as an icon.
+    /// starts with 'file:' followed by a path to a PNG image wich will be attempted to be loaded
+    /// and used as an icon.
     pub icon: String,
     /// Foreground color of the icon text.
     pub icon_color: String,
@@ -26,6 +61,12 @@ pub struct WidgetSettings {
     pub background_color: String,
     /// A command that is run on every update.
     pub command: String,
+    /// A built-in source of the value, one of "battery", "cpu" and "memory", which reads it from
+    /// the system without running a command. Empty if the value comes from `command`.
+    pub source: String,
+    /// A command that is run on every update before `command`, the widget is only shown while
+    /// it exits successfully. Empty if the widget is always shown.
+    pub show_when: String,
     /// Time, in seconds, of how often should the widget be updated.
     pub update_time: u32,
     /// Font of the widget.
@@ -34,6 +75,15 @@ pub struct WidgetSettings {
     pub separator: String,
     /// Format string used when formatting widgets.
     pub format: String,
+    /// How the value is shown, either "text" or "graph". A graph shows a number between 0 and 100
+    /// as a horizontal bar.
+    pub style: String,
+    /// Width of the graph, in pixels.
+    pub graph_width: u32,
+    /// Color of the filled part of the graph.
+    pub graph_color: String,
+    /// Color of the empty part of the graph.
+    pub graph_background_color: String,
 }
 
 impl Default for WidgetSettings {
@@ -46,10 +96,16 @@ impl Default for WidgetSettings {
             separator_color: "#ffffff".into(),
             background_color: "#00a2ff".into(),
             command: "".into(),
+            source: "".into(),
+            show_when: "".into(),
             update_time: 0,
             font: "monospace".into(),
             separator: "|".into(),
             format: "{separator} {icon} {value} {separator}".into(),
+            style: "text".into(),
+            graph_width: 50,
+            graph_color: "#ffffff".into(),
+            graph_background_color: "#333333".into(),
         }
     }
 }
@@ -68,6 +124,8 @@ pub struct WorkspaceSegmentSettings {
     pub font: String,
     /// Format string which specifies how the name and id of the workspace should be rendered.
     pub format: String,
+    /// Text shown in place of the `{dnd}` format token for workspaces in "do not disturb" mode.
+    pub dnd_indicator: String,
 }
 
 impl Default for WorkspaceSegmentSettings {
@@ -79,6 +137,7 @@ impl Default for WorkspaceSegmentSettings {
             normal_background_color: "#333333".to_string(),
             font: "monospace".to_string(),
             format: " {name}:{id} ".into(),
+            dnd_indicator: "dnd".into(),
         }
     }
 }
@@ -120,14 +179,47 @@ pub struct BarSettings {
     pub height: u32,
     /// Background color of the bar.
     pub background_color: String,
-    /// Indicates whether the bar should be located on the top or the bottom of the screen.
-    pub location_top: bool,
+    /// Edge of the monitor the bar is placed on.
+    pub position: BarPosition,
+}
+
+/// Edge of the monitor a bar is placed on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BarPosition {
+    #[default]
+    Top,
+    Bottom,
+}
+
+impl BarPosition {
+    /// Return the name of the position, as used in the config file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+        }
+    }
+}
+
+impl TryFrom<&str> for BarPosition {
+    type Error = Error;
+
+    fn try_from(value: &str) -> WmResult<Self> {
+        match value.to_lowercase().as_str() {
+            "top" => Ok(Self::Top),
+            "bottom" => Ok(Self::Bottom),
+            _ => Err(format!(
+                "bar settings error: '{value}' is not a valid bar position, possible values are `top` and `bottom`."
+            )
+            .into()),
+        }
+    }
 }
 
 impl BarSettings {
     pub fn contains_tray(&self) -> bool {
         for segment in self.segments.iter() {
-            if matches!(segment.segment_type, SegmentSettingsType::Title(_)) {
+            if matches!(segment.segment_type, SegmentSettingsType::IconTray(_)) {
                 return true;
             }
         }
@@ -170,7 +262,7 @@ impl BarSettings {
             segments: Vec::new(),
             font_size: 10,
             height: 15,
-            location_top: true,
+            position: BarPosition::Top,
         }
     }
 }
@@ -179,6 +271,16 @@ impl BarSettings {
 pub struct AllBarSettings(Vec<BarSettings>);
 
 impl AllBarSettings {
+    /// Return the number of configured bars.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Are there no configured bars?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn add(
         &mut self,
         bar_identifier: u32,
@@ -259,12 +361,15 @@ impl AllBarSettings {
                         ii += 2;
                         match &value[..] {
                             "icon" => {
-                                widget.icon = bar_setting_values
-                                    .get(ii + 1)
-                                    .ok_or_else(|| {
-                                        Error::Generic(format!("missing value for {value}"))
-                                    })?
-                                    .to_string();
+                                let icon = bar_setting_values.get(ii + 1).ok_or_else(|| {
+                                    Error::Generic(format!("missing value for {value}"))
+                                })?;
+                                if let Some(path) = icon.strip_prefix("file:") {
+                                    if !path.to_lowercase().ends_with(".png") {
+                                        return Err(format!("icon image {path} is not a PNG image, only PNG images are supported").into());
+                                    }
+                                }
+                                widget.icon = icon.to_string();
                             }
                             "icon_fg" | "icon_foreground" => {
                                 if let Some(next_val) = bar_setting_values.get(ii + 1) {
@@ -316,11 +421,8 @@ impl AllBarSettings {
                             }
                             "command" => {
                                 let mut command_parts = Vec::new();
-                                for command_segment in bar_setting_values[ii..].iter() {
-                                    if command_segment == "icon"
-                                        || command_segment == "update_time"
-                                        || command_segment == "font"
-                                    {
+                                for command_segment in bar_setting_values[ii + 1..].iter() {
+                                    if WIDGET_KEYS.contains(&command_segment.as_str()) {
                                         break;
                                     } else {
                                         command_parts.push(command_segment.clone())
@@ -337,6 +439,37 @@ impl AllBarSettings {
                                     widget.command = widget.command.trim_end().to_string();
                                 }
                             }
+                            "source" => {
+                                let source = bar_setting_values.get(ii + 1).ok_or_else(|| {
+                                    Error::Generic(format!("missing value for {value}"))
+                                })?;
+                                if !WIDGET_SOURCES.contains(&source.as_str()) {
+                                    return Err(format!(
+                                        "{source} is not a correct value for {value}, valid sources are: {}.",
+                                        WIDGET_SOURCES.join(", ")
+                                    )
+                                    .into());
+                                }
+                                widget.source = source.to_string();
+                            }
+                            "show_when" => {
+                                let mut command_parts = Vec::new();
+                                for command_segment in bar_setting_values[ii + 1..].iter() {
+                                    if WIDGET_KEYS.contains(&command_segment.as_str()) {
+                                        break;
+                                    } else {
+                                        command_parts.push(command_segment.clone())
+                                    }
+                                }
+                                if command_parts.is_empty() {
+                                    return Err(format!(
+                                        "the show_when field for widget named {} is empty.",
+                                        widget.id
+                                    )
+                                    .into());
+                                }
+                                widget.show_when = command_parts.join(" ").trim_end().to_string();
+                            }
                             "update_time" => {
                                 let digit = bar_setting_values.get(ii + 1).ok_or_else(|| {
                                     Error::Generic(format!("missing value for {value}"))
@@ -367,6 +500,49 @@ impl AllBarSettings {
                                     })?
                                     .to_string();
                             }
+                            "style" => {
+                                let style = bar_setting_values.get(ii + 1).ok_or_else(|| {
+                                    Error::Generic(format!("missing value for {value}"))
+                                })?;
+                                if !WIDGET_STYLES.contains(&style.as_str()) {
+                                    return Err(format!(
+                                        "{style} is not a correct value for {value}, valid styles are: {}.",
+                                        WIDGET_STYLES.join(", ")
+                                    )
+                                    .into());
+                                }
+                                widget.style = style.to_string();
+                            }
+                            "graph_width" => {
+                                let digit = bar_setting_values.get(ii + 1).ok_or_else(|| {
+                                    Error::Generic(format!("missing value for {value}"))
+                                })?;
+                                widget.graph_width = digit.parse()?;
+                            }
+                            "graph_fg" | "graph_foreground" => {
+                                if let Some(next_val) = bar_setting_values.get(ii + 1) {
+                                    if !next_val.starts_with('#') {
+                                        return Err(format!(
+                                            "{next_val} is not a correct value for {value}"
+                                        )
+                                        .into());
+                                    }
+
+                                    widget.graph_color = next_val.to_string();
+                                }
+                            }
+                            "graph_bg" | "graph_background" => {
+                                if let Some(next_val) = bar_setting_values.get(ii + 1) {
+                                    if !next_val.starts_with('#') {
+                                        return Err(format!(
+                                            "{next_val} is not a correct value for {value}"
+                                        )
+                                        .into());
+                                    }
+
+                                    widget.graph_background_color = next_val.to_string();
+                                }
+                            }
                             _ => (),
                         }
                     }
@@ -465,6 +641,14 @@ impl AllBarSettings {
                                         })?
                                         .to_string();
                                 }
+                                "dnd_indicator" => {
+                                    workspace_segment.dnd_indicator = bar_setting_values
+                                        .get(ii + 1)
+                                        .ok_or_else(|| {
+                                            Error::Generic(format!("{value} is missing a value"))
+                                        })?
+                                        .to_string();
+                                }
                                 _ => (),
                             }
                         }
@@ -548,15 +732,8 @@ impl AllBarSettings {
                 }
                 bar.background_color = val;
             }
-            "location" => {
-                let val = bar_setting_values[0].clone().to_lowercase();
-                if matches!(&val[..], "top") {
-                    bar.location_top = true;
-                } else if matches!(&val[..], "bottom") {
-                    bar.location_top = false;
-                } else {
-                    return Err(format!("'{val}' is not a valid value for the `location` field! Possible values are `top` and `bottom`").into());
-                }
+            "position" | "location" => {
+                bar.position = BarPosition::try_from(bar_setting_values[0].as_str())?
             }
             _ => {
                 return Err(
@@ -568,6 +745,96 @@ impl AllBarSettings {
     }
 }
 
+impl Repr for AllBarSettings {
+    fn repr(&self) -> WmResult<String> {
+        let mut buffer = String::new();
+
+        for bar in self.0.iter() {
+            let id = bar.identifier;
+            writeln!(buffer, "bar_set {id} monitor {}", bar.monitor)?;
+            writeln!(buffer, "bar_set {id} font_size {}", bar.font_size)?;
+            writeln!(buffer, "bar_set {id} height {}", bar.height)?;
+            writeln!(
+                buffer,
+                "bar_set {id} background_color {}",
+                quote(&bar.background_color)
+            )?;
+            writeln!(buffer, "bar_set {id} position {}", bar.position.name())?;
+
+            for segment in bar.segments.iter() {
+                let name = quote(&segment.name);
+                let segment_type = match segment.segment_type {
+                    SegmentSettingsType::Widget(_) => "widget",
+                    SegmentSettingsType::Workspace(_) => "workspace",
+                    SegmentSettingsType::Title(_) => "title",
+                    SegmentSettingsType::IconTray(_) => "icon_tray",
+                };
+                writeln!(
+                    buffer,
+                    "bar_set {id} segment add {segment_type} {name} {}",
+                    segment.position
+                )?;
+
+                match &segment.segment_type {
+                    SegmentSettingsType::Widget(widgets) => {
+                        for widget in widgets.iter() {
+                            write!(
+                                buffer,
+                                "bar_set {id} widget add {name} {} icon {} icon_fg {} value_fg {} separator_fg {} background_color {} update_time {} font {} separator {} format {} style {} graph_width {} graph_fg {} graph_bg {}",
+                                quote(&widget.id),
+                                quote(&widget.icon),
+                                quote(&widget.icon_color),
+                                quote(&widget.value_color),
+                                quote(&widget.separator_color),
+                                quote(&widget.background_color),
+                                widget.update_time,
+                                quote(&widget.font),
+                                quote(&widget.separator),
+                                quote(&widget.format),
+                                widget.style,
+                                widget.graph_width,
+                                quote(&widget.graph_color),
+                                quote(&widget.graph_background_color),
+                            )?;
+                            if !widget.source.is_empty() {
+                                write!(buffer, " source {}", widget.source)?;
+                            }
+                            if !widget.show_when.is_empty() {
+                                write!(buffer, " show_when {}", quote(&widget.show_when))?;
+                            }
+                            if !widget.command.is_empty() {
+                                write!(buffer, " command {}", quote(&widget.command))?;
+                            }
+                            buffer.push('\n');
+                        }
+                    }
+                    SegmentSettingsType::Workspace(workspace) => writeln!(
+                        buffer,
+                        "bar_set {id} workspace set {name} focused_fg {} focused_bg {} normal_fg {} normal_bg {} font {} format {} dnd_indicator {}",
+                        quote(&workspace.focused_foreground_color),
+                        quote(&workspace.focused_background_color),
+                        quote(&workspace.normal_foreground_color),
+                        quote(&workspace.normal_background_color),
+                        quote(&workspace.font),
+                        quote(&workspace.format),
+                        quote(&workspace.dnd_indicator),
+                    )?,
+                    SegmentSettingsType::Title(title) => writeln!(
+                        buffer,
+                        "bar_set {id} title set {name} font {} fg {} bg {}",
+                        quote(&title.font),
+                        quote(&title.foreground_color),
+                        quote(&title.background_color),
+                    )?,
+                    SegmentSettingsType::IconTray(_) => {}
+                }
+            }
+        }
+
+        Ok(buffer)
+    }
+}
+
 impl IntoIterator for AllBarSettings {
     type Item = BarSettings;
 
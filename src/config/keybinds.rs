@@ -6,8 +6,10 @@ use crate::{
 
 use std::fmt::Write;
 
+/// All the keybinds, along with the keybinds from the config file which have been overridden by a
+/// later keybind with the same key combination.
 #[derive(Debug, Clone)]
-pub struct Keybinds(Vec<Keybind>);
+pub struct Keybinds(Vec<Keybind>, Vec<Keybind>);
 
 impl Default for Keybinds {
     fn default() -> Self {
@@ -50,9 +52,13 @@ impl Default for Keybinds {
             ),
             Keybind::new(vec![Key::Mod, Key::KeyS], Action::CycleLayout),
             Keybind::new(vec![Key::Mod, Key::Space], Action::ToggleFloat),
+            Keybind::new(vec![Key::Mod, Key::KeyU], Action::Raise),
+            Keybind::new(vec![Key::Mod, Key::LShift, Key::KeyU], Action::Lower),
+            Keybind::new(vec![Key::Mod, Key::KeyG], Action::ToggleGaps),
             Keybind::new(vec![Key::Mod, Key::LShift, Key::KeyR], Action::ReloadConfig),
+            Keybind::new(vec![Key::Esc], Action::Abort),
         ];
-        Self(default_binds)
+        Self(default_binds, Vec::new())
     }
 }
 
@@ -61,22 +67,12 @@ impl Repr for Keybinds {
         let mut return_string = String::new();
 
         for keybind in self.0.iter() {
-            return_string.push_str("keybind ");
-            for (ii, key) in keybind.keys.iter().enumerate() {
-                if ii == 0 {
-                    return_string.push('"');
-                }
-
-                if key.is_special() {
-                    write!(return_string, "<{}>", key.get_x11_str())?;
-                } else {
-                    return_string.push_str(key.get_x11_str())
-                }
-            }
-
-            return_string.push('"');
-
-            write!(return_string, " {}", keybind.action.repr()?)?;
+            write!(
+                return_string,
+                "keybind \"{}\" {}",
+                keybind.keys_repr()?,
+                keybind.actions_repr()?
+            )?;
             return_string.push('\n');
         }
 
@@ -86,34 +82,71 @@ impl Repr for Keybinds {
 
 impl Keybinds {
     /// Add a new keybind.
-    pub fn add(&mut self, keys: String, action: String) -> WmResult {
-        let keybind = Keybind::from(keys, action)?;
-        let mut remove_index = None;
-
-        for (i, in_keybind) in self.0.iter().enumerate() {
-            if in_keybind.action == keybind.action || in_keybind.keys == keybind.keys {
-                remove_index = Some(i)
+    ///
+    /// A keybind with the same key combination replaces the existing one. If the replaced keybind
+    /// also comes from the config file, it is remembered, so that it can be reported by
+    /// [`Keybinds::duplicates`].
+    pub fn add(&mut self, keys: String, actions: Vec<String>) -> WmResult {
+        let keybind = Keybind::from(keys, actions)?;
+
+        if let Some(index) = self.0.iter().position(|k| k.same_keys(&keybind)) {
+            let replaced = self.0.remove(index);
+            if replaced.from_config {
+                self.1.push(replaced);
             }
         }
 
-        if let Some(to_remove) = remove_index {
-            self.0.remove(to_remove);
-        }
+        self.0.push(keybind);
+        Ok(())
+    }
 
+    /// Add a keybind, silently replacing a keybind with the same keys. Used for keybinds from a
+    /// config profile, which are meant to replace the keybinds of the rest of the config.
+    pub fn replace(&mut self, keys: String, actions: Vec<String>) -> WmResult {
+        let keybind = Keybind::from(keys, actions)?;
+        self.0.retain(|k| !k.same_keys(&keybind));
         self.0.push(keybind);
+
         Ok(())
     }
 
+    /// Describe every key combination which is bound more than once in the config file.
+    pub fn duplicates(&self) -> WmResult<Vec<String>> {
+        let mut ret = Vec::new();
+        for shadowed in &self.1 {
+            if let Some(keybind) = self.0.iter().find(|k| k.same_keys(shadowed)) {
+                ret.push(format!(
+                    "keybind \"{}\": \"{}\" is overridden by \"{}\"",
+                    shadowed.keys_repr()?,
+                    shadowed.actions_repr()?,
+                    keybind.actions_repr()?
+                ))
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// Return the number of keybinds.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Are there no keybinds?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn _extend(&mut self, from: Vec<Keybind>) {
         self.0.extend(from)
     }
 
-    /// Get the X11 keysym names and action associated with the keybind.
-    pub fn get_names_and_actions(&self) -> Vec<(Vec<&'_ str>, Action)> {
+    /// Get the X11 keysym names and actions associated with the keybind.
+    pub fn get_names_and_actions(&self) -> Vec<(Vec<&'_ str>, Vec<Action>)> {
         let mut ret = Vec::with_capacity(self.0.len());
         for each in &self.0 {
             let names: Vec<&'_ str> = each.keys.iter().map(|k| k.get_x11_str()).collect();
-            ret.push((names, each.action.clone()))
+            ret.push((names, each.actions.clone()))
         }
 
         ret
@@ -242,16 +275,6 @@ pub enum Key {
 }
 
 impl Key {
-    fn from_vec(input: &Vec<String>) -> WmResult<Vec<Self>> {
-        let mut ret = Vec::new();
-        for each in input {
-            let parsed = Key::from_key_str(each.as_ref())?;
-            ret.push(parsed);
-        }
-
-        Ok(ret)
-    }
-
     fn is_special(&self) -> bool {
         if self.get_x11_str().len() > 1 {
             return true;
@@ -486,62 +509,110 @@ impl Key {
 #[allow(unused)]
 pub struct Keybind {
     keys: Vec<Key>,
-    action: Action,
+    /// Actions which are run, in order, when the keybind is pressed.
+    actions: Vec<Action>,
+    /// Whether the keybind comes from the config file rather than the defaults.
+    from_config: bool,
 }
 
 impl Keybind {
     fn new(keys: Vec<Key>, action: Action) -> Self {
-        Self { keys, action }
+        Self {
+            keys,
+            actions: vec![action],
+            from_config: false,
+        }
     }
-    fn from(str_keys: String, str_action: String) -> WmResult<Self> {
+    fn from(str_keys: String, str_actions: Vec<String>) -> WmResult<Self> {
         let keys = Keybind::parse_keys(str_keys)?;
-        let action = Keybind::parse_action(str_action)?;
+        let actions = str_actions
+            .into_iter()
+            .map(Keybind::parse_action)
+            .collect::<WmResult<Vec<Action>>>()?;
+
+        Ok(Self {
+            keys,
+            actions,
+            from_config: true,
+        })
+    }
+
+    /// Do both keybinds use the same key combination, regardless of the order of the keys?
+    fn same_keys(&self, other: &Self) -> bool {
+        self.keys.len() == other.keys.len() && self.keys.iter().all(|k| other.keys.contains(k))
+    }
+
+    /// Return the actions the way they are written in a config file, separated by commas.
+    fn actions_repr(&self) -> WmResult<String> {
+        Ok(self
+            .actions
+            .iter()
+            .map(|a| a.repr())
+            .collect::<WmResult<Vec<String>>>()?
+            .join(", "))
+    }
+
+    /// Return the key combination the way it is written in a config file.
+    fn keys_repr(&self) -> WmResult<String> {
+        let mut ret = String::new();
+        for key in self.keys.iter() {
+            if key.is_special() {
+                write!(ret, "<{}>", key.get_x11_str())?;
+            } else {
+                ret.push_str(key.get_x11_str())
+            }
+        }
 
-        Ok(Self { keys, action })
+        Ok(ret)
     }
 
+    /// Parse a string of keys, such as `<Mod-Shift>k` or `<Ctrl><Alt>t`.
+    ///
+    /// Every character outside of angled brackets is a single key. Everything inside of a
+    /// `<...>` block is a list of key names, separated by `-`.
     fn parse_keys(input_keys: String) -> WmResult<Vec<Key>> {
         let mut ret = Vec::new();
-        let mut special: Vec<String> = Vec::new();
-        let mut is_special = false;
-        let mut current_char;
-        let mut keys = input_keys.chars().rev().collect::<String>();
-
-        while !keys.is_empty() {
-            current_char = keys.pop().unwrap();
-            // if we are parsing a '<' block
-            if current_char == '<' {
-                if is_special {
-                    return Err(format!("key parsing error: when parsing {input_keys}, invalid character {current_char}").into());
-                } else {
-                    is_special = true
-                }
-            } else if is_special {
-                // if we are in a '<' block and the current character is not '-' or '>'
-                if special.is_empty() && (current_char != '-' || current_char != '>') {
-                    // push a new string to the special vector and add add the character in
-                    special.push(String::from(current_char))
-                } else if special.is_empty() && (current_char == '>') {
-                    is_special = false;
-                } else if !special.is_empty() {
-                    if current_char == '-' {
-                        special.push(String::new())
-                    } else if current_char == '>' {
-                        // parse the current vec of keys, return from special mode and clear
-                        // vector of special keys
-                        let mut parsed_special = Key::from_vec(&special)?;
-                        ret.append(&mut parsed_special);
-                        special.clear();
-                        is_special = false
-                    } else if let Some(last) = special.last_mut() {
-                        last.push(current_char)
+        let mut chars = input_keys.chars();
+
+        while let Some(current_char) = chars.next() {
+            match current_char {
+                '<' => {
+                    let mut block = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('>') => break,
+                            Some('<') => {
+                                return Err(format!("key parsing error: when parsing {input_keys}, found '<' inside of a '<...>' block").into())
+                            }
+                            Some(c) => block.push(c),
+                            None => {
+                                return Err(format!("key parsing error: when parsing {input_keys}, '<{block}' is missing a closing '>'").into())
+                            }
+                        }
+                    }
+
+                    if block.is_empty() {
+                        return Err(format!("key parsing error: when parsing {input_keys}, found an empty '<>' block").into());
+                    }
+
+                    for name in block.split('-') {
+                        if name.is_empty() {
+                            return Err(format!("key parsing error: when parsing {input_keys}, '<{block}>' contains an empty key name").into());
+                        }
+                        ret.push(Key::from_key_str(name)?)
                     }
                 }
-            } else {
-                let key = Key::from_key_str(&current_char.to_string())?;
-                ret.push(key)
+                '>' => {
+                    return Err(format!("key parsing error: when parsing {input_keys}, found '>' without a matching '<'").into())
+                }
+                c => ret.push(Key::from_key_str(&c.to_string())?),
             }
         }
+
+        if ret.is_empty() {
+            return Err(format!("key parsing error: no keys found in \"{input_keys}\"").into());
+        }
+
         Ok(ret)
     }
 
@@ -554,6 +625,7 @@ impl Keybind {
 mod tests {
     use crate::config::Repr;
 
+    use super::Action;
     use super::Key;
     use super::Keybind;
     use super::Keybinds;
@@ -567,6 +639,34 @@ mod tests {
         println!("{:#?}", keys.unwrap())
     }
 
+    #[test]
+    fn test_special_block_parsing() {
+        let keys = Keybind::parse_keys("<Mod-k>".to_string()).unwrap();
+        assert_eq!(keys, vec![Key::Mod, Key::KeyK]);
+
+        let keys = Keybind::parse_keys("<Mod-Shift-k>".to_string()).unwrap();
+        assert_eq!(keys, vec![Key::Mod, Key::LShift, Key::KeyK]);
+
+        let keys = Keybind::parse_keys("<Ctrl-Alt-Delete>".to_string()).unwrap();
+        assert_eq!(keys, vec![Key::Ctrl, Key::Alt, Key::Delete]);
+
+        let keys = Keybind::parse_keys("<Mod><Shift>k".to_string()).unwrap();
+        assert_eq!(keys, vec![Key::Mod, Key::LShift, Key::KeyK]);
+    }
+
+    #[test]
+    fn test_single_key_parsing() {
+        let keys = Keybind::parse_keys("abc".to_string()).unwrap();
+        assert_eq!(keys, vec![Key::KeyA, Key::KeyB, Key::KeyC]);
+    }
+
+    #[test]
+    fn test_malformed_key_parsing() {
+        for input in ["", "<Mod", "Mod>k", "<>", "<Mod->", "<Mod<Shift>>"] {
+            assert!(Keybind::parse_keys(input.to_string()).is_err(), "{input}");
+        }
+    }
+
     #[test]
     fn test_key_from_codes() {
         use crate::config::keysyms::Keysym;
@@ -580,6 +680,55 @@ mod tests {
         assert_eq!(Key::Key8, key);
     }
 
+    #[test]
+    fn test_same_action_different_keys() {
+        let mut keybinds = Keybinds::default();
+        let len = keybinds.len();
+        keybinds
+            .add("<Mod>t".to_string(), vec!["execute firefox".to_string()])
+            .unwrap();
+        keybinds
+            .add("<Mod>y".to_string(), vec!["execute firefox".to_string()])
+            .unwrap();
+
+        assert_eq!(keybinds.len(), len + 2);
+        assert!(keybinds.duplicates().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_same_keys_different_action() {
+        let mut keybinds = Keybinds::default();
+        let len = keybinds.len();
+        keybinds
+            .add("<Mod>t".to_string(), vec!["execute firefox".to_string()])
+            .unwrap();
+        keybinds
+            .add("<Mod>t".to_string(), vec!["kill".to_string()])
+            .unwrap();
+
+        assert_eq!(keybinds.len(), len + 1);
+        assert_eq!(
+            keybinds.get_names_and_actions().last().unwrap().1,
+            vec![Action::Kill]
+        );
+        assert_eq!(keybinds.duplicates().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_multiple_actions() {
+        let keybind = Keybind::from(
+            "<Mod>t".to_string(),
+            vec!["goto 2".to_string(), "execute firefox".to_string()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            keybind.actions,
+            vec![Action::Goto(2), Action::Execute("firefox".to_string())]
+        );
+        assert_eq!(keybind.actions_repr().unwrap(), "goto 2, execute firefox");
+    }
+
     #[test]
     fn test_repr() {
         let keybinds = Keybinds::default();
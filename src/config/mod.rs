@@ -1,6 +1,8 @@
 pub mod bar_settings;
 pub mod keybinds;
 pub mod keysyms;
+pub mod monitor_settings;
+pub mod rules;
 pub mod settings;
 pub mod start_hooks;
 pub mod workspace_settings;
@@ -8,6 +10,8 @@ pub mod workspace_settings;
 use crate::errors::WmResult;
 pub use bar_settings::*;
 pub use keybinds::*;
+pub use monitor_settings::*;
+use rules::*;
 use settings::*;
 use start_hooks::*;
 use workspace_settings::*;
@@ -22,20 +26,72 @@ pub struct Config {
     pub start_hooks: StartHooks,
     pub workspace_settings: AllWorkspaceSettings,
     pub bar_settings: AllBarSettings,
+    pub monitor_settings: AllMonitorSettings,
+    pub rules: Rules,
     pub path: String,
+    /// The config profile whose lines were applied on top of the rest of the config file, if
+    /// any.
+    pub profile: Option<String>,
+    /// Names of the settings which were explicitly set in the config file.
+    pub explicit_settings: Vec<String>,
+    /// Problems found while loading the config file which didn't stop it from being loaded.
+    pub warnings: Vec<String>,
 }
 
 impl Config {
-    pub fn serialize(&self) -> WmResult<&[u8]> {
+    /// Write the config out in the config file format, so that parsing it results in the same
+    /// config.
+    pub fn serialize(&self) -> WmResult<Vec<u8>> {
+        let sections = [
+            ("Keybinds", self.keybinds.repr()?),
+            ("Settings", self.settings.repr()?),
+            ("Hooks", self.start_hooks.repr()?),
+            ("Workspace settings", self.workspace_settings.repr()?),
+            ("Bar settings", self.bar_settings.repr()?),
+            ("Monitor settings", self.monitor_settings.repr()?),
+            ("Rules", self.rules.repr()?),
+        ];
+
         let mut string = String::new();
+        for (title, section) in sections.iter().filter(|(_, s)| !s.is_empty()) {
+            string.push_str(&format!("# {title}\n{section}\n"));
+        }
+
+        Ok(string.into_bytes())
+    }
 
-        string.push_str(&self.keybinds.repr()?);
-        string.push_str(&self.settings.repr()?);
+    /// Summarize what has been loaded from the config file.
+    ///
+    /// This includes the number of keybinds, workspaces, bars, hooks and rules, as well as a list of the
+    /// settings which were not set in the config file and fell back to their default values.
+    pub fn summary(&self) -> String {
+        let defaulted: Vec<&str> = Settings::NAMES
+            .iter()
+            .filter(|name| !self.explicit_settings.iter().any(|e| e == *name))
+            .copied()
+            .collect();
 
-        Ok(&[])
+        format!(
+            "{} keybinds, {} workspaces, {} bars, {} hooks, {} rules; settings using default values: {}",
+            self.keybinds.len(),
+            self.workspace_settings.len(),
+            self.bar_settings.len(),
+            self.start_hooks.len(),
+            self.rules.len(),
+            if defaulted.is_empty() {
+                "none".to_string()
+            } else {
+                defaulted.join(", ")
+            }
+        )
     }
 }
 
 pub trait Repr {
     fn repr(&self) -> WmResult<String>;
 }
+
+/// Quote a value for the config file, escaping any quotes and backslashes inside of it.
+fn quote(value: &str) -> String {
+    format!("{value:?}")
+}
@@ -0,0 +1,123 @@
+use crate::errors::{Error, WmResult};
+
+use super::{quote, Repr};
+
+use std::fmt::Write;
+
+/// Space left empty on each edge of a monitor, for example where a TV's overscan cuts off the
+/// picture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MonitorPadding {
+    pub top: u16,
+    pub bottom: u16,
+    pub left: u16,
+    pub right: u16,
+}
+
+/// Settings of a single monitor, identified by the name RandR reports for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MonitorSettings {
+    /// Name of the monitor, for example "HDMI-1".
+    pub name: String,
+    /// Padding taken away from the area the workspaces on the monitor can use.
+    pub padding: MonitorPadding,
+}
+
+impl MonitorSettings {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            padding: MonitorPadding::default(),
+        }
+    }
+}
+
+/// Settings of all the monitors mentioned in the config file.
+#[derive(Clone, Debug, Default)]
+pub struct AllMonitorSettings(Vec<MonitorSettings>);
+
+impl AllMonitorSettings {
+    /// Return the number of configured monitors.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Are there no configured monitors?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Return the settings of the monitor with the given name.
+    pub fn get(&self, name: &str) -> Option<&MonitorSettings> {
+        self.0.iter().find(|m| m.name == name)
+    }
+
+    pub fn add(&mut self, name: String, setting: String, values: Vec<String>) -> WmResult {
+        let monitor = match self.0.iter().position(|m| m.name == name) {
+            Some(index) => &mut self.0[index],
+            None => {
+                self.0.push(MonitorSettings::new(name));
+                self.0.last_mut().unwrap()
+            }
+        };
+
+        match &setting[..] {
+            "padding" => {
+                if values.len() != 4 {
+                    return Err(format!(
+                        "monitor setting parsing error: padding takes four numbers of pixels, top, bottom, left and right, found {}.",
+                        values.len()
+                    )
+                    .into());
+                }
+                let values = values
+                    .iter()
+                    .map(|value| value.parse::<u16>())
+                    .collect::<Result<Vec<u16>, _>>()?;
+                monitor.padding = MonitorPadding {
+                    top: values[0],
+                    bottom: values[1],
+                    left: values[2],
+                    right: values[3],
+                };
+            }
+            _ => {
+                return Err(Error::Generic(format!(
+                    "monitor setting parsing error: setting {setting} does not exist!"
+                )))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Repr for AllMonitorSettings {
+    fn repr(&self) -> WmResult<String> {
+        let mut buffer = String::new();
+
+        for monitor in self.0.iter() {
+            let p = monitor.padding;
+            writeln!(
+                buffer,
+                "monitor_set {} padding {} {} {} {}",
+                quote(&monitor.name),
+                p.top,
+                p.bottom,
+                p.left,
+                p.right
+            )?;
+        }
+
+        Ok(buffer)
+    }
+}
+
+impl IntoIterator for AllMonitorSettings {
+    type Item = MonitorSettings;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
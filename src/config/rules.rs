@@ -0,0 +1,361 @@
+use crate::{
+    errors::{Error, WmResult},
+    wm::geometry::Geometry,
+};
+
+use super::{quote, Repr};
+
+use std::fmt::Write;
+
+/// What part of a window a rule is matched against, either a part of its `WM_CLASS` property or
+/// its title.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleField {
+    Class,
+    Instance,
+    Title,
+}
+
+impl TryFrom<String> for RuleField {
+    type Error = Error;
+
+    fn try_from(value: String) -> WmResult<Self> {
+        match value.to_lowercase().as_str() {
+            "class" => Ok(Self::Class),
+            "instance" => Ok(Self::Instance),
+            "title" => Ok(Self::Title),
+            _ => Err(format!("rule parsing error: unable to match windows by {value}").into()),
+        }
+    }
+}
+
+/// A single window rule, applied to new windows whose class or instance is exactly `value`, or
+/// whose title contains `value`.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pub field: RuleField,
+    pub value: String,
+    /// Workspace the window is opened on, instead of the one under the cursor.
+    pub workspace: Option<u32>,
+    /// Whether the window is opened as a floating window.
+    pub float: Option<bool>,
+    /// Position and size of the window, which makes it a floating window.
+    pub geometry: Option<Geometry>,
+    /// Whether the window is left unfocused when it opens.
+    pub no_focus: Option<bool>,
+    /// Whether the window is kept out of the layout, making it a floating window.
+    pub no_tile: Option<bool>,
+}
+
+impl Rule {
+    fn new(field: String, value: String, effects: Vec<String>) -> WmResult<Self> {
+        let mut rule = Self {
+            field: field.try_into()?,
+            value,
+            workspace: None,
+            float: None,
+            geometry: None,
+            no_focus: None,
+            no_tile: None,
+        };
+
+        if effects.is_empty() {
+            return Err("rule parsing error: a rule has to have at least one effect".into());
+        }
+
+        for effect in effects.chunks(2) {
+            match effect {
+                [name, value] => match name.to_lowercase().as_str() {
+                    "workspace" => rule.workspace = Some(value.parse::<u32>()?),
+                    "float" => rule.float = Some(value.to_lowercase().parse::<bool>()?),
+                    "geometry" => rule.geometry = Some(parse_geometry(value)?),
+                    "no_focus" => rule.no_focus = Some(value.to_lowercase().parse::<bool>()?),
+                    "no_tile" => rule.no_tile = Some(value.to_lowercase().parse::<bool>()?),
+                    _ => {
+                        return Err(format!("rule parsing error: unknown rule effect {name}").into())
+                    }
+                },
+                _ => {
+                    return Err(format!(
+                        "rule parsing error: rule effect {} has no value",
+                        effect[0]
+                    )
+                    .into())
+                }
+            }
+        }
+
+        Ok(rule)
+    }
+
+    /// Does the rule apply to a window with this instance, class and title?
+    pub fn matches(
+        &self,
+        instance: Option<&str>,
+        class: Option<&str>,
+        title: Option<&str>,
+    ) -> bool {
+        match self.field {
+            RuleField::Class => class == Some(self.value.as_str()),
+            RuleField::Instance => instance == Some(self.value.as_str()),
+            RuleField::Title => title.is_some_and(|t| t.contains(self.value.as_str())),
+        }
+    }
+}
+
+/// Parse a geometry written as `<width>x<height>+<x>+<y>`, where the position can be negative.
+fn parse_geometry(value: &str) -> WmResult<Geometry> {
+    let invalid = || {
+        Error::Generic(format!(
+            "rule parsing error: {value} is not a valid geometry, expected <width>x<height>+<x>+<y>"
+        ))
+    };
+
+    let (width, rest) = value.split_once('x').ok_or_else(invalid)?;
+    let position_start = rest.find(['+', '-']).ok_or_else(invalid)?;
+    let (height, position) = rest.split_at(position_start);
+    let y_start = position[1..].find(['+', '-']).ok_or_else(invalid)? + 1;
+    let (x, y) = position.split_at(y_start);
+
+    let geometry = Geometry {
+        x: x.parse().map_err(|_| invalid())?,
+        y: y.parse().map_err(|_| invalid())?,
+        width: width.parse().map_err(|_| invalid())?,
+        height: height.parse().map_err(|_| invalid())?,
+    };
+    if geometry.width == 0 || geometry.height == 0 {
+        return Err(invalid());
+    }
+
+    Ok(geometry)
+}
+
+/// What the matching rules want to happen to a new window.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RuleEffects {
+    pub workspace: Option<u32>,
+    pub float: Option<bool>,
+    pub geometry: Option<Geometry>,
+    pub no_focus: Option<bool>,
+    pub no_tile: Option<bool>,
+}
+
+impl RuleEffects {
+    /// Should the window be focused when it opens?
+    pub fn takes_focus(&self) -> bool {
+        !self.no_focus.unwrap_or(false)
+    }
+
+    /// Should the window be floating, tiled, or should the workspace decide?
+    pub fn floating(&self) -> Option<bool> {
+        if self.no_tile == Some(true) {
+            return Some(true);
+        }
+
+        self.float.or(self.geometry.map(|_| true))
+    }
+}
+
+/// All the window rules, in the order they appear in the config file.
+#[derive(Clone, Debug, Default)]
+pub struct Rules(Vec<Rule>);
+
+impl Rules {
+    pub fn add(&mut self, field: String, value: String, effects: Vec<String>) -> WmResult {
+        self.0.push(Rule::new(field, value, effects)?);
+
+        Ok(())
+    }
+
+    /// Return the number of rules.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Are there no rules?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Combine the effects of all the rules matching a window, later rules taking precedence.
+    pub fn effects_for(
+        &self,
+        instance: Option<&str>,
+        class: Option<&str>,
+        title: Option<&str>,
+    ) -> RuleEffects {
+        let mut effects = RuleEffects::default();
+
+        for rule in self.0.iter().filter(|r| r.matches(instance, class, title)) {
+            effects.workspace = rule.workspace.or(effects.workspace);
+            effects.float = rule.float.or(effects.float);
+            effects.geometry = rule.geometry.or(effects.geometry);
+            effects.no_focus = rule.no_focus.or(effects.no_focus);
+            effects.no_tile = rule.no_tile.or(effects.no_tile);
+        }
+
+        effects
+    }
+}
+
+impl Repr for Rules {
+    fn repr(&self) -> WmResult<String> {
+        let mut buffer = String::new();
+
+        for rule in self.0.iter() {
+            let field = match rule.field {
+                RuleField::Class => "class",
+                RuleField::Instance => "instance",
+                RuleField::Title => "title",
+            };
+            write!(buffer, "rule {field} {}", quote(&rule.value))?;
+            if let Some(workspace) = rule.workspace {
+                write!(buffer, " workspace {workspace}")?;
+            }
+            if let Some(float) = rule.float {
+                write!(buffer, " float {float}")?;
+            }
+            if let Some(no_focus) = rule.no_focus {
+                write!(buffer, " no_focus {no_focus}")?;
+            }
+            if let Some(no_tile) = rule.no_tile {
+                write!(buffer, " no_tile {no_tile}")?;
+            }
+            if let Some(g) = rule.geometry {
+                write!(
+                    buffer,
+                    " geometry {}x{}{:+}{:+}",
+                    g.width, g.height, g.x, g.y
+                )?;
+            }
+            buffer.push('\n');
+        }
+
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(lines: &[(&str, &str, &[&str])]) -> Rules {
+        let mut rules = Rules::default();
+        for (field, value, effects) in lines {
+            rules
+                .add(
+                    field.to_string(),
+                    value.to_string(),
+                    effects.iter().map(|e| e.to_string()).collect(),
+                )
+                .unwrap();
+        }
+        rules
+    }
+
+    #[test]
+    fn effects_for() {
+        let rules = rules(&[
+            ("class", "firefox", &["workspace", "2"]),
+            ("instance", "discord", &["workspace", "9", "float", "true"]),
+            ("class", "firefox", &["float", "false"]),
+        ]);
+
+        assert_eq!(
+            rules.effects_for(Some("Navigator"), Some("firefox"), None),
+            RuleEffects {
+                workspace: Some(2),
+                float: Some(false),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            rules.effects_for(Some("discord"), Some("Discord"), None),
+            RuleEffects {
+                workspace: Some(9),
+                float: Some(true),
+                ..Default::default()
+            }
+        );
+        // Matching is exact, and windows without a match are left alone.
+        assert_eq!(
+            rules.effects_for(None, Some("Firefox"), None),
+            RuleEffects::default()
+        );
+    }
+
+    #[test]
+    fn title_and_geometry() {
+        let rules = rules(&[(
+            "title",
+            "Volume Control",
+            &["float", "true", "geometry", "800x600+100-20"],
+        )]);
+        let effects = RuleEffects {
+            float: Some(true),
+            geometry: Some(Geometry {
+                x: 100,
+                y: -20,
+                width: 800,
+                height: 600,
+            }),
+            ..Default::default()
+        };
+
+        // Titles only have to contain the value.
+        assert_eq!(
+            rules.effects_for(None, Some("Pavucontrol"), Some("Volume Control - Output")),
+            effects
+        );
+        assert_eq!(
+            rules.effects_for(None, Some("Pavucontrol"), Some("volume control")),
+            RuleEffects::default()
+        );
+    }
+
+    #[test]
+    fn no_focus_and_no_tile() {
+        let rules = rules(&[
+            ("class", "Steam", &["no_focus", "true", "float", "false"]),
+            ("class", "Steam", &["no_tile", "true"]),
+            ("class", "mpv", &["no_focus", "true"]),
+            ("class", "mpv", &["no_focus", "false"]),
+        ]);
+
+        let steam = rules.effects_for(None, Some("Steam"), None);
+        assert!(!steam.takes_focus());
+        // no_tile wins over a float rule asking for the window to be tiled.
+        assert_eq!(steam.floating(), Some(true));
+
+        let mpv = rules.effects_for(None, Some("mpv"), None);
+        assert!(mpv.takes_focus());
+        assert_eq!(mpv.floating(), None);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let mut rules = Rules::default();
+        let add = |rules: &mut Rules, field: &str, effects: &[&str]| {
+            rules.add(
+                field.to_string(),
+                "firefox".to_string(),
+                effects.iter().map(|e| e.to_string()).collect(),
+            )
+        };
+
+        assert!(add(&mut rules, "role", &["workspace", "2"]).is_err());
+        assert!(add(&mut rules, "class", &["workspace"]).is_err());
+        assert!(add(&mut rules, "class", &["opacity", "1"]).is_err());
+        assert!(add(&mut rules, "class", &["float", "maybe"]).is_err());
+        for geometry in [
+            "800x600",
+            "800x600+1",
+            "0x600+0+0",
+            "800+600+0+0",
+            "ax600+0+0",
+        ] {
+            assert!(add(&mut rules, "class", &["geometry", geometry]).is_err());
+        }
+        assert!(rules.is_empty());
+    }
+}
@@ -1,5 +1,8 @@
-use crate::config::Repr;
+use crate::config::{quote, Repr};
 use crate::errors::WmResult;
+use crate::log::DEFAULT_LOG_FORMAT;
+
+use std::fmt::Write;
 
 #[derive(Debug, Clone)]
 pub struct Settings {
@@ -21,6 +24,20 @@ pub struct Settings {
     /// Default: #000000(full black)
     pub border_color: String,
 
+    /// Size, in pixels, of the inner part of window borders.
+    ///
+    /// The inner border is drawn inside the border, right next to the window, using
+    /// `inner_border_color`, while the rest of the border keeps `border_color`. The value is
+    /// capped at `border_size`. If the value is 0, the border is drawn with a single color.
+    ///
+    /// Default: 0
+    pub inner_border_size: u32,
+
+    /// A hexadecimal RGB representation of the inner window border color.
+    ///
+    /// Default: #000000(full black)
+    pub inner_border_color: String,
+
     /// The display name to use when connecting to a X11 server.
     ///
     /// Default is an empty string, which tells the WM to use the value from the DISPLAY environmental
@@ -46,9 +63,221 @@ pub struct Settings {
     pub gap_left_size: u32,
     pub gap_right_size: u32,
 
+    /// Size, in pixels, of the gap between the tiled windows and the edges of the screen.
+    ///
+    /// Toggled along with the other gaps by the `toggle_gaps` action.
+    ///
+    /// Default: 0
+    pub outer_gap_size: u32,
+
+    /// Which side of the layout should new windows be placed on?
+    ///
+    /// "right": new windows are appended after all the other windows, so the layout grows to the
+    /// right(or downwards).
+    /// "left": new windows are inserted before all the other windows, taking the leftmost(or
+    /// topmost) slot.
+    ///
+    /// Default: "right"
+    pub tiling_direction: String,
+
+    /// Where should new windows be placed relative to the focused window?
+    ///
+    /// "before": right before the focused window.
+    /// "after": right after the focused window.
+    /// "none": use `tiling_direction` instead.
+    ///
+    /// Default: "none"
+    pub spawn_side: String,
+
+    /// Should a floating window which gets tiled again return to the position among the tiled
+    /// windows it had before it was floated? Otherwise it is tiled wherever it currently is in the
+    /// window list.
+    ///
+    /// Default: true
+    pub keep_tiled_slot: bool,
+
+    /// How the focus follows the pointer and the other way around.
+    ///
+    /// "sloppy": the window under the pointer gets focused when the pointer enters it, focusing a
+    /// window using the keyboard leaves the pointer where it is.
+    /// "click": a window only gets focused by clicking it, focusing a window using the keyboard
+    /// leaves the pointer where it is.
+    /// "pointer-follows-focus": like "sloppy", but focusing a window or a workspace using the
+    /// keyboard also moves the pointer to the middle of it.
+    ///
+    /// Default: "pointer-follows-focus"
+    pub focus_model: String,
+
+    /// Should windows spawned from a terminal swallow the terminal?
+    ///
+    /// If enabled, a new window whose process is a descendant of the process of a tiled window in
+    /// the same workspace takes that window's place in the layout. The original window is hidden
+    /// until the new window is closed.
+    ///
+    /// Default: false
+    pub swallow: bool,
+
+    /// Should the layout be applied once after a burst of windows is opened or closed, instead of
+    /// once for every window?
+    ///
+    /// Default: false
+    pub coalesce_layouts: bool,
+
+    /// Should the windows of a workspace slide off the screen when another workspace is shown in
+    /// its place?
+    ///
+    /// Default: false
+    pub animations: bool,
+
+    /// How long, in milliseconds, sliding the windows of a workspace off the screen takes. It's
+    /// capped at `MAX_ANIMATION_DURATION`, 500.
+    ///
+    /// Default: 120
+    pub animation_duration: u32,
+
+    /// A hexadecimal RGB representation of the color which the border of the focused window
+    /// flashes with when a bell rings, for example when a terminal application beeps.
+    ///
+    /// Default: empty, the visual bell is off
+    pub visual_bell: String,
+
+    /// How long, in milliseconds, the border of the focused window keeps the `visual_bell` color.
+    ///
+    /// Default: 150
+    pub visual_bell_duration: u32,
+
+    /// The terminal started by the `spawn_float_terminal` action, passed to `/bin/bash -c`.
+    ///
+    /// Default: xterm
+    pub terminal: String,
+
+    /// Width of the floating terminal started by the `spawn_float_terminal` action, in pixels.
+    ///
+    /// Default: 800
+    pub float_terminal_width: u32,
+
+    /// Height of the floating terminal started by the `spawn_float_terminal` action, in pixels.
+    ///
+    /// Default: 450
+    pub float_terminal_height: u32,
+
+    /// Should the floating terminal started by the `spawn_float_terminal` action be centered at
+    /// the pointer, instead of on the monitor the pointer is on?
+    ///
+    /// Default: true
+    pub float_terminal_at_cursor: bool,
+
+    /// Should a lone window fill the whole screen in the master-stack layout?
+    ///
+    /// If disabled, the window only takes the master's part of the screen, so that it doesn't
+    /// move once a second window is opened.
+    ///
+    /// Default: true
+    pub master_fill_when_alone: bool,
+
+    /// Should every monitor have its own set of workspaces?
+    ///
+    /// If enabled, `goto N` and `move N` refer to the Nth workspace of the focused monitor,
+    /// instead of the workspace with the identifier N.
+    ///
+    /// Default: false
+    pub per_monitor_workspaces: bool,
+
+    /// Should new windows be opened on the workspace where a window with the same WM_CLASS was
+    /// last seen, instead of the workspace under the cursor?
+    ///
+    /// Default: false
+    pub remember_workspace: bool,
+
+    /// The workspace onto which windows are moved while they are in the fullscreen state, 0
+    /// disables this.
+    ///
+    /// Default: 0
+    pub fullscreen_workspace: u32,
+
+    /// A comma separated list of window classes which are moved onto the `fullscreen_workspace`,
+    /// all windows are moved if it's empty.
+    ///
+    /// Default: ""
+    pub fullscreen_workspace_classes: String,
+
+    /// Should the bar stay visible above fullscreen windows on its monitor?
+    ///
+    /// Default: false
+    pub bar_on_top_of_fullscreen: bool,
+
+    /// A comma separated list of keyboard layouts, as understood by `setxkbmap -layout`, which the
+    /// `switch_layout` action cycles through.
+    ///
+    /// Default: ""
+    pub keyboard_layouts: String,
+
+    /// A comma separated list of the monitors the window manager manages, either by their RandR
+    /// names or by their indices, counting from 0. Workspaces are only created on these monitors
+    /// and windows on the other monitors are left alone. An empty list manages all the monitors.
+    ///
+    /// Default: ""
+    pub manage_monitors: String,
+
+    /// Where to write the state of the workspaces and the focused window's title for external
+    /// bars, as a line of JSON on every change.
+    /// Either "STDOUT", a path to a file or a fifo, or an empty string to disable the output.
+    ///
+    /// Default: ""
+    pub bar_output: String,
+
+    /// Width of windows pinned to a corner of the screen with the `pin` action.
+    ///
+    /// Default: 480
+    pub pin_width: u32,
+    /// Height of windows pinned to a corner of the screen with the `pin` action.
+    ///
+    /// Default: 270
+    pub pin_height: u32,
+
+    /// How long, in milliseconds, the `kill_unresponsive` action waits for a window to close
+    /// before each escalation step.
+    ///
+    /// Default: 3000
+    pub kill_timeout: u32,
+    /// How often, in milliseconds, the status bars are redrawn to refresh their widgets.
+    ///
+    /// Default: 1000
+    pub bar_update_interval: u32,
+
+    /// What to do when handling an event fails.
+    ///
+    /// "continue": log the error and keep going.
+    /// "log-verbose": log the error along with the event which caused it and keep going.
+    /// "abort-on-repeated": log the error, but exit if the same error occurs `error_repeat_limit`
+    /// times within a few seconds.
+    ///
+    /// Default: "continue"
+    pub error_policy: String,
+    /// How many times the same error has to occur in a short time for the "abort-on-repeated"
+    /// error policy to exit the window manager.
+    ///
+    /// Default: 10
+    pub error_repeat_limit: u32,
+
     /// Log file to write logs to
     /// If "STDOUT" or "STDERR" is passed, logging will be done on stdout and stderr respectivelly.
     pub log_file: String,
+    /// Size of the log file in bytes, after which the file is rotated: `log` becomes `log.1`,
+    /// `log.1` becomes `log.2` and so on. 0 disables log rotation.
+    ///
+    /// Default: 0
+    pub log_max_size: u64,
+    /// Number of rotated log files to keep around.
+    ///
+    /// Default: 3
+    pub log_max_files: u32,
+    /// Format of a single log line.
+    /// `{time}` is replaced by an ISO-8601 timestamp, `{level}` by either "INFO" or "ERROR" and
+    /// `{message}` by the logged message.
+    ///
+    /// Default: "{time} [{level}] {message}"
+    pub log_format: String,
     /// Logging level:
     /// 0: disabled
     /// 1: only the most importatnt logs will be logged.
@@ -63,6 +292,8 @@ impl Default for Settings {
             border_size: 1,
 
             border_color: "#000000".to_string(),
+            inner_border_size: 0,
+            inner_border_color: "#000000".to_string(),
 
             display_name: "".to_string(),
 
@@ -75,13 +306,99 @@ impl Default for Settings {
             gap_bottom_size: 0,
             gap_left_size: 0,
             gap_right_size: 0,
+            outer_gap_size: 0,
+            tiling_direction: "right".into(),
+            spawn_side: "none".into(),
+            keep_tiled_slot: true,
+            focus_model: "pointer-follows-focus".into(),
+            swallow: false,
+            coalesce_layouts: false,
+            animations: false,
+            animation_duration: 120,
+            visual_bell: "".into(),
+            visual_bell_duration: 150,
+            terminal: "xterm".into(),
+            float_terminal_width: 800,
+            float_terminal_height: 450,
+            float_terminal_at_cursor: true,
+            master_fill_when_alone: true,
+            per_monitor_workspaces: false,
+            remember_workspace: false,
+            fullscreen_workspace: 0,
+            fullscreen_workspace_classes: "".into(),
+            bar_on_top_of_fullscreen: false,
+            keyboard_layouts: "".into(),
+            manage_monitors: "".into(),
+            bar_output: "".into(),
+            error_policy: "continue".into(),
+            error_repeat_limit: 10,
+            kill_timeout: 3000,
+            bar_update_interval: 1000,
+            pin_width: 480,
+            pin_height: 270,
             log_file: "STDERR".into(),
+            log_max_size: 0,
+            log_max_files: 3,
+            log_format: DEFAULT_LOG_FORMAT.into(),
             log_level: 0,
         }
     }
 }
 
 impl Settings {
+    /// Names of all the options, in the order in which they are declared.
+    pub const NAMES: &'static [&'static str] = &[
+        "border",
+        "border_size",
+        "border_color",
+        "inner_border_size",
+        "inner_border_color",
+        "display_name",
+        "gap_top",
+        "gap_bottom",
+        "gap_left",
+        "gap_right",
+        "gap_top_size",
+        "gap_bottom_size",
+        "gap_left_size",
+        "gap_right_size",
+        "outer_gap_size",
+        "tiling_direction",
+        "spawn_side",
+        "keep_tiled_slot",
+        "focus_model",
+        "swallow",
+        "coalesce_layouts",
+        "animations",
+        "animation_duration",
+        "visual_bell",
+        "visual_bell_duration",
+        "terminal",
+        "float_terminal_width",
+        "float_terminal_height",
+        "float_terminal_at_cursor",
+        "master_fill_when_alone",
+        "per_monitor_workspaces",
+        "remember_workspace",
+        "fullscreen_workspace",
+        "fullscreen_workspace_classes",
+        "bar_on_top_of_fullscreen",
+        "keyboard_layouts",
+        "manage_monitors",
+        "bar_output",
+        "pin_width",
+        "pin_height",
+        "kill_timeout",
+        "bar_update_interval",
+        "error_policy",
+        "error_repeat_limit",
+        "log_file",
+        "log_max_size",
+        "log_max_files",
+        "log_format",
+        "log_level",
+    ];
+
     pub fn add(&mut self, name: String, value: String) -> WmResult {
         match name.as_ref() {
             "border" => {
@@ -97,6 +414,15 @@ impl Settings {
                     self.border_color = value;
                 }
             }
+            "inner_border_size" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                self.inner_border_size = val;
+            }
+            "inner_border_color" => {
+                if value.starts_with('#') && value.len() == 7 {
+                    self.inner_border_color = value;
+                }
+            }
             "display_name" => self.display_name = value,
             "gap_top" => {
                 let val = value.to_lowercase().parse::<bool>()?;
@@ -138,7 +464,167 @@ impl Settings {
 
                 self.gap_right_size = val;
             }
+            "outer_gap_size" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+
+                self.outer_gap_size = val;
+            }
+            "tiling_direction" => {
+                let val = value.to_lowercase();
+                if val != "left" && val != "right" {
+                    return Err(format!(
+                        "option parsing error: tiling_direction must be either left or right, not {value}"
+                    )
+                    .into());
+                }
+
+                self.tiling_direction = val;
+            }
+            "spawn_side" => {
+                let val = value.to_lowercase();
+                if !["before", "after", "none"].contains(&val.as_str()) {
+                    return Err(format!(
+                        "option parsing error: spawn_side must be one of before, after or none, not {value}"
+                    )
+                    .into());
+                }
+
+                self.spawn_side = val;
+            }
+            "keep_tiled_slot" => {
+                let val = value.to_lowercase().parse::<bool>()?;
+
+                self.keep_tiled_slot = val;
+            }
+            "focus_model" => {
+                let val = value.to_lowercase();
+                if !["sloppy", "click", "pointer-follows-focus"].contains(&val.as_str()) {
+                    return Err(format!(
+                        "option parsing error: focus_model must be one of sloppy, click or pointer-follows-focus, not {value}"
+                    )
+                    .into());
+                }
+
+                self.focus_model = val;
+            }
+            "coalesce_layouts" => {
+                let val = value.to_lowercase().parse::<bool>()?;
+                self.coalesce_layouts = val;
+            }
+            "animations" => {
+                let val = value.to_lowercase().parse::<bool>()?;
+                self.animations = val;
+            }
+            "animation_duration" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                self.animation_duration = val;
+            }
+            "visual_bell" => {
+                let is_color = value.starts_with('#') && value.len() == 7;
+                if !value.is_empty() && !is_color {
+                    return Err(format!(
+                        "{value} is not a color, expected a hex color beginning with #, or nothing to turn the visual bell off."
+                    )
+                    .into());
+                }
+                self.visual_bell = value;
+            }
+            "visual_bell_duration" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                self.visual_bell_duration = val;
+            }
+            "terminal" => {
+                if value.trim().is_empty() {
+                    return Err("the terminal command is empty.".into());
+                }
+                self.terminal = value;
+            }
+            "float_terminal_width" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                self.float_terminal_width = val.max(1);
+            }
+            "float_terminal_height" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                self.float_terminal_height = val.max(1);
+            }
+            "float_terminal_at_cursor" => {
+                let val = value.to_lowercase().parse::<bool>()?;
+                self.float_terminal_at_cursor = val;
+            }
+            "master_fill_when_alone" => {
+                let val = value.to_lowercase().parse::<bool>()?;
+                self.master_fill_when_alone = val;
+            }
+            "swallow" => {
+                let val = value.to_lowercase().parse::<bool>()?;
+                self.swallow = val;
+            }
+            "per_monitor_workspaces" => {
+                let val = value.to_lowercase().parse::<bool>()?;
+                self.per_monitor_workspaces = val;
+            }
+            "remember_workspace" => {
+                let val = value.to_lowercase().parse::<bool>()?;
+                self.remember_workspace = val;
+            }
+            "fullscreen_workspace" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                self.fullscreen_workspace = val;
+            }
+            "fullscreen_workspace_classes" => self.fullscreen_workspace_classes = value,
+            "bar_on_top_of_fullscreen" => {
+                let val = value.to_lowercase().parse::<bool>()?;
+                self.bar_on_top_of_fullscreen = val;
+            }
+            "keyboard_layouts" => self.keyboard_layouts = value,
+            "manage_monitors" => self.manage_monitors = value,
+            "bar_output" => self.bar_output = value,
+            "pin_width" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                self.pin_width = val;
+            }
+            "kill_timeout" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                self.kill_timeout = val;
+            }
+            "bar_update_interval" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                if val == 0 {
+                    return Err(
+                        "option parsing error: bar_update_interval must be greater than 0".into(),
+                    );
+                }
+                self.bar_update_interval = val;
+            }
+            "pin_height" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                self.pin_height = val;
+            }
+            "error_policy" => {
+                let val = value.to_lowercase();
+                if !["continue", "log-verbose", "abort-on-repeated"].contains(&val.as_str()) {
+                    return Err(format!(
+                        "option parsing error: error_policy must be one of continue, log-verbose or abort-on-repeated, not {value}"
+                    )
+                    .into());
+                }
+
+                self.error_policy = val;
+            }
+            "error_repeat_limit" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                self.error_repeat_limit = val;
+            }
             "log_file" => self.log_file = value,
+            "log_max_size" => {
+                let val = value.to_lowercase().parse::<u64>()?;
+                self.log_max_size = val
+            }
+            "log_format" => self.log_format = value,
+            "log_max_files" => {
+                let val = value.to_lowercase().parse::<u32>()?;
+                self.log_max_files = val
+            }
             "log_level" => {
                 let val = value.to_lowercase().parse::<u8>()?;
                 self.log_level = val
@@ -149,6 +635,80 @@ impl Settings {
         Ok(())
     }
 
+    /// Should new windows be inserted in front of all the other windows in a layout?
+    pub fn new_windows_first(&self) -> bool {
+        self.tiling_direction == "left"
+    }
+
+    /// Should the pointer be moved to windows and workspaces focused using the keyboard?
+    pub fn pointer_follows_focus(&self) -> bool {
+        self.focus_model == "pointer-follows-focus"
+    }
+
+    /// Should windows get focused when the pointer enters them?
+    pub fn focus_follows_pointer(&self) -> bool {
+        self.focus_model != "click"
+    }
+
+    /// Should windows get focused by clicking them?
+    pub fn click_to_focus(&self) -> bool {
+        self.focus_model == "click"
+    }
+
+    /// Should new windows be inserted after the focused window(`Some(true)`), before it
+    /// (`Some(false)`), or according to `tiling_direction`(`None`)?
+    pub fn spawn_after_focused(&self) -> Option<bool> {
+        match self.spawn_side.as_str() {
+            "before" => Some(false),
+            "after" => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Return the workspace onto which a fullscreen window of the given class should be moved, if
+    /// any.
+    pub fn fullscreen_workspace_for(&self, class: Option<&str>) -> Option<u32> {
+        if self.fullscreen_workspace == 0 {
+            return None;
+        }
+
+        let mut classes = self
+            .fullscreen_workspace_classes
+            .split(',')
+            .map(|class| class.trim())
+            .filter(|class| !class.is_empty())
+            .peekable();
+        if classes.peek().is_none() {
+            return Some(self.fullscreen_workspace);
+        }
+
+        let class = class?;
+        if classes.any(|c| c.eq_ignore_ascii_case(class)) {
+            Some(self.fullscreen_workspace)
+        } else {
+            None
+        }
+    }
+
+    /// Return the keyboard layouts from the `keyboard_layouts` setting.
+    pub fn keyboard_layouts(&self) -> Vec<String> {
+        self.keyboard_layouts
+            .split(',')
+            .map(|layout| layout.trim().to_string())
+            .filter(|layout| !layout.is_empty())
+            .collect()
+    }
+
+    /// Return the monitors listed in the `manage_monitors` setting, empty if all the monitors
+    /// should be managed.
+    pub fn manage_monitors(&self) -> Vec<String> {
+        self.manage_monitors
+            .split(',')
+            .map(|monitor| monitor.trim().to_string())
+            .filter(|monitor| !monitor.is_empty())
+            .collect()
+    }
+
     /// Returns the tuple contining the width of the window gaps or 0 if that particular gap is
     /// disabled.
     ///
@@ -189,56 +749,120 @@ impl Settings {
 
     /// Convert a string representing a hex color into a 32-bit RGBA number.
     pub fn convert_border_color(&self) -> u32 {
-        let nums = self
-            .border_color
-            .clone()
-            .strip_prefix('#')
-            .unwrap_or("000000")
-            .to_owned();
-        if nums.len() != 6 {
-            return 0u32;
-        }
+        convert_color(&self.border_color)
+    }
 
-        let red = u32::from_str_radix(&nums[0..=1], 16).unwrap_or(0);
-        let green = u32::from_str_radix(&nums[2..=3], 16).unwrap_or(0);
-        let blue = u32::from_str_radix(&nums[4..=5], 16).unwrap_or(0);
+    /// Convert the inner border color into a 32-bit RGBA number.
+    pub fn convert_inner_border_color(&self) -> u32 {
+        convert_color(&self.inner_border_color)
+    }
 
-        255 << 24 | (red << 16) | (green << 8) | blue
+    /// Returns the width of the inner part of window borders, which is never wider than the whole
+    /// border.
+    pub fn get_inner_border(&self) -> u32 {
+        self.inner_border_size.min(self.get_borders())
     }
 }
 
-impl Repr for Settings {
-    fn repr(&self) -> WmResult<String> {
-        let mut buffer = String::new();
-        let options = format!("{:#?}", self);
-
-        for (ii, option) in options.lines().enumerate() {
-            if ii == 0 {
-                continue;
-            }
-
-            if option.starts_with('}') {
-                continue;
-            }
-
-            let part = option.trim();
-            let pair = part.split(',').collect::<Vec<&str>>()[0];
+/// Convert a string representing a hex color into a 32-bit RGBA number.
+pub fn convert_color(color: &str) -> u32 {
+    let nums = color.strip_prefix('#').unwrap_or("000000").to_owned();
+    if nums.len() != 6 {
+        return 0u32;
+    }
 
-            let (left, right) = pair.split_at(pair.find(':').unwrap());
-            let mut right = right.to_string();
+    let red = u32::from_str_radix(&nums[0..=1], 16).unwrap_or(0);
+    let green = u32::from_str_radix(&nums[2..=3], 16).unwrap_or(0);
+    let blue = u32::from_str_radix(&nums[4..=5], 16).unwrap_or(0);
 
-            right.remove(0);
-            right.remove(0);
+    255 << 24 | (red << 16) | (green << 8) | blue
+}
 
-            if right == r#""""# {
-                right = String::new();
-            }
+impl Repr for Settings {
+    fn repr(&self) -> WmResult<String> {
+        let mut buffer = String::new();
 
-            buffer.push_str("option ");
-            buffer.push_str(format!("\"{}\"", left).as_str());
-            buffer.push(' ');
-            buffer.push_str(format!("\"{}\"", right).as_str());
-            buffer.push('\n')
+        let settings = [
+            ("border", self.border.to_string()),
+            ("border_size", self.border_size.to_string()),
+            ("border_color", quote(&self.border_color)),
+            ("inner_border_size", self.inner_border_size.to_string()),
+            ("inner_border_color", quote(&self.inner_border_color)),
+            ("display_name", quote(&self.display_name)),
+            ("gap_top", self.gap_top.to_string()),
+            ("gap_bottom", self.gap_bottom.to_string()),
+            ("gap_left", self.gap_left.to_string()),
+            ("gap_right", self.gap_right.to_string()),
+            ("gap_top_size", self.gap_top_size.to_string()),
+            ("gap_bottom_size", self.gap_bottom_size.to_string()),
+            ("gap_left_size", self.gap_left_size.to_string()),
+            ("gap_right_size", self.gap_right_size.to_string()),
+            ("outer_gap_size", self.outer_gap_size.to_string()),
+            ("tiling_direction", quote(&self.tiling_direction)),
+            ("spawn_side", quote(&self.spawn_side)),
+            ("keep_tiled_slot", self.keep_tiled_slot.to_string()),
+            ("focus_model", quote(&self.focus_model)),
+            ("swallow", self.swallow.to_string()),
+            ("coalesce_layouts", self.coalesce_layouts.to_string()),
+            ("animations", self.animations.to_string()),
+            ("animation_duration", self.animation_duration.to_string()),
+            ("visual_bell", quote(&self.visual_bell)),
+            (
+                "visual_bell_duration",
+                self.visual_bell_duration.to_string(),
+            ),
+            ("terminal", quote(&self.terminal)),
+            (
+                "float_terminal_width",
+                self.float_terminal_width.to_string(),
+            ),
+            (
+                "float_terminal_height",
+                self.float_terminal_height.to_string(),
+            ),
+            (
+                "float_terminal_at_cursor",
+                self.float_terminal_at_cursor.to_string(),
+            ),
+            (
+                "master_fill_when_alone",
+                self.master_fill_when_alone.to_string(),
+            ),
+            (
+                "per_monitor_workspaces",
+                self.per_monitor_workspaces.to_string(),
+            ),
+            ("remember_workspace", self.remember_workspace.to_string()),
+            (
+                "fullscreen_workspace",
+                self.fullscreen_workspace.to_string(),
+            ),
+            (
+                "fullscreen_workspace_classes",
+                quote(&self.fullscreen_workspace_classes),
+            ),
+            (
+                "bar_on_top_of_fullscreen",
+                self.bar_on_top_of_fullscreen.to_string(),
+            ),
+            ("keyboard_layouts", quote(&self.keyboard_layouts)),
+            ("manage_monitors", quote(&self.manage_monitors)),
+            ("bar_output", quote(&self.bar_output)),
+            ("pin_width", self.pin_width.to_string()),
+            ("pin_height", self.pin_height.to_string()),
+            ("kill_timeout", self.kill_timeout.to_string()),
+            ("bar_update_interval", self.bar_update_interval.to_string()),
+            ("error_policy", quote(&self.error_policy)),
+            ("error_repeat_limit", self.error_repeat_limit.to_string()),
+            ("log_file", quote(&self.log_file)),
+            ("log_max_size", self.log_max_size.to_string()),
+            ("log_max_files", self.log_max_files.to_string()),
+            ("log_format", quote(&self.log_format)),
+            ("log_level", self.log_level.to_string()),
+        ];
+
+        for (name, value) in settings {
+            writeln!(buffer, "set {name} {value}")?;
         }
 
         Ok(buffer)
@@ -265,10 +889,113 @@ mod tests {
         assert_eq!(c.convert_border_color(), 0xfffb11cc)
     }
 
+    #[test]
+    fn test_inner_border() {
+        let mut c = Settings::default();
+        assert_eq!(c.get_inner_border(), 0);
+
+        c.add("inner_border_size".into(), "3".into()).unwrap();
+        c.add("inner_border_color".into(), "#fb11cc".into())
+            .unwrap();
+        assert_eq!(c.get_inner_border(), 1);
+        assert_eq!(c.convert_inner_border_color(), 0xfffb11cc);
+
+        c.border_size = 5;
+        assert_eq!(c.get_inner_border(), 3);
+        c.border = false;
+        assert_eq!(c.get_inner_border(), 0);
+    }
+
+    #[test]
+    fn test_visual_bell() {
+        let mut settings = Settings::default();
+        assert!(settings.visual_bell.is_empty());
+
+        settings
+            .add("visual_bell".into(), "#ff0000".into())
+            .unwrap();
+        assert_eq!(convert_color(&settings.visual_bell), 0xffff0000);
+        assert!(settings.add("visual_bell".into(), "red".into()).is_err());
+        assert_eq!(settings.visual_bell, "#ff0000");
+
+        settings.add("visual_bell".into(), "".into()).unwrap();
+        assert!(settings.visual_bell.is_empty());
+    }
+
     #[test]
     fn test_repr() {
-        let options = Settings::default();
+        let options = Settings {
+            terminal: "st -e \"sh\"".into(),
+            border_size: 7,
+            ..Default::default()
+        };
+
+        let config = crate::parsers::ConfigParser::parse_str(&options.repr().unwrap()).unwrap();
+        assert_eq!(config.explicit_settings, Settings::NAMES);
+        assert_eq!(config.settings.terminal, options.terminal);
+        assert_eq!(config.settings.border_size, 7);
+    }
 
-        println!("{}", options.repr().unwrap())
+    #[test]
+    fn test_option_names() {
+        assert_eq!(Settings::NAMES.first(), Some(&"border"));
+        assert!(Settings::NAMES.contains(&"log_level"));
+
+        // every name is accepted by `Settings::add`, even if the value isn't
+        for name in Settings::NAMES {
+            if let Err(e) = Settings::default().add(name.to_string(), "?".into()) {
+                assert!(!e.to_string().contains("Unknown option"), "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_focus_model() {
+        let mut settings = Settings::default();
+        assert!(settings.pointer_follows_focus());
+        assert!(settings.focus_follows_pointer());
+        assert!(!settings.click_to_focus());
+
+        settings.add("focus_model".into(), "sloppy".into()).unwrap();
+        assert!(!settings.pointer_follows_focus());
+        assert!(settings.focus_follows_pointer());
+        assert!(!settings.click_to_focus());
+
+        settings.add("focus_model".into(), "Click".into()).unwrap();
+        assert!(!settings.pointer_follows_focus());
+        assert!(!settings.focus_follows_pointer());
+        assert!(settings.click_to_focus());
+
+        assert!(settings.add("focus_model".into(), "follow".into()).is_err());
+    }
+
+    #[test]
+    fn test_fullscreen_workspace() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.fullscreen_workspace_for(Some("steam")), None);
+
+        settings
+            .add("fullscreen_workspace".into(), "9".into())
+            .unwrap();
+        assert_eq!(settings.fullscreen_workspace_for(Some("mpv")), Some(9));
+        assert_eq!(settings.fullscreen_workspace_for(None), Some(9));
+
+        settings
+            .add("fullscreen_workspace_classes".into(), "steam, mpv".into())
+            .unwrap();
+        assert_eq!(settings.fullscreen_workspace_for(Some("Steam")), Some(9));
+        assert_eq!(settings.fullscreen_workspace_for(Some("firefox")), None);
+        assert_eq!(settings.fullscreen_workspace_for(None), None);
+    }
+
+    #[test]
+    fn test_keyboard_layouts() {
+        let mut settings = Settings::default();
+        assert!(settings.keyboard_layouts().is_empty());
+
+        settings
+            .add("keyboard_layouts".into(), "us, ru,,de".into())
+            .unwrap();
+        assert_eq!(settings.keyboard_layouts(), vec!["us", "ru", "de"]);
     }
 }
@@ -1,8 +1,10 @@
-use crate::config::Repr;
+use crate::config::{quote, Repr};
 use crate::errors::{Error, WmResult};
 use crate::log::{log, LL_FULL};
 use crate::logm;
 
+use std::fmt::Write;
+
 #[derive(Debug, Clone)]
 pub enum HookType {
     Startup,
@@ -76,6 +78,16 @@ impl StartHooks {
         Ok(())
     }
 
+    /// Return the number of hooks.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Are there no hooks?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn run(&self) -> WmResult {
         for hook in &self.0 {
             match hook.hook_option {
@@ -137,8 +149,42 @@ impl StartHooks {
     }
 }
 
+impl Repr for HookType {
+    fn repr(&self) -> WmResult<String> {
+        Ok(match self {
+            Self::Startup => "startup",
+            Self::Always => "always",
+            Self::After => "after",
+        }
+        .to_string())
+    }
+}
+
+impl Repr for HookOption {
+    fn repr(&self) -> WmResult<String> {
+        Ok(match self {
+            Self::Sync => "sync",
+            Self::Async => "async",
+        }
+        .to_string())
+    }
+}
+
 impl Repr for StartHooks {
     fn repr(&self) -> WmResult<String> {
-        Ok("ahoy".to_string())
+        let mut buffer = String::new();
+
+        for hook in self.0.iter() {
+            let args: Vec<String> = hook.hook_args.iter().map(|a| quote(a)).collect();
+            writeln!(
+                buffer,
+                "hook {} {} {}",
+                hook.hook_type.repr()?,
+                hook.hook_option.repr()?,
+                args.join(" ")
+            )?;
+        }
+
+        Ok(buffer)
     }
 }
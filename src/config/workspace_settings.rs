@@ -1,14 +1,36 @@
 use crate::errors::{Error, WmResult};
 
-use super::Repr;
+use super::{quote, Repr};
+
+use std::fmt::Write;
 
 #[derive(Clone, Debug)]
 pub struct WorkspaceSettings {
     pub identifier: u32,
     pub name: String,
+    /// Icon or symbol shown for this workspace in the bar, using the `{icon}` format token.
+    pub icon: String,
     pub allowed_layouts: Vec<String>,
     pub monitor: String,
     pub default_container_type: String,
+    /// Keep the windows of this workspace mapped, moving them off-screen, when the workspace is
+    /// not shown.
+    pub keep_mapped: bool,
+    /// Maximum number of tiled windows on this workspace, 0 means there is no limit.
+    pub max_tiled: u32,
+    /// What happens to new windows once `max_tiled` is reached, either "float" or
+    /// "next_workspace".
+    pub overflow: String,
+    /// The part of the screen's width taken by the master window in the master-stack layout.
+    pub master_ratio: f32,
+    /// How the stack of the master-stack layout is split, either "vertical" or "horizontal".
+    pub stack_orientation: String,
+    /// Color shown behind the workspace while it has no windows, as "#rrggbb". Empty if the root
+    /// window should show through instead.
+    pub background: String,
+    /// Leave this workspace out when switching to the next or previous workspace, it can still be
+    /// focused directly.
+    pub skip_cycle: bool,
 }
 
 impl WorkspaceSettings {
@@ -16,9 +38,17 @@ impl WorkspaceSettings {
         Self {
             identifier,
             name: format!("{}", identifier),
+            icon: "".to_string(),
             monitor: "".to_string(),
             allowed_layouts: vec!["all".to_string()],
             default_container_type: "in_layout".to_string(),
+            keep_mapped: false,
+            max_tiled: 0,
+            overflow: "float".to_string(),
+            master_ratio: 0.5,
+            stack_orientation: "vertical".to_string(),
+            background: "".to_string(),
+            skip_cycle: false,
         }
     }
 
@@ -26,6 +56,10 @@ impl WorkspaceSettings {
         self.name = name;
     }
 
+    pub fn with_icon(&mut self, icon: String) {
+        self.icon = icon;
+    }
+
     pub fn with_allowed_layouts(&mut self, allowed_layouts: Vec<String>) {
         self.allowed_layouts = allowed_layouts;
     }
@@ -37,6 +71,34 @@ impl WorkspaceSettings {
     pub fn with_default_container_type(&mut self, default_container_type: String) {
         self.default_container_type = default_container_type;
     }
+
+    pub fn with_keep_mapped(&mut self, keep_mapped: bool) {
+        self.keep_mapped = keep_mapped;
+    }
+
+    pub fn with_max_tiled(&mut self, max_tiled: u32) {
+        self.max_tiled = max_tiled;
+    }
+
+    pub fn with_overflow(&mut self, overflow: String) {
+        self.overflow = overflow;
+    }
+
+    pub fn with_master_ratio(&mut self, master_ratio: f32) {
+        self.master_ratio = master_ratio;
+    }
+
+    pub fn with_stack_orientation(&mut self, stack_orientation: String) {
+        self.stack_orientation = stack_orientation;
+    }
+
+    pub fn with_background(&mut self, background: String) {
+        self.background = background;
+    }
+
+    pub fn with_skip_cycle(&mut self, skip_cycle: bool) {
+        self.skip_cycle = skip_cycle;
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -56,11 +118,56 @@ impl Default for AllWorkspaceSettings {
 
 impl Repr for AllWorkspaceSettings {
     fn repr(&self) -> WmResult<String> {
-        Ok("self".to_string())
+        let mut buffer = String::new();
+
+        for workspace in self.0.iter() {
+            let id = workspace.identifier;
+            let mut settings = vec![
+                ("name", quote(&workspace.name)),
+                ("icon", quote(&workspace.icon)),
+                ("allowed_layouts", workspace.allowed_layouts.join(" ")),
+                ("monitor", quote(&workspace.monitor)),
+                (
+                    "default_container_type",
+                    workspace.default_container_type.clone(),
+                ),
+                ("keep_mapped", workspace.keep_mapped.to_string()),
+                ("max_tiled", workspace.max_tiled.to_string()),
+                ("overflow", workspace.overflow.clone()),
+                ("master_ratio", workspace.master_ratio.to_string()),
+                ("stack_orientation", workspace.stack_orientation.clone()),
+                ("skip_cycle", workspace.skip_cycle.to_string()),
+            ];
+            // An empty background is not a valid color, it is only the absence of one.
+            if !workspace.background.is_empty() {
+                settings.push(("background", workspace.background.clone()));
+            }
+
+            for (name, value) in settings {
+                writeln!(buffer, "workspace_set {id} {name} {value}")?;
+            }
+        }
+
+        Ok(buffer)
     }
 }
 
 impl AllWorkspaceSettings {
+    /// Return the number of configured workspaces.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Are there no configured workspaces?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Return the settings of the workspace with the given identifier.
+    pub fn get(&self, identifier: u32) -> Option<&WorkspaceSettings> {
+        self.0.iter().find(|w| w.identifier == identifier)
+    }
+
     pub fn add(&mut self, identifier: u32, name: String, value: Vec<String>) -> WmResult {
         let workspace = self
             .0
@@ -75,6 +182,9 @@ impl AllWorkspaceSettings {
             "name" => {
                 workspace.with_name(value[0].clone());
             }
+            "icon" => {
+                workspace.with_icon(value[0].clone());
+            }
             "monitor" => {
                 workspace.with_monitor(value[0].clone());
             }
@@ -82,6 +192,61 @@ impl AllWorkspaceSettings {
             "default_container_type" => {
                 workspace.with_default_container_type(value[0].clone());
             }
+            "keep_mapped" => {
+                workspace.with_keep_mapped(value[0].to_lowercase().parse::<bool>()?);
+            }
+            "max_tiled" => {
+                workspace.with_max_tiled(value[0].parse::<u32>()?);
+            }
+            "overflow" => {
+                let overflow = value[0].to_lowercase();
+                if overflow != "float" && overflow != "next_workspace" {
+                    return Err(format!(
+                        "workspace setting parsing error: {overflow} is not a valid overflow behavior, expected float or next_workspace."
+                    )
+                    .into());
+                }
+                workspace.with_overflow(overflow);
+            }
+            "skip_cycle" => {
+                workspace.with_skip_cycle(value[0].to_lowercase().parse::<bool>()?);
+            }
+            "master_ratio" => {
+                let master_ratio = value[0]
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|ratio| ratio.is_finite() && *ratio > 0.0 && *ratio < 1.0)
+                    .ok_or_else(|| {
+                        Error::Generic(format!(
+                            "workspace setting parsing error: {} is not a valid master ratio, expected a number between 0 and 1.",
+                            value[0]
+                        ))
+                    })?;
+                workspace.with_master_ratio(master_ratio);
+            }
+            "stack_orientation" => {
+                let orientation = value[0].to_lowercase();
+                if orientation != "vertical" && orientation != "horizontal" {
+                    return Err(format!(
+                        "workspace setting parsing error: {orientation} is not a valid stack orientation, expected vertical or horizontal."
+                    )
+                    .into());
+                }
+                workspace.with_stack_orientation(orientation);
+            }
+            "background" => {
+                let color = &value[0];
+                let valid = color.len() == 7
+                    && color.starts_with('#')
+                    && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+                if !valid {
+                    return Err(format!(
+                        "workspace setting parsing error: {color} is not a valid background color, expected #rrggbb."
+                    )
+                    .into());
+                }
+                workspace.with_background(color.clone());
+            }
             _ => {
                 return Err(format!(
                     "workspace setting parsing error: setting {name} does not exist!"
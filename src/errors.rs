@@ -19,6 +19,12 @@ pub enum Error {
     SystemTime(std::time::SystemTimeError),
     Cairo(cairo::Error),
     HpError(hp::HpError),
+    /// An error in a config file, along with the number and text of the offending line.
+    ConfigError {
+        line: usize,
+        text: String,
+        message: String,
+    },
     MutexPoison,
     NullPtr,
 }
@@ -136,32 +142,86 @@ impl From<x11rb::errors::ReplyOrIdError> for Error {
     }
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Error {
+    /// Turn an error which occurred while parsing a line of a config file into a `ConfigError`.
+    pub fn config(line: usize, text: impl Into<String>, error: Error) -> Self {
+        match error {
+            Self::ConfigError { .. } => error,
+            e => Self::ConfigError {
+                line,
+                text: text.into(),
+                message: e.message(),
+            },
+        }
+    }
+
+    /// Is this error caused by a broken connection to the X server?
+    ///
+    /// Such errors can't be recovered from, unlike errors caused by a single request or event.
+    pub fn is_connection_error(&self) -> bool {
+        use x11rb::errors::{ReplyError, ReplyOrIdError};
+
+        matches!(
+            self,
+            Self::X11Connection(_)
+                | Self::X11Reply(ReplyError::ConnectionError(_))
+                | Self::X11ReplyOrIdError(ReplyOrIdError::ConnectionError(_))
+        )
+    }
+
+    /// Return the error message, without the `[ERR]` prefix.
+    pub fn message(&self) -> String {
         match self {
-            Self::Io(e) => write!(f, "[ERR] {}", e),
-            Self::Generic(e) => write!(f, "[ERR] {}", e),
-            Self::Borrow(e) => write!(f, "[ERR] {}", e),
-            Self::ParseInt(e) => write!(f, "[ERR] {}", e),
-            Self::ParseBool(e) => write!(f, "[ERR] {}", e),
-            Self::BorrowMut(e) => write!(f, "[ERR] {}", e),
-            Self::X11Connect(e) => write!(f, "[ERR] {}", e),
-            Self::X11Reply(e) => write!(f, "[ERR] {}", e),
-            Self::X11Connection(e) => write!(f, "[ERR] {}", e),
-            Self::FromUtf8(e) => write!(f, "[ERR] {}", e),
-            Self::Utf8(e) => write!(f, "[ERR] {}", e),
-            Self::Null(e) => write!(f, "[ERR] {}", e),
-            Self::X11ReplyOrIdError(e) => write!(f, "[ERR] {}", e),
-            Self::Fmt(e) => write!(f, "[ERR] {}", e),
-            Self::SystemTime(e) => write!(f, "[ERR] {}", e),
-            Self::Cairo(e) => write!(f, "[ERR] {}", e),
-            Self::MutexPoison => write!(f, "[ERR] bar mutex has been poisoned."),
-            Self::NullPtr => write!(f, "[ERR] a pointer expected to be not null is null"),
-            Self::HpError(e) => write!(f, "[ERR] {}", e),
+            Self::Io(e) => e.to_string(),
+            Self::Generic(e) => e.to_string(),
+            Self::Borrow(e) => e.to_string(),
+            Self::ParseInt(e) => e.to_string(),
+            Self::ParseBool(e) => e.to_string(),
+            Self::BorrowMut(e) => e.to_string(),
+            Self::X11Connect(e) => e.to_string(),
+            Self::X11Reply(e) => e.to_string(),
+            Self::X11Connection(e) => e.to_string(),
+            Self::FromUtf8(e) => e.to_string(),
+            Self::Utf8(e) => e.to_string(),
+            Self::Null(e) => e.to_string(),
+            Self::X11ReplyOrIdError(e) => e.to_string(),
+            Self::Fmt(e) => e.to_string(),
+            Self::SystemTime(e) => e.to_string(),
+            Self::Cairo(e) => e.to_string(),
+            Self::MutexPoison => "bar mutex has been poisoned.".to_string(),
+            Self::NullPtr => "a pointer expected to be not null is null".to_string(),
+            Self::HpError(e) => e.to_string(),
+            Self::ConfigError {
+                line,
+                text,
+                message,
+            } => format!("config error on line {line}: {message}\n    {line} | {text}"),
         }
     }
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[ERR] {}", self.message())
+    }
+}
+
 impl std::error::Error for Error {}
 
 pub type WmResult<T = ()> = Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x11rb::errors::{ConnectionError, ReplyError};
+
+    #[test]
+    fn connection_errors() {
+        assert!(Error::from(ConnectionError::UnknownError).is_connection_error());
+        assert!(
+            Error::from(ReplyError::ConnectionError(ConnectionError::UnknownError))
+                .is_connection_error()
+        );
+        assert!(!Error::from("workspace error").is_connection_error());
+    }
+}
@@ -1,11 +1,12 @@
 use std::fs::{File, OpenOptions};
 use std::io::{stderr, stdout, Write};
 use std::os::unix::prelude::AsFd;
-use std::os::unix::prelude::OwnedFd;
+use std::path::Path;
 use std::sync::{
     atomic::{AtomicU8, Ordering},
     Mutex,
 };
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::errors::WmResult;
 
@@ -16,86 +17,208 @@ pub const LL_FULL: u8 = 2;
 pub const LF_STDOUT: &str = "STDOUT";
 pub const LF_STDERR: &str = "STDERR";
 
-static mut LOG_LEVEL: AtomicU8 = AtomicU8::new(LL_OFF);
-static mut WRITER: Mutex<Option<OwnedFd>> = Mutex::new(None);
+/// The default format of a log line.
+pub const DEFAULT_LOG_FORMAT: &str = "{time} [{level}] {message}";
 
-pub fn prepare_logger(file: &impl AsRef<str>, level: u8) -> WmResult {
-    if level >= 3 {
-        return Err("Invalid log level: {level}".into());
-    }
-    unsafe {
-        LOG_LEVEL.store(level, Ordering::Relaxed);
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LL_OFF);
+static WRITER: Mutex<Option<LogWriter>> = Mutex::new(None);
+
+/// Writes formatted log lines to the log file, rotating it once it grows too large.
+struct LogWriter {
+    file: File,
+    /// Format of a log line, see [`format_line`].
+    format: String,
+    rotation: Option<Rotation>,
+    /// Number of bytes in the log file, kept track of so that the file doesn't have to be
+    /// inspected for every line.
+    size: u64,
+}
+
+/// When and how the log file should be rotated.
+struct Rotation {
+    path: String,
+    /// Size of the log file in bytes, after which the file is rotated.
+    max_size: u64,
+    /// Number of rotated log files to keep.
+    max_files: u32,
+}
+
+impl LogWriter {
+    fn new(file: File, format: &str, rotation: Option<Rotation>) -> Self {
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Self {
+            file,
+            format: format.to_string(),
+            rotation,
+            size,
+        }
     }
-    let writer = Mutex::new(None);
-    let fname = file.as_ref();
-    if fname == LF_STDOUT {
-        if let Ok(mut guard) = writer.lock() {
-            let fd = stdout().as_fd().try_clone_to_owned()?;
-            *guard = Some(fd);
-            println!("{guard:#?}");
-            drop(guard)
+
+    /// Write a single line to the log, rotating the log file if it grew too large.
+    fn write_line(&mut self, level: &str, msg: &str, time: SystemTime) {
+        let line = format_line(&self.format, level, msg, time);
+        if writeln!(&mut self.file, "{line}").is_ok() {
+            self.file.flush().unwrap_or(());
+            self.size += line.len() as u64 + 1;
         }
-    } else if fname == LF_STDERR {
-        if let Ok(mut guard) = writer.lock() {
-            let fd = stderr().as_fd().try_clone_to_owned()?;
-            let _ = guard.insert(fd);
+
+        if let Some(file) = self.rotate_if_needed() {
+            self.file = file;
+            self.size = 0;
         }
-    } else if let Ok(file) = OpenOptions::new().write(true).create(true).open(fname) {
-        {
-            if let Ok(mut guard) = writer.lock() {
-                let fd = file.into();
-                let _ = guard.insert(fd);
-            }
+    }
+
+    /// If the log file is larger than `log_max_size`, rotate the log files and return the new,
+    /// empty log file.
+    fn rotate_if_needed(&self) -> Option<File> {
+        let rotation = self.rotation.as_ref()?;
+        if self.size <= rotation.max_size {
+            return None;
         }
+
+        rotate_files(&rotation.path, rotation.max_files).ok()?;
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&rotation.path)
+            .ok()
     }
+}
+
+/// Set up the logger.
+///
+/// Every log line is written using `format`, see [`format_line`].
+///
+/// If `max_size` is not 0 and the log is written to a file, the file is rotated once it grows
+/// larger than `max_size` bytes, keeping at most `max_files` old log files around.
+pub fn prepare_logger(
+    file: &impl AsRef<str>,
+    level: u8,
+    format: &str,
+    max_size: u64,
+    max_files: u32,
+) -> WmResult {
+    if level >= 3 {
+        return Err("Invalid log level: {level}".into());
+    }
+    LOG_LEVEL.store(level, Ordering::Relaxed);
+    let fname = file.as_ref();
+    let file = if fname == LF_STDOUT {
+        Some(File::from(stdout().as_fd().try_clone_to_owned()?))
+    } else if fname == LF_STDERR {
+        Some(File::from(stderr().as_fd().try_clone_to_owned()?))
+    } else {
+        open_log_file(fname).ok()
+    };
+
+    let rotation = if max_size > 0 && fname != LF_STDOUT && fname != LF_STDERR {
+        Some(Rotation {
+            path: fname.to_string(),
+            max_size,
+            max_files,
+        })
+    } else {
+        None
+    };
 
-    unsafe {
-        WRITER = writer;
+    if let Ok(mut guard) = WRITER.lock() {
+        *guard = file.map(|file| LogWriter::new(file, format, rotation));
     }
 
     Ok(())
 }
 
+/// Open a log file, appending to what has been logged into it before.
+fn open_log_file(path: &str) -> std::io::Result<File> {
+    OpenOptions::new().append(true).create(true).open(path)
+}
+
 pub fn log<T: AsRef<str> + ?Sized>(msg: &T, level: u8) -> bool {
-    // unsafe go brrrrrr lol
-    // pls don't scream at me, i know it's bad
-    unsafe {
-        if level <= LOG_LEVEL.load(Ordering::Relaxed) && level != LL_OFF {
-            if let Ok(guard) = WRITER.lock() {
-                if guard.is_some() {
-                    let fd = guard.as_ref().unwrap();
-                    if let Ok(cloned) = fd.try_clone() {
-                        let mut file = File::from(cloned);
-                        if writeln!(&mut file, "[LOG] {}", msg.as_ref()).is_ok() {
-                            file.flush().unwrap_or(());
-                        }
-                    }
-                }
-            }
-        }
+    if level <= LOG_LEVEL.load(Ordering::Relaxed) && level != LL_OFF {
+        write_line("INFO", msg.as_ref());
     }
 
     false
 }
 
 pub fn err<T: AsRef<str> + ?Sized>(msg: &T) {
-    // unsafe go brrrrrr lol
-    // pls don't scream at me, i know it's bad
-    unsafe {
-        if let Ok(guard) = WRITER.lock() {
-            if guard.is_some() {
-                let fd = guard.as_ref().unwrap();
-                if let Ok(cloned) = fd.try_clone() {
-                    let mut file = File::from(cloned);
-                    if writeln!(&mut file, "[ERROR] {}", msg.as_ref()).is_ok() {
-                        file.flush().unwrap_or(());
-                    }
-                }
-            }
+    write_line("ERROR", msg.as_ref());
+}
+
+/// Write a single line to the log.
+fn write_line(level: &str, msg: &str) {
+    if let Ok(mut guard) = WRITER.lock() {
+        if let Some(writer) = guard.as_mut() {
+            writer.write_line(level, msg, SystemTime::now());
         }
     }
 }
 
+/// Format a single log line.
+///
+/// `{time}` is replaced by an ISO-8601 UTC timestamp, `{level}` by the severity of the message,
+/// either `INFO` or `ERROR`, and `{message}` by the message itself. An empty format falls back to
+/// [`DEFAULT_LOG_FORMAT`].
+pub fn format_line(format: &str, level: &str, msg: &str, time: SystemTime) -> String {
+    let format = if format.is_empty() {
+        DEFAULT_LOG_FORMAT
+    } else {
+        format
+    };
+    let mut line = format.replace("{level}", level);
+    if line.contains("{time}") {
+        line = line.replace("{time}", &iso8601(time));
+    }
+
+    line.replace("{message}", msg)
+}
+
+/// Return the time as an ISO-8601 UTC timestamp, with a precision of one second.
+fn iso8601(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, seconds) = ((seconds / 86400) as i64, seconds % 86400);
+
+    // Convert days since the epoch into a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// Shift the log files by one: `file.1` becomes `file.2` and so on, `file` becomes `file.1`. Only
+/// `max_files` old log files are kept.
+fn rotate_files(path: &str, max_files: u32) -> std::io::Result<()> {
+    if max_files == 0 {
+        return Ok(());
+    }
+
+    for n in (1..max_files).rev() {
+        let from = format!("{path}.{n}");
+        if Path::new(&from).exists() {
+            std::fs::rename(&from, format!("{path}.{}", n + 1))?;
+        }
+    }
+
+    std::fs::rename(path, format!("{path}.1"))
+}
+
 #[macro_export]
 macro_rules! logm {
     (  $level:ident, $($arg:tt)* ) => {
@@ -109,3 +232,88 @@ macro_rules! errm {
         err(&format!($($arg)*))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_rotation() {
+        let path = std::env::temp_dir().join(format!("crubwm-log-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .unwrap();
+        let rotation = Rotation {
+            path: path.to_string(),
+            max_size: 64,
+            max_files: 2,
+        };
+        let mut writer = LogWriter::new(file, DEFAULT_LOG_FORMAT, Some(rotation));
+        for _ in 0..10 {
+            writer.write_line(
+                "INFO",
+                "a message which is long enough to fill the log",
+                UNIX_EPOCH,
+            );
+        }
+
+        assert!(Path::new(&format!("{path}.1")).exists());
+        assert!(Path::new(&format!("{path}.2")).exists());
+        assert!(!Path::new(&format!("{path}.3")).exists());
+
+        for file in [path.to_string(), format!("{path}.1"), format!("{path}.2")] {
+            std::fs::remove_file(file).unwrap_or(());
+        }
+    }
+
+    #[test]
+    fn test_log_append() {
+        let path = std::env::temp_dir().join(format!("crubwm-log-append-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "an old line\n").unwrap();
+
+        let rotation = Rotation {
+            path: path.to_string(),
+            max_size: 1024,
+            max_files: 1,
+        };
+        let mut writer = LogWriter::new(open_log_file(path).unwrap(), "{message}", Some(rotation));
+        assert_eq!(writer.size, 12);
+        writer.write_line("INFO", "a new line", UNIX_EPOCH);
+
+        let contents = std::fs::read_to_string(path).unwrap();
+        assert_eq!(contents, "an old line\na new line\n");
+        assert_eq!(writer.size, contents.len() as u64);
+        assert!(!Path::new(&format!("{path}.1")).exists());
+
+        std::fs::remove_file(path).unwrap_or(());
+    }
+
+    #[test]
+    fn test_log_format() {
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+
+        assert_eq!(
+            format_line(DEFAULT_LOG_FORMAT, "INFO", "hello", time),
+            "2023-11-14T22:13:20Z [INFO] hello"
+        );
+        assert_eq!(
+            format_line("{level}: {message}", "ERROR", "oops", time),
+            "ERROR: oops"
+        );
+        assert_eq!(
+            format_line("", "ERROR", "oops", time),
+            "2023-11-14T22:13:20Z [ERROR] oops"
+        );
+        assert_eq!(iso8601(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            iso8601(UNIX_EPOCH + std::time::Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+    }
+}
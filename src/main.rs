@@ -31,9 +31,9 @@ pub mod wm;
 
 use errors::WmResult;
 use hp::{Parser, Template};
-use log::prepare_logger;
+use log::{err, log, prepare_logger, LL_NORMAL};
 use parsers::ConfigParser;
-use wm::Wm;
+use wm::{actions::Action, layouts::LayoutType, Wm};
 
 use std::{fmt::Display, process::exit};
 
@@ -51,21 +51,96 @@ fn main() {
             .number_of_values(1)
             .optional_values(false),
     );
+    parser.add_template(
+        Template::new()
+            .matches("--profile")
+            .with_help("Apply a profile of the config file on top of the rest of the config")
+            .number_of_values(1)
+            .optional_values(false),
+    );
+    parser.add_template(
+        Template::new()
+            .matches("--list-layouts")
+            .with_help("Print the names of all the available layouts and exit")
+            .number_of_values(0),
+    );
+    parser.add_template(
+        Template::new()
+            .matches("--list-actions")
+            .with_help("Print the names of all the available keybind actions and exit")
+            .number_of_values(0),
+    );
+
+    parser.add_template(
+        Template::new()
+            .matches("--replace")
+            .with_help("Replace the window manager which is already running")
+            .number_of_values(0),
+    );
+
+    parser.add_template(
+        Template::new()
+            .matches("--check-config")
+            .with_help("Check the config file for keybinds which are bound more than once and exit")
+            .number_of_values(0),
+    );
 
     let command_line_arguments_res = parser.parse(None);
 
     if let Ok(command_line_arguments) = print_err(command_line_arguments_res) {
+        if command_line_arguments.get("--list-layouts").is_some() {
+            LayoutType::NAMES.iter().for_each(|name| println!("{name}"));
+            return;
+        }
+        if command_line_arguments.get("--list-actions").is_some() {
+            Action::NAMES.iter().for_each(|name| println!("{name}"));
+            return;
+        }
+
         if let Ok(config) = print_err(ConfigParser::parse(Some(&command_line_arguments), None)) {
+            if command_line_arguments.get("--check-config").is_some() {
+                match print_err(config.keybinds.duplicates()) {
+                    Ok(duplicates) if duplicates.is_empty() => {
+                        println!("{}: no problems found.", config.path)
+                    }
+                    Ok(duplicates) => {
+                        duplicates.iter().for_each(|d| println!("{d}"));
+                        exit(1)
+                    }
+                    Err(_) => exit(1),
+                }
+                return;
+            }
+
             if print_err(prepare_logger(
                 &config.settings.log_file,
                 config.settings.log_level,
+                &config.settings.log_format,
+                config.settings.log_max_size,
+                config.settings.log_max_files,
             ))
             .is_ok()
             {
-                if let Ok(mut wm) = print_err(Wm::new(config)) {
-                    if print_err(wm.run()).is_err() {
-                        exit(1)
+                logm!(
+                    LL_NORMAL,
+                    "Loaded config file {}: {}",
+                    config.path,
+                    config.summary()
+                );
+                for warning in &config.warnings {
+                    errm!("config warning: {warning}");
+                }
+                for duplicate in config.keybinds.duplicates().unwrap_or_default() {
+                    errm!("config warning: {duplicate}");
+                }
+                let replace = command_line_arguments.get("--replace").is_some();
+                match print_err(Wm::new(config, replace)) {
+                    Ok(mut wm) => {
+                        if print_err(wm.run()).is_err() {
+                            exit(1)
+                        }
                     }
+                    Err(_) => exit(1),
                 }
             }
         }
@@ -10,6 +10,10 @@ use crate::WmResult;
 /// The default config path is located in `~/.config/crubwm/config`
 const CONFIG_PATH: &str = ".config/crubwm/config";
 
+/// Warning returned with the default config when the default config file can't be located.
+const UNSET_HOME_WARNING: &str =
+    "$HOME is not set, unable to locate the default config file, using the default config.";
+
 /// Config file parser.
 pub struct ConfigParser;
 
@@ -19,96 +23,260 @@ impl ConfigParser {
     /// Given a list of commands already received, check whether the `--config` command has been
     /// passed and read the new path, otherwise read the default config file which is located in
     /// `~/.config/crubwm/config`.
-    pub fn parse(
-        commands: Option<&ParsedArguments>,
-        path_arg: Option<&str>,
-    ) -> WmResult<Config> {
-        let mut ret = Config::default();
-        let mut default_path = std::env::var("HOME").map_err(|_| {
-            Error::Generic("parsing error: unable to read $HOME environmental variable.".into())
-        })?;
-        default_path.push('/');
-        default_path.push_str(CONFIG_PATH);
-        let mut path = default_path.clone();
+    ///
+    /// An explicitly passed config file which does not exist is an error, while an unset `$HOME`
+    /// results in the default config being used.
+    ///
+    /// The profile passed using `--profile` is applied on top of the rest of the config.
+    pub fn parse(commands: Option<&ParsedArguments>, path_arg: Option<&str>) -> WmResult<Config> {
+        let mut explicit_path = None;
+        let mut profile = None;
 
         if let Some(arguments) = commands {
             if let Some(config_file) = arguments.get("--config") {
-                path = config_file.values()[0].clone()
+                explicit_path = Some(config_file.values()[0].clone())
+            }
+            if let Some(profile_name) = arguments.get("--profile") {
+                profile = Some(profile_name.values()[0].clone())
             }
         }
 
         if let Some(ppath) = path_arg {
-            path = ppath.to_string()
+            explicit_path = Some(ppath.to_string())
         }
 
-        if !std::path::PathBuf::from(&default_path).exists() {
-            let mut new_config_file = std::fs::File::create(default_path)?;
+        Self::parse_profile(explicit_path, profile.as_deref())
+    }
+
+    pub fn parse_with_path(path: &str) -> WmResult<Config> {
+        Self::parse(None, Some(path))
+    }
+
+    /// Parse a config file, applying the given profile on top of the rest of it.
+    pub fn parse_with_profile(path: &str, profile: Option<&str>) -> WmResult<Config> {
+        Self::parse_profile(Some(path.to_string()), profile)
+    }
+
+    fn parse_profile(explicit_path: Option<String>, profile: Option<&str>) -> WmResult<Config> {
+        let path = match explicit_path {
+            // A config file which was explicitly requested has to exist.
+            Some(path) => {
+                if !std::path::Path::new(&path).exists() {
+                    return Err(Error::Generic(format!(
+                        "parsing error: config file {path} does not exist."
+                    )));
+                }
+                path
+            }
+            // Without $HOME there is no default config file to read, so fall back to the
+            // default settings.
+            None => {
+                let default_path =
+                    match Self::default_config_path(std::env::var("HOME").ok().as_deref()) {
+                        Some(path) => path,
+                        None => {
+                            return Ok(Config {
+                                warnings: vec![UNSET_HOME_WARNING.into()],
+                                ..Default::default()
+                            });
+                        }
+                    };
+
+                if !std::path::PathBuf::from(&default_path).exists() {
+                    Self::create_config_file(&default_path, &Config::default().serialize()?)?;
+                }
+
+                default_path
+            }
+        };
+
+        let file_contents = read_to_string(&path).map_err(|e| {
+            Error::Generic(format!(
+                "parsing error: unable to read config file {path}: {e}"
+            ))
+        })?;
+
+        let mut ret = Self::parse_str_with_profile(&file_contents, profile)?;
+        ret.path = path;
+
+        Ok(ret)
+    }
+
+    /// Path of the default config file in the given home directory, if there is one.
+    fn default_config_path(home: Option<&str>) -> Option<String> {
+        home.filter(|home| !home.is_empty())
+            .map(|home| format!("{home}/{CONFIG_PATH}"))
+    }
+
+    /// Parse the contents of a config file, without touching the filesystem.
+    ///
+    /// The returned config has an empty `path`.
+    pub fn parse_str(contents: &str) -> WmResult<Config> {
+        Self::parse_str_with_profile(contents, None)
+    }
+
+    /// Parse the contents of a config file, applying the lines of the given profile's section after
+    /// all the other lines, so that they take precedence. The lines of the other profiles are only
+    /// checked for errors.
+    ///
+    /// A profile's section starts with a `profile <name>` line and ends with an `end_profile` line,
+    /// or with the start of another profile's section.
+    pub fn parse_str_with_profile(contents: &str, profile: Option<&str>) -> WmResult<Config> {
+        let mut ret = Config::default();
+        let mut section: Option<&str> = None;
+        let mut profile_found = false;
+        let mut profile_lines = Vec::new();
+
+        for (index, line) in contents.lines().enumerate() {
+            if let Some(name) = line.strip_prefix("profile ") {
+                let name = name.trim().trim_matches('"');
+                if name.is_empty() {
+                    return Err(Error::config(
+                        index + 1,
+                        line,
+                        "config parsing error: a profile needs a name".into(),
+                    ));
+                }
+                profile_found |= Some(name) == profile;
+                section = Some(name);
+                continue;
+            } else if line.trim() == "end_profile" {
+                section = None;
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            match section {
+                None => Self::parse_line(&mut ret, line, false)
+                    .map_err(|e| Error::config(index + 1, line, e))?,
+                Some(name) if Some(name) == profile => profile_lines.push((index, line)),
+                Some(_) => Self::parse_line(&mut Config::default(), line, true)
+                    .map_err(|e| Error::config(index + 1, line, e))?,
+            }
+        }
 
-            new_config_file.write_all(ret.serialize()?)?;
+        for (index, line) in profile_lines {
+            Self::parse_line(&mut ret, line, true)
+                .map_err(|e| Error::config(index + 1, line, e))?;
         }
+        if let Some(profile) = profile {
+            if !profile_found {
+                ret.warnings.push(format!("profile {profile} is not defined in the config file, using the config without a profile."));
+            }
+        }
+        ret.profile = profile.map(String::from);
+
+        Ok(ret)
+    }
 
-        let file_contents = read_to_string(&path)?;
-
-        for line in file_contents.lines() {
-            if !line.is_empty() {
-                let config_line = ConfigLine::try_from(line.to_owned())?;
-                match config_line {
-                    ConfigLine::Comment(..) => {}
-                    ConfigLine::Keybind {
-                        keys,
-                        mut action,
-                        action_arguments,
-                    } => {
+    /// Parse a single, non-empty line of a config file, adding its contents to the config.
+    ///
+    /// Keybinds of a profile replace the keybinds with the same keys without a warning.
+    fn parse_line(config: &mut Config, line: &str, in_profile: bool) -> WmResult {
+        let config_line = ConfigLine::try_from(line.to_owned())?;
+        match config_line {
+            ConfigLine::Comment(..) => {}
+            ConfigLine::Keybind { keys, actions } => {
+                let actions = actions
+                    .into_iter()
+                    .map(|(mut action, action_arguments)| {
                         action.push(' ');
                         action.push_str(&action_arguments.join(" "));
-                        ret.keybinds.add(keys, action)?
-                    }
-                    ConfigLine::Hook {
-                        hook_type,
-                        hook_args,
-                        hook_option,
-                    } => {
-                        ret.start_hooks.add(hook_type, hook_args, hook_option)?;
-                    }
-                    ConfigLine::Setting {
-                        setting_name: option_name,
-                        setting_value: option_value,
-                    } => {
-                        ret.settings.add(option_name, option_value)?;
-                    }
-                    ConfigLine::WorkspaceSetting {
-                        workspace_identifier,
-                        workspace_setting_name,
-                        workspace_setting_value,
-                    } => {
-                        ret.workspace_settings.add(
-                            workspace_identifier.parse::<u32>()?,
-                            workspace_setting_name,
-                            workspace_setting_value,
-                        )?;
-                    }
-                    ConfigLine::BarSetting {
-                        bar_identifier,
-                        bar_setting_name,
-                        bar_setting_values,
-                    } => {
-                        ret.bar_settings.add(
-                            bar_identifier.parse::<u32>()?,
-                            bar_setting_name,
-                            bar_setting_values,
-                        )?;
-                    }
+                        action
+                    })
+                    .collect();
+                if in_profile {
+                    config.keybinds.replace(keys, actions)?
+                } else {
+                    config.keybinds.add(keys, actions)?
                 }
             }
+            ConfigLine::Hook {
+                hook_type,
+                hook_args,
+                hook_option,
+            } => {
+                config.start_hooks.add(hook_type, hook_args, hook_option)?;
+            }
+            ConfigLine::Setting {
+                setting_name: option_name,
+                setting_value: option_value,
+            } => {
+                config.explicit_settings.push(option_name.clone());
+                config.settings.add(option_name, option_value)?;
+            }
+            ConfigLine::WorkspaceSetting {
+                workspace_identifier,
+                workspace_setting_name,
+                workspace_setting_value,
+            } => {
+                config.workspace_settings.add(
+                    workspace_identifier.parse::<u32>()?,
+                    workspace_setting_name,
+                    workspace_setting_value,
+                )?;
+            }
+            ConfigLine::BarSetting {
+                bar_identifier,
+                bar_setting_name,
+                bar_setting_values,
+            } => {
+                config.bar_settings.add(
+                    bar_identifier.parse::<u32>()?,
+                    bar_setting_name,
+                    bar_setting_values,
+                )?;
+            }
+            ConfigLine::Rule {
+                rule_field,
+                rule_value,
+                rule_effects,
+            } => {
+                config.rules.add(rule_field, rule_value, rule_effects)?;
+            }
+            ConfigLine::MonitorSetting {
+                monitor_name,
+                monitor_setting_name,
+                monitor_setting_values,
+            } => {
+                config.monitor_settings.add(
+                    monitor_name,
+                    monitor_setting_name,
+                    monitor_setting_values,
+                )?;
+            }
         }
 
-        ret.path = path;
-
-        Ok(ret)
+        Ok(())
     }
 
-    pub fn parse_with_path(path: &str) -> WmResult<Config> {
-        Self::parse(None, Some(path))
+    /// Atomically create a new config file with the given contents.
+    ///
+    /// The contents are first written to a temporary file next to `path`, which is then linked
+    /// into place. Linking fails if `path` already exists, so an existing config is never
+    /// overwritten and an interrupted write never leaves a truncated config behind.
+    fn create_config_file(path: &str, contents: &[u8]) -> WmResult {
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let tmp_path = format!("{path}.{}.tmp", std::process::id());
+        let result = (|| -> WmResult {
+            let mut tmp_file = std::fs::File::create(&tmp_path)?;
+            tmp_file.write_all(contents)?;
+            tmp_file.sync_all()?;
+
+            match std::fs::hard_link(&tmp_path, path) {
+                Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => Err(e.into()),
+                _ => Ok(()),
+            }
+        })();
+
+        std::fs::remove_file(&tmp_path).unwrap_or(());
+
+        result
     }
 }
 
@@ -122,10 +290,9 @@ enum ConfigLine {
     Keybind {
         /// A string which represents one or multiple keys, to which we want to bind to
         keys: String,
-        /// The action which we are binding
-        action: String,
-        /// Different arguments, depending on action.
-        action_arguments: Vec<String>,
+        /// The actions which we are binding, each with its arguments. Actions are separated by
+        /// commas.
+        actions: Vec<(String, Vec<String>)>,
     },
     /// Representation of a hook.
     Hook {
@@ -153,6 +320,21 @@ enum ConfigLine {
         bar_setting_name: String,
         bar_setting_values: Vec<String>,
     },
+    /// A setting of a monitor, given by its name.
+    MonitorSetting {
+        monitor_name: String,
+        monitor_setting_name: String,
+        monitor_setting_values: Vec<String>,
+    },
+    /// A window rule, applied to new windows matching it.
+    Rule {
+        /// What part of the window's class is matched
+        rule_field: String,
+        /// The value it has to match
+        rule_value: String,
+        /// What happens to matching windows, as pairs of names and values
+        rule_effects: Vec<String>,
+    },
 }
 
 impl TryFrom<String> for ConfigLine {
@@ -160,13 +342,24 @@ impl TryFrom<String> for ConfigLine {
 
     fn try_from(line: String) -> WmResult<Self> {
         if let Some(s) = line.strip_prefix("keybind ") {
-            let rest_of_line = s;
-            let parser = LineParser::parse(rest_of_line.to_string());
+            let mut segments = LineParser::split(s, ',').into_iter();
+            let parser = LineParser::parse(segments.next().unwrap_or_default());
+            if parser.0.len() < 2 {
+                return Err("config parsing error: a keybind needs keys and an action".into());
+            }
+            let mut actions = vec![(parser.0[1].clone(), parser.0[2..].to_vec())];
+
+            for segment in segments {
+                let action_parser = LineParser::parse(segment);
+                if action_parser.0.is_empty() {
+                    return Err("config parsing error: empty action in a keybind".into());
+                }
+                actions.push((action_parser.0[0].clone(), action_parser.0[1..].to_vec()));
+            }
 
             return Ok(Self::Keybind {
                 keys: parser.0[0].clone(),
-                action: parser.0[1].clone(),
-                action_arguments: parser.0[2..].to_vec(),
+                actions,
             });
         } else if let Some(s) = line.strip_prefix("set ") {
             let rest_of_line = s;
@@ -203,6 +396,33 @@ impl TryFrom<String> for ConfigLine {
                 bar_setting_name: parser.0[1].clone(),
                 bar_setting_values: parser.0[2..].to_vec(),
             });
+        } else if let Some(s) = line.strip_prefix("monitor_set ") {
+            let parser = LineParser::parse(s.to_string());
+            if parser.0.len() < 2 {
+                return Err(
+                    "config parsing error: a monitor setting needs a monitor name and a setting"
+                        .into(),
+                );
+            }
+
+            return Ok(Self::MonitorSetting {
+                monitor_name: parser.0[0].clone(),
+                monitor_setting_name: parser.0[1].clone(),
+                monitor_setting_values: parser.0[2..].to_vec(),
+            });
+        } else if let Some(s) = line.strip_prefix("rule ") {
+            let parser = LineParser::parse(s.to_string());
+            if parser.0.len() < 2 {
+                return Err(
+                    "config parsing error: a rule needs a field and a value to match".into(),
+                );
+            }
+
+            return Ok(Self::Rule {
+                rule_field: parser.0[0].clone(),
+                rule_value: parser.0[1].clone(),
+                rule_effects: parser.0[2..].to_vec(),
+            });
         } else if line.starts_with('#') {
             return Ok(Self::Comment(line));
         }
@@ -238,11 +458,11 @@ impl LineParser {
                 } else {
                     buffer.push(current)
                 }
-            } else if current == '\\' {
-                in_escape = true
-            } else if in_escape && current == '"' {
+            } else if in_escape {
                 buffer.push(current);
                 in_escape = false
+            } else if current == '\\' {
+                in_escape = true
             } else if current == '"' {
                 in_str = false;
                 string_list.push(buffer.clone());
@@ -257,4 +477,331 @@ impl LineParser {
         }
         Self(string_list)
     }
+
+    /// Split a line on every `separator` which is not inside of a string.
+    fn split(input: &str, separator: char) -> Vec<String> {
+        let mut in_str = false;
+        let mut in_escape = false;
+        let mut ret = vec![String::new()];
+
+        for current in input.chars() {
+            if in_escape {
+                in_escape = false
+            } else if in_str && current == '\\' {
+                in_escape = true
+            } else if current == '"' {
+                in_str = !in_str
+            } else if !in_str && current == separator {
+                ret.push(String::new());
+                continue;
+            }
+
+            if let Some(last) = ret.last_mut() {
+                last.push(current)
+            }
+        }
+
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_config_path() {
+        let path = "/nonexistent/crubwm/config";
+        let result = ConfigParser::parse_with_path(path);
+
+        match result {
+            Err(Error::Generic(msg)) => assert!(msg.contains(path)),
+            _ => panic!("expected an error naming the missing config file"),
+        }
+    }
+
+    #[test]
+    fn test_unset_home() {
+        assert_eq!(ConfigParser::default_config_path(None), None);
+        assert_eq!(ConfigParser::default_config_path(Some("")), None);
+        assert_eq!(
+            ConfigParser::default_config_path(Some("/home/user")).as_deref(),
+            Some("/home/user/.config/crubwm/config")
+        );
+    }
+
+    #[test]
+    fn test_config_error_location() {
+        let result = ConfigParser::parse_str("# comment\nset border maybe\n");
+
+        match result {
+            Err(Error::ConfigError { line, text, .. }) => {
+                assert_eq!(line, 2);
+                assert_eq!(text, "set border maybe");
+            }
+            _ => panic!("expected a config error"),
+        }
+    }
+
+    #[test]
+    fn test_parse_comment() {
+        let config = ConfigParser::parse_str("# keybind \"<Mod>t\" execute firefox\n\n").unwrap();
+        assert_eq!(config.keybinds.len(), Config::default().keybinds.len());
+        assert!(config.explicit_settings.is_empty());
+        assert!(config.path.is_empty());
+    }
+
+    #[test]
+    fn test_parse_keybind() {
+        let config = ConfigParser::parse_str("keybind \"<Mod>t\" execute firefox").unwrap();
+        assert_eq!(config.keybinds.len(), Config::default().keybinds.len() + 1);
+    }
+
+    #[test]
+    fn test_parse_multi_action_keybind() {
+        let line = ConfigLine::try_from(
+            "keybind \"<Mod>t\" goto 2, execute \"notify-send a,b\"".to_string(),
+        )
+        .unwrap();
+
+        match line {
+            ConfigLine::Keybind { keys, actions } => {
+                assert_eq!(keys, "<Mod>t");
+                assert_eq!(
+                    actions,
+                    vec![
+                        ("goto".to_string(), vec!["2".to_string()]),
+                        ("execute".to_string(), vec!["notify-send a,b".to_string()])
+                    ]
+                );
+            }
+            _ => panic!("expected a keybind"),
+        }
+
+        let config = ConfigParser::parse_str("keybind \"<Mod>t\" goto 2, kill").unwrap();
+        assert_eq!(config.keybinds.len(), Config::default().keybinds.len() + 1);
+
+        assert!(ConfigLine::try_from("keybind \"<Mod>t\"".to_string()).is_err());
+        assert!(ConfigParser::parse_str("keybind \"<Mod>t\" goto, kill").is_err());
+    }
+
+    #[test]
+    fn test_parse_setting() {
+        let config = ConfigParser::parse_str("set border false\nset border_size 3").unwrap();
+        assert!(!config.settings.border);
+        assert_eq!(config.settings.border_size, 3);
+        assert_eq!(config.explicit_settings, vec!["border", "border_size"]);
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let contents = "set border_size 3\n\
+            keybind \"<Mod>t\" goto 2\n\
+            profile docked\n\
+            set border_size 5\n\
+            keybind \"<Mod>t\" kill\n\
+            keybind \"<Mod>y\" kill\n\
+            end_profile\n\
+            profile mobile\n\
+            set border false\n\
+            keybind \"<Mod>i\" kill\n\
+            end_profile\n\
+            set outer_gap_size 4";
+
+        let base = ConfigParser::parse_str(contents).unwrap();
+        assert_eq!(base.settings.border_size, 3);
+        assert_eq!(base.settings.outer_gap_size, 4);
+        assert!(base.settings.border);
+        assert_eq!(base.keybinds.len(), Config::default().keybinds.len() + 1);
+        assert_eq!(base.profile, None);
+
+        let docked = ConfigParser::parse_str_with_profile(contents, Some("docked")).unwrap();
+        assert_eq!(docked.settings.border_size, 5);
+        assert_eq!(docked.settings.outer_gap_size, 4);
+        assert!(docked.settings.border);
+        assert_eq!(docked.keybinds.len(), Config::default().keybinds.len() + 2);
+        assert!(docked.keybinds.duplicates().unwrap().is_empty());
+        assert_eq!(docked.profile.as_deref(), Some("docked"));
+        assert!(docked.warnings.is_empty());
+
+        let mobile = ConfigParser::parse_str_with_profile(contents, Some("mobile")).unwrap();
+        assert_eq!(mobile.settings.border_size, 3);
+        assert!(!mobile.settings.border);
+        assert_eq!(mobile.keybinds.len(), Config::default().keybinds.len() + 2);
+
+        let missing = ConfigParser::parse_str_with_profile(contents, Some("desk")).unwrap();
+        assert_eq!(missing.settings.border_size, 3);
+        assert_eq!(missing.warnings.len(), 1);
+        assert!(missing.warnings[0].starts_with("profile desk is not defined"));
+
+        // lines of profiles which are not selected are still checked
+        assert!(ConfigParser::parse_str("profile docked\nset border maybe").is_err());
+        assert!(ConfigParser::parse_str("profile \"\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_hook() {
+        let config = ConfigParser::parse_str("hook startup async \"echo hi\"").unwrap();
+        assert_eq!(config.start_hooks.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_workspace_setting() {
+        let config = ConfigParser::parse_str("workspace_set 1 name web").unwrap();
+        assert_eq!(config.workspace_settings.get(1).unwrap().name, "web");
+    }
+
+    #[test]
+    fn test_parse_master_ratio() {
+        let config = ConfigParser::parse_str("workspace_set 2 master_ratio 0.6").unwrap();
+        assert_eq!(config.workspace_settings.get(2).unwrap().master_ratio, 0.6);
+        for ratio in ["NaN", "inf", "0", "1", "-0.5", "1.5", "half"] {
+            assert!(
+                ConfigParser::parse_str(&format!("workspace_set 2 master_ratio {ratio}")).is_err(),
+                "{ratio}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_workspace_background() {
+        let config = ConfigParser::parse_str("workspace_set 3 background \"#223344\"").unwrap();
+        assert_eq!(
+            config.workspace_settings.get(3).unwrap().background,
+            "#223344"
+        );
+        assert!(ConfigParser::parse_str("workspace_set 3 background blue").is_err());
+    }
+
+    #[test]
+    fn test_parse_bar_setting() {
+        let config = ConfigParser::parse_str("bar_set 0 monitor 1").unwrap();
+        assert_eq!(config.bar_settings.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_bar_position() {
+        let config = ConfigParser::parse_str("bar_set 0 position bottom").unwrap();
+        let serialized = String::from_utf8(config.serialize().unwrap()).unwrap();
+        assert!(serialized.contains("bar_set 0 position bottom"));
+        assert!(ConfigParser::parse_str("bar_set 0 location top").is_ok());
+        assert!(ConfigParser::parse_str("bar_set 0 position left").is_err());
+    }
+
+    #[test]
+    fn test_parse_widget_source() {
+        let config = ConfigParser::parse_str(
+            "bar_set 0 segment add widget w right\n\
+             bar_set 0 widget add w battery source battery update_time 60",
+        )
+        .unwrap();
+        let serialized = String::from_utf8(config.serialize().unwrap()).unwrap();
+        assert!(serialized.contains("source battery"));
+        assert!(ConfigParser::parse_str(
+            "bar_set 0 segment add widget w right\n\
+             bar_set 0 widget add w battery source acpi"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_widget_icon_image() {
+        assert!(ConfigParser::parse_str(
+            "bar_set 0 segment add widget w right\n\
+             bar_set 0 widget add w clock icon file:/usr/share/icons/clock.png"
+        )
+        .is_ok());
+        assert!(ConfigParser::parse_str(
+            "bar_set 0 segment add widget w right\n\
+             bar_set 0 widget add w clock icon file:/usr/share/icons/clock.svg"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_monitor_setting() {
+        let config = ConfigParser::parse_str("monitor_set HDMI-1 padding 20 20 40 40").unwrap();
+        assert_eq!(
+            config.monitor_settings.get("HDMI-1").unwrap().padding,
+            crate::config::MonitorPadding {
+                top: 20,
+                bottom: 20,
+                left: 40,
+                right: 40
+            }
+        );
+        assert!(config.monitor_settings.get("DP-1").is_none());
+        assert!(ConfigParser::parse_str("monitor_set HDMI-1 padding 20 20").is_err());
+        assert!(ConfigParser::parse_str("monitor_set HDMI-1 padding 20 20 -4 0").is_err());
+        assert!(ConfigParser::parse_str("monitor_set HDMI-1 overscan 20").is_err());
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let config = ConfigParser::parse_str(
+            "rule class firefox workspace 2\nrule instance \"discord\" workspace 9 float true",
+        )
+        .unwrap();
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(
+            config
+                .rules
+                .effects_for(Some("discord"), None, None)
+                .workspace,
+            Some(9)
+        );
+        assert!(ConfigParser::parse_str("rule class").is_err());
+        assert!(ConfigParser::parse_str("rule class firefox workspace").is_err());
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let serialized = Config::default().serialize().unwrap();
+        let config = ConfigParser::parse_str(std::str::from_utf8(&serialized).unwrap()).unwrap();
+        assert_eq!(config.serialize().unwrap(), serialized);
+        assert_eq!(config.keybinds.len(), Config::default().keybinds.len());
+
+        let config = ConfigParser::parse_str(
+            "set border_color \"#112233\"\n\
+             set keyboard_layouts \"us,ru\"\n\
+             hook startup async \"echo \\\"hi\\\"\"\n\
+             workspace_set 2 name \"the web\"\n\
+             workspace_set 2 background \"#223344\"\n\
+             bar_set 0 segment add workspace ws left\n\
+             bar_set 0 workspace set ws format \" {name} \"\n\
+             bar_set 0 segment add widget w right\n\
+             bar_set 0 widget add w clock command date +%H:%M update_time 60\n\
+             bar_set 0 location bottom\n\
+             monitor_set HDMI-1 padding 20 20 40 40\n\
+             rule instance discord workspace 9 float true\n\
+             rule title \"Volume Control\" geometry 800x600-10+0",
+        )
+        .unwrap();
+        let serialized = config.serialize().unwrap();
+        let reparsed = ConfigParser::parse_str(std::str::from_utf8(&serialized).unwrap()).unwrap();
+
+        assert_eq!(reparsed.serialize().unwrap(), serialized);
+        assert_eq!(reparsed.settings.keyboard_layouts, "us,ru");
+        assert_eq!(reparsed.workspace_settings.get(2).unwrap().name, "the web");
+        assert_eq!(reparsed.start_hooks.len(), 1);
+        assert_eq!(reparsed.bar_settings.len(), 1);
+        assert_eq!(reparsed.monitor_settings.len(), 1);
+        assert_eq!(reparsed.rules.len(), 2);
+    }
+
+    #[test]
+    fn test_create_config_file() {
+        let dir = std::env::temp_dir().join(format!("crubwm-test-{}", std::process::id()));
+        let path = dir.join("config");
+        let path = path.to_str().unwrap();
+
+        ConfigParser::create_config_file(path, b"set border true\n").unwrap();
+        // An already existing config is left untouched.
+        ConfigParser::create_config_file(path, b"").unwrap();
+
+        assert_eq!(read_to_string(path).unwrap(), "set border true\n");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
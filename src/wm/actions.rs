@@ -1,12 +1,24 @@
 use crate::{
     config::Repr,
     errors::{Error, WmResult},
+    wm::geometry::Geometry,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Next,
     Previous,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    /// Is the direction based on the on-screen position of windows, rather than their order?
+    pub fn is_spatial(&self) -> bool {
+        matches!(self, Self::Left | Self::Right | Self::Up | Self::Down)
+    }
 }
 
 impl Repr for Direction {
@@ -14,6 +26,10 @@ impl Repr for Direction {
         match &self {
             Self::Next => Ok("next".to_string()),
             Self::Previous => Ok("previous".to_string()),
+            Self::Left => Ok("left".to_string()),
+            Self::Right => Ok("right".to_string()),
+            Self::Up => Ok("up".to_string()),
+            Self::Down => Ok("down".to_string()),
         }
     }
 }
@@ -25,6 +41,10 @@ impl TryFrom<&str> for Direction {
         let direction = match s {
             "next" => Self::Next,
             "previous" => Self::Previous,
+            "left" => Self::Left,
+            "right" => Self::Right,
+            "up" => Self::Up,
+            "down" => Self::Down,
             _ => return Err("not a valid direction".into()),
         };
 
@@ -32,14 +52,79 @@ impl TryFrom<&str> for Direction {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// A corner of the screen, which a window can be pinned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    /// Return the geometry of a window of the given size, placed in this corner of `screen`.
+    pub fn geometry(&self, screen: Geometry, width: u16, height: u16) -> Geometry {
+        let width = width.min(screen.width);
+        let height = height.min(screen.height);
+        let right = screen.x + (screen.width - width) as i16;
+        let bottom = screen.y + (screen.height - height) as i16;
+
+        let (x, y) = match self {
+            Self::TopLeft => (screen.x, screen.y),
+            Self::TopRight => (right, screen.y),
+            Self::BottomLeft => (screen.x, bottom),
+            Self::BottomRight => (right, bottom),
+        };
+
+        Geometry {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+impl Repr for Corner {
+    fn repr(&self) -> WmResult<String> {
+        match &self {
+            Self::TopLeft => Ok("top_left".to_string()),
+            Self::TopRight => Ok("top_right".to_string()),
+            Self::BottomLeft => Ok("bottom_left".to_string()),
+            Self::BottomRight => Ok("bottom_right".to_string()),
+        }
+    }
+}
+
+impl TryFrom<&str> for Corner {
+    type Error = Error;
+
+    fn try_from(s: &str) -> WmResult<Self> {
+        let corner = match s {
+            "top_left" => Self::TopLeft,
+            "top_right" => Self::TopRight,
+            "bottom_left" => Self::BottomLeft,
+            "bottom_right" => Self::BottomRight,
+            _ => return Err("not a valid corner".into()),
+        };
+
+        Ok(corner)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Action {
     /// A noop, doesn't do anything.
     Noop,
     /// Run a system command.
     Execute(String),
+    /// Run a system command and put its first window onto the workspace with the given ID,
+    /// without switching to it.
+    ExecuteOn(usize, String),
     /// Kill currently focused window.
     Kill,
+    /// Close the focused window, escalating to SIGTERM and SIGKILL if it doesn't close in time.
+    KillUnresponsive,
     /// Switch focus to a workspace, given its ID.
     Goto(usize),
     /// Move currently focused window to a given workspace ID.
@@ -52,15 +137,127 @@ pub enum Action {
     ChangeLayout(String),
     /// Cycle layouts for the currently focused workspace.
     CycleLayout,
+    /// Make the master window of the focused workspace take a larger part of the screen.
+    IncreaseMasterRatio,
+    /// Make the master window of the focused workspace take a smaller part of the screen.
+    DecreaseMasterRatio,
+    /// Change the number of windows in the master area of the focused workspace by the given
+    /// amount.
+    Nmaster(i32),
     /// Toggle the currently focused window in and out of floating.
     ToggleFloat,
+    /// Toggle whether the currently focused window is shown on all workspaces.
+    ToggleSticky,
     /// Swap two clients.
+    ///
+    /// `Next` and `Previous` swap with the neighbouring client in the list, while `Left`, `Right`,
+    /// `Up` and `Down` swap with the closest client in that direction on the screen.
     Swap(Direction),
     /// Reload a configuration file
     ReloadConfig,
+    /// Grow or shrink the bar on the focused monitor by a number of pixels.
+    BarHeight(i32),
+    /// Move the focused window to the open workspace of the next or previous monitor.
+    MoveToMonitor(Direction),
+    /// Move the focused window to the open workspace of the next or previous monitor and focus
+    /// it there.
+    MoveToMonitorFollow(Direction),
+    /// Float the focused window, keep it above the others and pin it to a corner of the screen, or
+    /// unpin it, if no corner is given.
+    Pin(Option<Corner>),
+    /// Float the focused window and center it, sized to fractions of the screen's width and
+    /// height.
+    FloatResize(f32, f32),
+    /// Switch between the configured gaps and no gaps at all.
+    ToggleGaps,
+    /// Switch to the next keyboard layout from the `keyboard_layouts` setting.
+    SwitchLayout,
+    /// Show only the focused window on the focused workspace, or restore the previous layout.
+    FocusMode,
+    /// Shift focus to the next or previous floating window, raising it.
+    FocusFloating(Direction),
+    /// Shift focus to the next or previous tiled window.
+    FocusTiled(Direction),
+    /// Focus the next or previous monitor, along with the workspace last focused on it.
+    FocusMonitor(Direction),
+    /// Focus the previously focused window, even if it's on another workspace.
+    FocusLastWindow,
+    /// Make the focused window cover its whole monitor, or restore it.
+    ToggleFullscreen,
+    /// Put the focused floating window on top of the other windows.
+    Raise,
+    /// Put the focused floating window below the other windows.
+    Lower,
+    /// Focus the next workspace of the focused monitor.
+    NextWorkspace,
+    /// Focus the previous workspace of the focused monitor.
+    PrevWorkspace,
+    /// Cancel the operation in progress, such as dragging or resizing a window with the pointer.
+    Abort,
+    /// Turn "do not disturb" mode on or off for the focused workspace.
+    ToggleDnd,
+    /// Move the focused floating window by a number of pixels horizontally and vertically.
+    MoveFloating(i16, i16),
+    /// Grow or shrink the focused floating window by a number of pixels horizontally and
+    /// vertically.
+    ResizeFloating(i16, i16),
+    /// Move the focused floating window to the middle of the monitor it's on.
+    CenterFloating,
+    /// Put the workspaces back onto the monitors from their settings.
+    RebalanceMonitors,
+    /// Move every window with the given `WM_CLASS` class onto the focused workspace.
+    Gather(String),
+    /// Start the configured terminal as a floating window, centered at the pointer.
+    SpawnFloatTerminal,
 }
 
 impl Action {
+    /// Names of all the actions which can be used in keybinds.
+    pub const NAMES: &'static [&'static str] = &[
+        "noop",
+        "execute",
+        "execute_on",
+        "kill",
+        "kill_unresponsive",
+        "goto",
+        "move",
+        "focus",
+        "change_layout",
+        "cycle_layout",
+        "increase_master_ratio",
+        "decrease_master_ratio",
+        "nmaster",
+        "toggle_float",
+        "toggle_sticky",
+        "swap",
+        "reload_config",
+        "bar_height",
+        "move_to_monitor",
+        "move_to_monitor_follow",
+        "pin",
+        "float_resize",
+        "toggle_gaps",
+        "switch_layout",
+        "focus_mode",
+        "focus_floating",
+        "focus_tiled",
+        "focus_monitor",
+        "focus_last_window",
+        "toggle_fullscreen",
+        "raise",
+        "lower",
+        "next_workspace",
+        "prev_workspace",
+        "abort",
+        "toggle_dnd",
+        "move_float",
+        "resize_float",
+        "center_float",
+        "rebalance_monitors",
+        "gather",
+        "spawn_float_terminal",
+    ];
+
     /// Attetmpt to parse a string into an `Action`.
     ///
     /// More about this can be found in the `config` and `parsers` section of the documentation.
@@ -71,20 +268,30 @@ impl Action {
             let parts = s.split(' ').collect::<Vec<&str>>();
             let action = match parts[0] {
                 "noop" => Action::Noop,
-                "execute" | "exec" => {
+                // the command is kept as it is, including its quotes and spaces, for the shell
+                "execute" | "exec" => Action::Execute(parts[1..].join(" ")),
+                "execute_on" => {
                     let rest = &parts[1..];
-                    let mut buff = String::new();
-
-                    for each in rest {
-                        buff.push_str(each)
+                    if rest.len() < 2 {
+                        return Err(format!(
+                            "action parsing error: Action takes a workspace and a command {s}"
+                        )
+                        .into());
+                    }
+                    match rest[0].parse::<usize>() {
+                        Ok(n) => Action::ExecuteOn(n, rest[1..].join(" ")),
+                        Err(_) => {
+                            return Err(format!(
+                                "action paring error: Argument must be a number {s}"
+                            )
+                            .into())
+                        }
                     }
-
-                    Action::Execute(buff)
                 }
                 "kill" => Action::Kill,
                 "goto" => {
                     let rest = &parts[1..];
-                    if rest.len() > 1 {
+                    if rest.len() != 1 {
                         return Err(format!(
                             "action parsing error: Action takes exactly one argument {s}"
                         )
@@ -103,7 +310,7 @@ impl Action {
                 }
                 "move" => {
                     let rest = &parts[1..];
-                    if rest.len() > 1 {
+                    if rest.len() != 1 {
                         return Err(format!(
                             "action parsing error: Action takes exactly one argument {s}"
                         )
@@ -122,20 +329,20 @@ impl Action {
                 }
                 "focus" => {
                     let rest = &parts[1..];
-                    if rest.len() > 1 {
+                    if rest.len() != 1 {
                         return Err(format!(
                             "action parsing error: Action takes exactly one argument {s}"
                         )
                         .into());
                     } else {
-                        let direction = rest[0].try_into();
-                        if let Ok(dir) = direction {
-                            Action::Focus(dir)
-                        } else {
-                            return Err(format!(
-                                "action paring error: Argument must be a number {s}"
-                            )
-                            .into());
+                        match Direction::try_from(rest[0]) {
+                            Ok(dir) => Action::Focus(dir),
+                            _ => {
+                                return Err(format!(
+                                    "action paring error: Argument must be a direction {s}"
+                                )
+                                .into())
+                            }
                         }
                     }
                 }
@@ -155,9 +362,45 @@ impl Action {
                 }
                 "cycle_layout" => Action::CycleLayout,
                 "toggle_float" => Action::ToggleFloat,
+                "toggle_sticky" => Action::ToggleSticky,
+                "toggle_gaps" => Action::ToggleGaps,
+                "increase_master_ratio" => Action::IncreaseMasterRatio,
+                "decrease_master_ratio" => Action::DecreaseMasterRatio,
+                "kill_unresponsive" => Action::KillUnresponsive,
+                "switch_layout" => Action::SwitchLayout,
+                "focus_mode" => Action::FocusMode,
+                "focus_last_window" => Action::FocusLastWindow,
+                "toggle_fullscreen" => Action::ToggleFullscreen,
+                "raise" => Action::Raise,
+                "lower" => Action::Lower,
+                "next_workspace" => Action::NextWorkspace,
+                "prev_workspace" => Action::PrevWorkspace,
+                "abort" => Action::Abort,
+                "toggle_dnd" => Action::ToggleDnd,
+                "center_float" => Action::CenterFloating,
+                "rebalance_monitors" => Action::RebalanceMonitors,
+                "spawn_float_terminal" => Action::SpawnFloatTerminal,
+                "gather" => {
+                    let rest = &parts[1..];
+                    if rest.len() < 2 || rest[0] != "class" {
+                        return Err(format!(
+                            "action parsing error: Action takes the word class and a window class {s}"
+                        )
+                        .into());
+                    }
+                    // the class may be quoted, since it can contain spaces
+                    let class = rest[1..].join(" ");
+                    let class = class.trim_matches('"');
+                    if class.is_empty() {
+                        return Err(
+                            format!("action parsing error: The window class is empty {s}").into(),
+                        );
+                    }
+                    Action::Gather(class.to_string())
+                }
                 "swap" => {
                     let rest = &parts[1..];
-                    if rest.len() > 1 {
+                    if rest.len() != 1 {
                         return Err(format!(
                             "action parsing error: Action takes exactly one argument {s}"
                         )
@@ -175,7 +418,145 @@ impl Action {
                     }
                 }
                 "reload_config" => Action::ReloadConfig,
-                a => return Err(format!("action parsing error: Unknown action {a}!").into()),
+                "bar_height" => {
+                    let rest = &parts[1..];
+                    if rest.len() != 1 {
+                        return Err(format!(
+                            "action parsing error: Action takes exactly one argument {s}"
+                        )
+                        .into());
+                    } else if let Ok(n) = rest[0].parse::<i32>() {
+                        Action::BarHeight(n)
+                    } else {
+                        return Err(
+                            format!("action paring error: Argument must be a number {s}").into(),
+                        );
+                    }
+                }
+                "nmaster" => {
+                    let rest = &parts[1..];
+                    if rest.len() != 1 {
+                        return Err(format!(
+                            "action parsing error: Action takes exactly one argument {s}"
+                        )
+                        .into());
+                    } else if let Ok(n) = rest[0].parse::<i32>() {
+                        Action::Nmaster(n)
+                    } else {
+                        return Err(
+                            format!("action paring error: Argument must be a number {s}").into(),
+                        );
+                    }
+                }
+                "focus_floating" | "focus_tiled" | "focus_monitor" => {
+                    let rest = &parts[1..];
+                    if rest.len() != 1 {
+                        return Err(format!(
+                            "action parsing error: Action takes exactly one argument {s}"
+                        )
+                        .into());
+                    }
+                    match Direction::try_from(rest[0]) {
+                        Ok(dir) if !dir.is_spatial() && parts[0] == "focus_floating" => {
+                            Action::FocusFloating(dir)
+                        }
+                        Ok(dir) if !dir.is_spatial() && parts[0] == "focus_monitor" => {
+                            Action::FocusMonitor(dir)
+                        }
+                        Ok(dir) if !dir.is_spatial() => Action::FocusTiled(dir),
+                        _ => {
+                            return Err(format!(
+                                "action paring error: Argument must be either next or previous {s}"
+                            )
+                            .into())
+                        }
+                    }
+                }
+                "move_to_monitor" | "move_to_monitor_follow" => {
+                    let rest = &parts[1..];
+                    if rest.len() != 1 {
+                        return Err(format!(
+                            "action parsing error: Action takes exactly one argument {s}"
+                        )
+                        .into());
+                    }
+                    match Direction::try_from(rest[0]) {
+                        Ok(dir) if !dir.is_spatial() && parts[0] == "move_to_monitor" => {
+                            Action::MoveToMonitor(dir)
+                        }
+                        Ok(dir) if !dir.is_spatial() => Action::MoveToMonitorFollow(dir),
+                        _ => {
+                            return Err(format!(
+                                "action paring error: Argument must be either next or previous {s}"
+                            )
+                            .into())
+                        }
+                    }
+                }
+                "pin" => {
+                    let rest = &parts[1..];
+                    if rest.len() != 1 {
+                        return Err(format!(
+                            "action parsing error: Action takes exactly one argument {s}"
+                        )
+                        .into());
+                    } else if rest[0] == "none" {
+                        Action::Pin(None)
+                    } else if let Ok(corner) = Corner::try_from(rest[0]) {
+                        Action::Pin(Some(corner))
+                    } else {
+                        return Err(format!(
+                            "action paring error: Argument must be a corner or none {s}"
+                        )
+                        .into());
+                    }
+                }
+                "float_resize" => {
+                    let rest = &parts[1..];
+                    if rest.len() != 2 {
+                        return Err(format!(
+                            "action parsing error: Action takes exactly two arguments {s}"
+                        )
+                        .into());
+                    }
+                    match (rest[0].parse::<f32>(), rest[1].parse::<f32>()) {
+                        (Ok(w), Ok(h)) if w > 0. && w <= 1. && h > 0. && h <= 1. => {
+                            Action::FloatResize(w, h)
+                        }
+                        _ => {
+                            return Err(format!(
+                                "action paring error: Arguments must be numbers between 0 and 1 {s}"
+                            )
+                            .into())
+                        }
+                    }
+                }
+                "move_float" | "resize_float" => {
+                    let rest = &parts[1..];
+                    if rest.len() != 2 {
+                        return Err(format!(
+                            "action parsing error: Action takes exactly two arguments {s}"
+                        )
+                        .into());
+                    }
+                    match (rest[0].parse::<i16>(), rest[1].parse::<i16>()) {
+                        (Ok(x), Ok(y)) if parts[0] == "move_float" => Action::MoveFloating(x, y),
+                        (Ok(w), Ok(h)) => Action::ResizeFloating(w, h),
+                        _ => {
+                            return Err(format!(
+                                "action paring error: Arguments must be numbers of pixels {s}"
+                            )
+                            .into())
+                        }
+                    }
+                }
+                a => {
+                    return Err(format!(
+                        "action parsing error: Unknown action {a}! Valid actions are: {}",
+                        Self::NAMES.join(", ")
+                    )
+                    .into())
+                }
             };
 
             Ok(action)
@@ -190,13 +571,47 @@ impl Repr for Action {
             &Self::Noop => Ok("noop".to_string()),
             &Self::Kill => Ok("kill".to_string()),
             Self::Execute(command) => Ok(format!("execute {command}")),
+            Self::ExecuteOn(workspace, command) => Ok(format!("execute_on {workspace} {command}")),
             &Self::Move(workspace) => Ok(format!("move {workspace}")),
             &Self::Focus(direction) => Ok(format!("focus {}", direction.repr()?)),
             &Self::ToggleFloat => Ok("toggle_float".to_string()),
+            &Self::ToggleSticky => Ok("toggle_sticky".to_string()),
             &Self::CycleLayout => Ok("cycle_layout".to_string()),
             Self::ChangeLayout(name) => Ok(format!("change_layout {name}")),
             &Self::Swap(direction) => Ok(format!("swap {}", direction.repr()?)),
             &Self::ReloadConfig => Ok("reload_config".to_string()),
+            &Self::BarHeight(delta) => Ok(format!("bar_height {delta:+}")),
+            &Self::MoveToMonitor(direction) => Ok(format!("move_to_monitor {}", direction.repr()?)),
+            &Self::MoveToMonitorFollow(direction) => {
+                Ok(format!("move_to_monitor_follow {}", direction.repr()?))
+            }
+            &Self::Pin(Some(corner)) => Ok(format!("pin {}", corner.repr()?)),
+            &Self::Pin(None) => Ok("pin none".to_string()),
+            &Self::FloatResize(w, h) => Ok(format!("float_resize {w} {h}")),
+            &Self::ToggleGaps => Ok("toggle_gaps".to_string()),
+            &Self::IncreaseMasterRatio => Ok("increase_master_ratio".to_string()),
+            &Self::DecreaseMasterRatio => Ok("decrease_master_ratio".to_string()),
+            &Self::Nmaster(delta) => Ok(format!("nmaster {delta:+}")),
+            &Self::KillUnresponsive => Ok("kill_unresponsive".to_string()),
+            &Self::SwitchLayout => Ok("switch_layout".to_string()),
+            &Self::FocusMode => Ok("focus_mode".to_string()),
+            &Self::FocusFloating(direction) => Ok(format!("focus_floating {}", direction.repr()?)),
+            &Self::FocusTiled(direction) => Ok(format!("focus_tiled {}", direction.repr()?)),
+            &Self::FocusMonitor(direction) => Ok(format!("focus_monitor {}", direction.repr()?)),
+            &Self::FocusLastWindow => Ok("focus_last_window".to_string()),
+            &Self::ToggleFullscreen => Ok("toggle_fullscreen".to_string()),
+            &Self::Raise => Ok("raise".to_string()),
+            &Self::Lower => Ok("lower".to_string()),
+            &Self::NextWorkspace => Ok("next_workspace".to_string()),
+            &Self::PrevWorkspace => Ok("prev_workspace".to_string()),
+            &Self::Abort => Ok("abort".to_string()),
+            &Self::ToggleDnd => Ok("toggle_dnd".to_string()),
+            &Self::MoveFloating(x, y) => Ok(format!("move_float {x} {y}")),
+            &Self::ResizeFloating(w, h) => Ok(format!("resize_float {w} {h}")),
+            &Self::CenterFloating => Ok("center_float".to_string()),
+            &Self::RebalanceMonitors => Ok("rebalance_monitors".to_string()),
+            Self::Gather(class) => Ok(format!("gather class \"{class}\"")),
+            &Self::SpawnFloatTerminal => Ok("spawn_float_terminal".to_string()),
         }
     }
 }
@@ -211,4 +626,295 @@ mod tests {
 
         assert_eq!(str.unwrap(), "next".to_string());
     }
+
+    #[test]
+    fn action_names() {
+        for name in Action::NAMES {
+            if let Err(e) = Action::from_action_str(name.to_string()) {
+                assert!(!format!("{e:?}").contains("Unknown action"), "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn execute_parsing() {
+        let command = "notify-send 'hi  there' \"from crubwm\"";
+        let action = Action::from_action_str(format!("execute {command}")).unwrap();
+
+        assert_eq!(action, Action::Execute(command.to_string()));
+        assert_eq!(action.repr().unwrap(), format!("execute {command}"));
+    }
+
+    #[test]
+    fn execute_on_parsing() {
+        let action =
+            Action::from_action_str("execute_on 3 firefox --private-window".to_string()).unwrap();
+
+        assert_eq!(
+            action,
+            Action::ExecuteOn(3, "firefox --private-window".to_string())
+        );
+        assert_eq!(
+            action.repr().unwrap(),
+            "execute_on 3 firefox --private-window"
+        );
+        assert!(Action::from_action_str("execute_on 3".to_string()).is_err());
+        assert!(Action::from_action_str("execute_on web firefox".to_string()).is_err());
+    }
+
+    #[test]
+    fn spatial_focus_parsing() {
+        for direction in ["up", "down", "left", "right"] {
+            let action = Action::from_action_str(format!("focus {direction}")).unwrap();
+            assert_eq!(action.repr().unwrap(), format!("focus {direction}"));
+        }
+        assert_eq!(
+            Action::from_action_str("focus up".to_string()).unwrap(),
+            Action::Focus(Direction::Up)
+        );
+        assert!(Action::from_action_str("focus sideways".to_string()).is_err());
+    }
+
+    #[test]
+    fn spatial_swap_parsing() {
+        let action = Action::from_action_str("swap right".to_string()).unwrap();
+
+        assert_eq!(action, Action::Swap(Direction::Right));
+        assert_eq!(action.repr().unwrap(), "swap right");
+        assert!(Action::from_action_str("swap sideways".to_string()).is_err());
+    }
+
+    #[test]
+    fn bar_height_parsing() {
+        let grow = Action::from_action_str("bar_height +4".to_string()).unwrap();
+        let shrink = Action::from_action_str("bar_height -2".to_string()).unwrap();
+
+        assert_eq!(grow, Action::BarHeight(4));
+        assert_eq!(shrink, Action::BarHeight(-2));
+        assert_eq!(grow.repr().unwrap(), "bar_height +4");
+        assert!(Action::from_action_str("bar_height".to_string()).is_err());
+    }
+
+    #[test]
+    fn move_to_monitor_parsing() {
+        let action = Action::from_action_str("move_to_monitor_follow next".to_string()).unwrap();
+
+        assert_eq!(action, Action::MoveToMonitorFollow(Direction::Next));
+        assert_eq!(action.repr().unwrap(), "move_to_monitor_follow next");
+        assert_eq!(
+            Action::from_action_str("move_to_monitor previous".to_string()).unwrap(),
+            Action::MoveToMonitor(Direction::Previous)
+        );
+        assert!(Action::from_action_str("move_to_monitor left".to_string()).is_err());
+    }
+
+    #[test]
+    fn filtered_focus_parsing() {
+        let action = Action::from_action_str("focus_floating next".to_string()).unwrap();
+
+        assert_eq!(action, Action::FocusFloating(Direction::Next));
+        assert_eq!(action.repr().unwrap(), "focus_floating next");
+        assert_eq!(
+            Action::from_action_str("focus_tiled previous".to_string()).unwrap(),
+            Action::FocusTiled(Direction::Previous)
+        );
+        assert!(Action::from_action_str("focus_tiled up".to_string()).is_err());
+        assert!(Action::from_action_str("focus_floating".to_string()).is_err());
+    }
+
+    #[test]
+    fn focus_monitor_parsing() {
+        let action = Action::from_action_str("focus_monitor previous".to_string()).unwrap();
+
+        assert_eq!(action, Action::FocusMonitor(Direction::Previous));
+        assert_eq!(action.repr().unwrap(), "focus_monitor previous");
+        assert!(Action::from_action_str("focus_monitor left".to_string()).is_err());
+    }
+
+    #[test]
+    fn pin_parsing() {
+        let action = Action::from_action_str("pin bottom_right".to_string()).unwrap();
+
+        assert_eq!(action, Action::Pin(Some(Corner::BottomRight)));
+        assert_eq!(action.repr().unwrap(), "pin bottom_right");
+        assert_eq!(
+            Action::from_action_str("pin none".to_string()).unwrap(),
+            Action::Pin(None)
+        );
+        assert!(Action::from_action_str("pin middle".to_string()).is_err());
+    }
+
+    #[test]
+    fn float_resize_parsing() {
+        let action = Action::from_action_str("float_resize 0.5 0.75".to_string()).unwrap();
+
+        assert_eq!(action, Action::FloatResize(0.5, 0.75));
+        assert_eq!(action.repr().unwrap(), "float_resize 0.5 0.75");
+        assert!(Action::from_action_str("float_resize 0.5".to_string()).is_err());
+        assert!(Action::from_action_str("float_resize 0 1".to_string()).is_err());
+        assert!(Action::from_action_str("float_resize 1.5 0.5".to_string()).is_err());
+    }
+
+    #[test]
+    fn toggle_gaps_parsing() {
+        let action = Action::from_action_str("toggle_gaps".to_string()).unwrap();
+
+        assert_eq!(action, Action::ToggleGaps);
+        assert_eq!(action.repr().unwrap(), "toggle_gaps");
+    }
+
+    #[test]
+    fn master_ratio_parsing() {
+        let increase = Action::from_action_str("increase_master_ratio".to_string()).unwrap();
+        let decrease = Action::from_action_str("decrease_master_ratio".to_string()).unwrap();
+
+        assert_eq!(increase, Action::IncreaseMasterRatio);
+        assert_eq!(decrease, Action::DecreaseMasterRatio);
+        assert_eq!(increase.repr().unwrap(), "increase_master_ratio");
+        assert_eq!(decrease.repr().unwrap(), "decrease_master_ratio");
+    }
+
+    #[test]
+    fn nmaster_parsing() {
+        let more = Action::from_action_str("nmaster +1".to_string()).unwrap();
+        let fewer = Action::from_action_str("nmaster -1".to_string()).unwrap();
+
+        assert_eq!(more, Action::Nmaster(1));
+        assert_eq!(fewer, Action::Nmaster(-1));
+        assert_eq!(more.repr().unwrap(), "nmaster +1");
+        assert!(Action::from_action_str("nmaster many".to_string()).is_err());
+    }
+
+    #[test]
+    fn kill_unresponsive_parsing() {
+        let action = Action::from_action_str("kill_unresponsive".to_string()).unwrap();
+
+        assert_eq!(action, Action::KillUnresponsive);
+        assert_eq!(action.repr().unwrap(), "kill_unresponsive");
+    }
+
+    #[test]
+    fn adjacent_workspace_parsing() {
+        let next = Action::from_action_str("next_workspace".to_string()).unwrap();
+        let prev = Action::from_action_str("prev_workspace".to_string()).unwrap();
+
+        assert_eq!(next, Action::NextWorkspace);
+        assert_eq!(prev, Action::PrevWorkspace);
+        assert_eq!(next.repr().unwrap(), "next_workspace");
+        assert_eq!(prev.repr().unwrap(), "prev_workspace");
+    }
+
+    #[test]
+    fn toggle_dnd_parsing() {
+        let action = Action::from_action_str("toggle_dnd".to_string()).unwrap();
+
+        assert_eq!(action, Action::ToggleDnd);
+        assert_eq!(action.repr().unwrap(), "toggle_dnd");
+    }
+
+    #[test]
+    fn move_and_resize_float_parsing() {
+        let action = Action::from_action_str("move_float 10 -20".to_string()).unwrap();
+        assert_eq!(action, Action::MoveFloating(10, -20));
+        assert_eq!(action.repr().unwrap(), "move_float 10 -20");
+
+        let action = Action::from_action_str("resize_float -10 0".to_string()).unwrap();
+        assert_eq!(action, Action::ResizeFloating(-10, 0));
+        assert_eq!(action.repr().unwrap(), "resize_float -10 0");
+
+        assert!(Action::from_action_str("move_float 10".to_string()).is_err());
+        assert!(Action::from_action_str("resize_float 0.5 1".to_string()).is_err());
+    }
+
+    #[test]
+    fn center_float_parsing() {
+        let action = Action::from_action_str("center_float".to_string()).unwrap();
+
+        assert_eq!(action, Action::CenterFloating);
+        assert_eq!(action.repr().unwrap(), "center_float");
+    }
+
+    #[test]
+    fn rebalance_monitors_parsing() {
+        let action = Action::from_action_str("rebalance_monitors".to_string()).unwrap();
+
+        assert_eq!(action, Action::RebalanceMonitors);
+        assert_eq!(action.repr().unwrap(), "rebalance_monitors");
+    }
+
+    #[test]
+    fn gather_parsing() {
+        let action = Action::from_action_str("gather class \"Google Chrome\"".to_string()).unwrap();
+
+        assert_eq!(action, Action::Gather("Google Chrome".to_string()));
+        assert_eq!(action.repr().unwrap(), "gather class \"Google Chrome\"");
+        assert_eq!(
+            Action::from_action_str("gather class Firefox".to_string()).unwrap(),
+            Action::Gather("Firefox".to_string())
+        );
+        assert!(Action::from_action_str("gather Firefox".to_string()).is_err());
+        assert!(Action::from_action_str("gather class \"\"".to_string()).is_err());
+    }
+
+    #[test]
+    fn spawn_float_terminal_parsing() {
+        let action = Action::from_action_str("spawn_float_terminal".to_string()).unwrap();
+
+        assert_eq!(action, Action::SpawnFloatTerminal);
+        assert_eq!(action.repr().unwrap(), "spawn_float_terminal");
+    }
+
+    #[test]
+    fn abort_parsing() {
+        let action = Action::from_action_str("abort".to_string()).unwrap();
+
+        assert_eq!(action, Action::Abort);
+        assert_eq!(action.repr().unwrap(), "abort");
+    }
+
+    #[test]
+    fn focus_last_window_parsing() {
+        let action = Action::from_action_str("focus_last_window".to_string()).unwrap();
+
+        assert_eq!(action, Action::FocusLastWindow);
+        assert_eq!(action.repr().unwrap(), "focus_last_window");
+    }
+
+    #[test]
+    fn toggle_fullscreen_parsing() {
+        let action = Action::from_action_str("toggle_fullscreen".to_string()).unwrap();
+
+        assert_eq!(action, Action::ToggleFullscreen);
+        assert_eq!(action.repr().unwrap(), "toggle_fullscreen");
+    }
+
+    #[test]
+    fn raise_lower_parsing() {
+        let raise = Action::from_action_str("raise".to_string()).unwrap();
+        let lower = Action::from_action_str("lower".to_string()).unwrap();
+
+        assert_eq!(raise, Action::Raise);
+        assert_eq!(lower, Action::Lower);
+        assert_eq!(raise.repr().unwrap(), "raise");
+        assert_eq!(lower.repr().unwrap(), "lower");
+    }
+
+    #[test]
+    fn corner_geometry() {
+        let screen = Geometry {
+            x: 1920,
+            y: 20,
+            width: 1280,
+            height: 1004,
+        };
+
+        let top_left = Corner::TopLeft.geometry(screen, 400, 200);
+        assert_eq!((top_left.x, top_left.y), (1920, 20));
+
+        let bottom_right = Corner::BottomRight.geometry(screen, 400, 200);
+        assert_eq!((bottom_right.x, bottom_right.y), (2800, 824));
+
+        let too_big = Corner::TopRight.geometry(screen, 2000, 2000);
+        assert_eq!(too_big, screen);
+    }
 }
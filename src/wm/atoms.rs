@@ -6,7 +6,9 @@
 //! interclient communication and property response parsing.
 //!
 //! This file also contains the `send_client_message` function which is a generic abstraction for
-//! sending client messages to different clients.
+//! sending client messages to different clients, the `protocol_message` function building the
+//! ICCCM `WM_PROTOCOLS` messages, and the `set_frame_extents` function which publishes the size of
+//! a window's decorations.
 use crate::errors::WmResult;
 
 use std::collections::HashMap;
@@ -20,10 +22,34 @@ use x11rb::protocol::xproto::AtomEnum;
 use x11rb::protocol::xproto::ClientMessageEvent;
 use x11rb::protocol::xproto::ConnectionExt;
 use x11rb::protocol::xproto::EventMask;
+use x11rb::protocol::xproto::PropMode;
+use x11rb::wrapper::ConnectionExt as _;
 
 /// Maximum amount of bytes able to receive from a `get_property` reply.
 const MEG: usize = 1024 * 1024;
 
+/// The EWMH atoms which the window manager honors, published in the `_NET_SUPPORTED` property of
+/// the root window.
+pub const SUPPORTED: [&str; 17] = [
+    "_NET_SUPPORTED",
+    "_NET_SUPPORTING_WM_CHECK",
+    "_NET_CLIENT_LIST",
+    "_NET_ACTIVE_WINDOW",
+    "_NET_NUMBER_OF_DESKTOPS",
+    "_NET_CURRENT_DESKTOP",
+    "_NET_DESKTOP_NAMES",
+    "_NET_WM_NAME",
+    "_NET_WM_STATE",
+    "_NET_WM_STATE_ABOVE",
+    "_NET_WM_STATE_BELOW",
+    "_NET_WM_STATE_STICKY",
+    "_NET_WM_STATE_FULLSCREEN",
+    "_NET_WM_STRUT",
+    "_NET_WM_STRUT_PARTIAL",
+    "_NET_WM_PID",
+    "_NET_FRAME_EXTENTS",
+];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// How a property response value should be interpreted.
 pub enum ValueType {
@@ -59,7 +85,7 @@ pub struct WmClassWrapper {
 
 impl WmClassWrapper {
     /// Create a new wrapper from an instance of `WmClass`.
-    fn from_class(c: &WmClass) -> Self {
+    pub fn from_class(c: &WmClass) -> Self {
         let class = String::from_utf8(c.class().to_vec()).ok();
         let instance = String::from_utf8(c.instance().to_vec()).ok();
 
@@ -290,6 +316,13 @@ impl AtomManager {
             ),
             // client messages
             ("_NET_WM_STATE", ValueType::List(AtomEnum::ATOM, MEG)),
+            ("_NET_WM_STATE_ABOVE", ValueType::Single(AtomEnum::ATOM)),
+            ("_NET_WM_STATE_BELOW", ValueType::Single(AtomEnum::ATOM)),
+            ("_NET_WM_STATE_STICKY", ValueType::Single(AtomEnum::ATOM)),
+            (
+                "_NET_WM_STATE_FULLSCREEN",
+                ValueType::Single(AtomEnum::ATOM),
+            ),
             // "_NET_CLOSE_WINDOW",
             // "_NET_WM_MOVERESIZE",
             // "_NET_MOVERESIZE_WINDOW",
@@ -327,6 +360,18 @@ impl AtomManager {
             // "_NET_WM_HANDLED_ICONS",
             ("_NET_WM_USER_TIME", ValueType::Single(AtomEnum::CARDINAL)),
             ("_NET_FRAME_EXTENTS", ValueType::List(AtomEnum::CARDINAL, 4)),
+            // system tray
+            (
+                "_NET_SYSTEM_TRAY_OPCODE",
+                ValueType::List(AtomEnum::CARDINAL, 5),
+            ),
+            (
+                "_NET_SYSTEM_TRAY_ORIENTATION",
+                ValueType::Single(AtomEnum::CARDINAL),
+            ),
+            ("MANAGER", ValueType::List(AtomEnum::CARDINAL, 5)),
+            ("_XEMBED", ValueType::List(AtomEnum::CARDINAL, 5)),
+            ("UTF8_STRING", ValueType::Single(AtomEnum::ATOM)),
             ("WM_NAME", ValueType::Single(AtomEnum::STRING)),
             ("WM_DELETE_WINDOW", ValueType::Single(AtomEnum::ATOM)),
             ("WM_PROTOCOLS", ValueType::List(AtomEnum::ATOM, MEG)),
@@ -336,6 +381,8 @@ impl AtomManager {
                 ValueType::Single(AtomEnum::WM_SIZE_HINTS),
             ),
             ("WM_ZOOM_HINTS", ValueType::Single(AtomEnum::WM_SIZE_HINTS)),
+            // crubwm specific
+            ("_CRUBWM_LAYOUT", ValueType::Single(AtomEnum::STRING)),
         ];
 
         for (atom, value) in atoms_def {
@@ -449,3 +496,78 @@ pub fn send_client_message(
     };
     Ok(())
 }
+
+/// Build the `WM_PROTOCOLS` client message asking a window to follow the given protocol, for
+/// example `WM_DELETE_WINDOW`, as described by the ICCCM.
+pub fn protocol_message(window: u32, wm_protocols: u32, protocol: u32) -> ClientMessageEvent {
+    ClientMessageEvent::new(
+        32,
+        window,
+        wm_protocols,
+        [protocol, x11rb::CURRENT_TIME, 0, 0, 0],
+    )
+}
+
+/// Does a `WM_PROTOCOLS` property reply list the protocol with the given atom?
+pub fn supports_protocol(protocols: &[PropertyReturnValue], protocol: u32) -> bool {
+    protocols
+        .iter()
+        .any(|value| matches!(value, PropertyReturnValue::Number(x) if *x == protocol))
+}
+
+/// Publish the size of the window's decorations in its `_NET_FRAME_EXTENTS` property.
+///
+/// Windows only have uniform X borders, so the left, right, top and bottom extents are all set to
+/// the border width.
+pub fn set_frame_extents(
+    connection: Arc<impl Connection>,
+    window: u32,
+    atom: u32,
+    border_width: u32,
+) -> WmResult {
+    connection.change_property32(
+        PropMode::REPLACE,
+        window,
+        atom,
+        AtomEnum::CARDINAL,
+        &[border_width; 4],
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delete_window_protocol() {
+        let (take_focus, delete_window) = (100, 200);
+        let protocols = vec![
+            PropertyReturnValue::Number(take_focus),
+            PropertyReturnValue::Number(delete_window),
+        ];
+
+        assert!(supports_protocol(&protocols, delete_window));
+        assert!(!supports_protocol(&protocols[..1], delete_window));
+        assert!(!supports_protocol(
+            &[PropertyReturnValue::String("200".into())],
+            delete_window
+        ));
+        assert!(!supports_protocol(&[], delete_window));
+    }
+
+    #[test]
+    fn delete_window_message() {
+        let (window, wm_protocols, delete_window) = (42, 100, 200);
+        let event = protocol_message(window, wm_protocols, delete_window);
+
+        assert_eq!(event.window, window);
+        assert_eq!(event.format, 32);
+        assert_eq!(event.type_, wm_protocols);
+        assert_eq!(
+            event.data.as_data32(),
+            [delete_window, x11rb::CURRENT_TIME, 0, 0, 0]
+        );
+    }
+}
@@ -11,6 +11,10 @@
 //! More information on each segment type can be found in their respective modules bellow.
 //!
 //! A status bar communicates with the window manager by sending and receiving status bar events.
+//!
+//! Instead of, or in addition to drawing its own bar, the window manager can also write its state
+//! for external bars, see the `output` module.
+pub mod output;
 pub mod title;
 pub mod tray;
 pub mod widgets;
@@ -84,7 +88,7 @@ pub enum SegmentType {
     /// defined information. A user can choose how often the widget updates or what actions should
     /// be taken when updating a widget.
     Widget(WidgetSegment),
-    /// A place where iconified windows(window icons) will be shown.
+    /// A system tray, showing the icons of applets docked into the bar.
     IconTray(IconTraySegment),
 }
 
@@ -100,7 +104,7 @@ pub struct Segment {
 
 impl Segment {
     fn draw(&mut self, cr: &Context, position: Option<(f32, f32)>, geometry: Geometry) -> WmResult {
-        match &self.segment_type {
+        match &mut self.segment_type {
             SegmentType::Widget(widget) => widget.draw(cr, position, geometry)?,
             SegmentType::IconTray(tray) => tray.draw(cr, position, geometry)?,
             SegmentType::Workspace(ws) => ws.draw(cr, position, geometry)?,
@@ -113,7 +117,7 @@ impl Segment {
     fn _get_drawable_text(&self) -> WmResult<String> {
         let res = match &self.segment_type {
             SegmentType::Widget(widget) => widget._get_text(),
-            SegmentType::IconTray(_) => "[DEBUG]".into(),
+            SegmentType::IconTray(_) => String::new(),
             SegmentType::Workspace(ws) => ws._get_text()?,
             SegmentType::WindowTitle(title) => title.get_text(),
         };
@@ -125,7 +129,7 @@ impl Segment {
     fn get_text_extents(&self, cr: &Context, font_size: f64) -> WmResult<TextExtents> {
         match &self.segment_type {
             SegmentType::Widget(widget) => widget.get_text_extents(cr, font_size),
-            SegmentType::IconTray(_) => Ok(TextExtents::default()),
+            SegmentType::IconTray(tray) => Ok(tray.get_text_extents()),
             SegmentType::Workspace(ws) => ws.get_text_extents(cr, Some(font_size)),
             SegmentType::WindowTitle(title) => title.get_text_extent(cr, Some(font_size)),
         }
@@ -197,6 +201,8 @@ pub struct Bar {
     settings: Option<BarSettings>,
     /// Bar height.
     height: f64,
+    /// Where the icon tray starts on the bar, if the bar has one.
+    tray_x: Option<f64>,
 }
 
 impl Bar {
@@ -217,6 +223,7 @@ impl Bar {
             geometry: None,
             settings: Some(bar_settings.clone()),
             height: 0.,
+            tray_x: None,
         })
     }
 
@@ -238,7 +245,7 @@ impl Bar {
     }
 
     /// Get the X window id of the bar window.
-    pub fn _window_id(&self) -> WmResult<u32> {
+    pub fn window_id(&self) -> WmResult<u32> {
         self.window_id
             .ok_or_else(|| Error::Generic("bar does not have an associated window id.".to_string()))
     }
@@ -261,14 +268,103 @@ impl Bar {
         self.window_id = Some(wid.into())
     }
 
-    /// Set the bar's Cairo surface.
+    /// Set the bar's Cairo surface, finishing the previous one, if there was any.
     pub fn set_surface(&mut self, surface: XCBSurface) {
-        self.surface = Some(surface)
+        if let Some(old) = self.surface.replace(surface) {
+            old.finish()
+        }
     }
 
     /// Set the bar's geometry.
     pub fn set_geometry(&mut self, geometry: Geometry) {
-        self.geometry = Some(geometry)
+        self.geometry = Some(geometry);
+        for segment in self.segments.iter_mut() {
+            if let SegmentType::IconTray(tray) = &mut segment.segment_type {
+                tray.set_icon_size(geometry.height)
+            }
+        }
+    }
+
+    /// Does the bar contain an icon tray segment?
+    pub fn has_tray(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment.segment_type, SegmentType::IconTray(_)))
+    }
+
+    /// Add an icon to the bar's icon tray, returning whether the bar has a tray.
+    pub fn add_tray_icon(&mut self, icon: u32) -> bool {
+        for segment in self.segments.iter_mut() {
+            if let SegmentType::IconTray(tray) = &mut segment.segment_type {
+                tray.add_icon(icon);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Remove an icon from the bar's icon tray, returning whether it was there.
+    pub fn remove_tray_icon(&mut self, icon: u32) -> bool {
+        let mut removed = false;
+        for segment in self.segments.iter_mut() {
+            if let SegmentType::IconTray(tray) = &mut segment.segment_type {
+                removed |= tray.remove_icon(icon);
+            }
+        }
+
+        removed
+    }
+
+    /// Get the icon windows docked into the bar's icon tray.
+    pub fn tray_icons(&self) -> Vec<u32> {
+        self.segments
+            .iter()
+            .filter_map(|segment| match &segment.segment_type {
+                SegmentType::IconTray(tray) => Some(tray.icons().to_vec()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Does the bar's icon tray contain the given icon window?
+    pub fn has_tray_icon(&self, icon: u32) -> bool {
+        self.segments.iter().any(|segment| {
+            matches!(&segment.segment_type, SegmentType::IconTray(tray) if tray.has_icon(icon))
+        })
+    }
+
+    /// Lay out the tray icons at the position the tray was last drawn at, returning the icons
+    /// which should be moved.
+    pub fn place_tray_icons(&mut self) -> Vec<(u32, Geometry)> {
+        let x = match self.tray_x {
+            Some(x) => x as i16,
+            None => return Vec::new(),
+        };
+
+        let mut changed = Vec::new();
+        for segment in self.segments.iter_mut() {
+            if let SegmentType::IconTray(tray) = &mut segment.segment_type {
+                changed.append(&mut tray.place_icons(x));
+            }
+        }
+
+        changed
+    }
+
+    /// Draw a single segment, remembering where the icon tray starts.
+    fn draw_segment(
+        &mut self,
+        segment: &mut Segment,
+        cr: &Context,
+        geometry: Geometry,
+    ) -> WmResult {
+        if let SegmentType::IconTray(_) = segment.segment_type {
+            self.tray_x = Some(cr.current_point()?.0);
+        }
+
+        segment.draw(cr, None, geometry)
     }
 
     /// Get the latest values for the bar.
@@ -310,7 +406,7 @@ impl Bar {
         let mut segment = &mut sorted[index];
         // draw the left segments
         while let SegmentPosition::Left = segment.position {
-            segment.draw(&cr, None, geom)?;
+            self.draw_segment(segment, &cr, geom)?;
             index += 1;
             if let Some(x) = sorted.get_mut(index) {
                 segment = x;
@@ -328,7 +424,7 @@ impl Bar {
         let mut segment = &mut sorted[index];
         // draw the middle segments
         while let SegmentPosition::Middle = segment.position {
-            segment.draw(&cr, None, geom)?;
+            self.draw_segment(segment, &cr, geom)?;
             index += 1;
             if let Some(x) = sorted.get_mut(index) {
                 segment = x;
@@ -344,7 +440,7 @@ impl Bar {
         let mut segment = &mut sorted[index];
         // draw the right segments
         while let SegmentPosition::Right = segment.position {
-            segment.draw(&cr, None, geom)?;
+            self.draw_segment(segment, &cr, geom)?;
             index += 1;
             if let Some(x) = sorted.get_mut(index) {
                 segment = x;
@@ -353,9 +449,22 @@ impl Bar {
             break;
         }
 
+        // keep the drawn segments, so that clicks can be mapped onto the workspaces
+        self.segments = sorted;
+
         Ok(())
     }
 
+    /// Get the id of the workspace drawn at the given x coordinate of the bar, if any.
+    pub fn workspace_at(&self, x: f64) -> Option<WorkspaceId> {
+        self.segments
+            .iter()
+            .find_map(|segment| match &segment.segment_type {
+                SegmentType::Workspace(ws) => ws.workspace_at(x),
+                _ => None,
+            })
+    }
+
     /// Get the text extents of all the segments based on their positions from left to right.
     fn get_bar_text_extents(
         &self,
@@ -414,14 +523,14 @@ impl Bar {
     /// Adding workspace info to the bar, based on which monitor it is located.
     ///
     /// This adds the workspace info to every workspace segment in the bar.
-    pub fn create_workspaces(&mut self, workspace_ids: Vec<(String, u32)>) {
+    pub fn create_workspaces(&mut self, workspace_ids: Vec<(String, String, u32)>) {
         let mut segments: Vec<&mut Segment> = self
             .segments
             .iter_mut()
             .filter(|segment| matches!(segment.segment_type, SegmentType::Workspace(_)))
             .collect();
         for tuple in workspace_ids.iter() {
-            let segment = WorkspaceInfoSegment::new(tuple.0.clone(), tuple.1);
+            let segment = WorkspaceInfoSegment::new(tuple.0.clone(), tuple.2).with_icon(&tuple.1);
             for workspace_info in segments.iter_mut() {
                 if let SegmentType::Workspace(info) = &mut workspace_info.segment_type {
                     info.add(segment.clone())
@@ -449,6 +558,28 @@ impl Bar {
         Ok(())
     }
 
+    /// Return the widgets of all the bar's widget segments.
+    pub fn widgets(&self) -> Vec<Widget> {
+        self.segments
+            .iter()
+            .filter_map(|segment| match &segment.segment_type {
+                SegmentType::Widget(widgets) => Some(widgets.widgets().to_vec()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Seed the bar's widgets with the values of the widgets from before a config reload, so that
+    /// they don't show up empty until their commands run again.
+    pub fn restore_widgets(&mut self, old: &[Widget]) {
+        for segment in self.segments.iter_mut() {
+            if let SegmentType::Widget(widgets) = &mut segment.segment_type {
+                widgets.restore(old);
+            }
+        }
+    }
+
     /// Update all bar's workspace info segments.
     ///
     /// This attempts to set set the open and focused workspaces.
@@ -475,6 +606,26 @@ impl Bar {
         Ok(())
     }
 
+    /// Set the workspaces in "do not disturb" mode, shown by workspace segments using the `{dnd}`
+    /// format token.
+    pub fn set_dnd_workspaces(&mut self, workspaces: &[WorkspaceId]) {
+        for segment in self.segments.iter_mut() {
+            if let SegmentType::Workspace(workspace_info) = &mut segment.segment_type {
+                workspace_info.set_dnd(workspaces);
+            }
+        }
+    }
+
+    /// Set the name of the active keyboard layout, shown by widgets using the `{keyboard_layout}`
+    /// format token.
+    pub fn set_keyboard_layout(&mut self, layout: &str) {
+        for segment in self.segments.iter_mut() {
+            if let SegmentType::Widget(widgets) = &mut segment.segment_type {
+                widgets.set_keyboard_layout(layout);
+            }
+        }
+    }
+
     /// Update the window title for the bar.
     fn update_window_title(&mut self, window_title: String) {
         let mut segments: Vec<&mut Segment> = self
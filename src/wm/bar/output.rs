@@ -0,0 +1,194 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::{
+    errors::WmResult,
+    log::{log, LL_NORMAL},
+    logm,
+    wm::{monitors::MonitorId, workspace::WorkspaceId},
+};
+
+/// The state of a single workspace, as reported to external bars.
+#[derive(Debug, Clone)]
+pub struct WorkspaceStatus {
+    pub id: WorkspaceId,
+    pub name: String,
+    pub monitor: MonitorId,
+    /// Is the workspace open on its monitor?
+    pub open: bool,
+    /// Is the workspace focused?
+    pub focused: bool,
+}
+
+/// Writes the state of the window manager as JSON lines, for external bars such as lemonbar or
+/// polybar to consume.
+///
+/// A line is only written when the state has changed since the last line.
+pub struct BarOutput {
+    /// Either "STDOUT" or a path to a file or a fifo.
+    target: String,
+    file: Option<File>,
+    last_line: String,
+    /// Has it already been logged that the target is a fifo without a reader?
+    reported_no_reader: bool,
+}
+
+impl BarOutput {
+    pub fn new(target: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            file: None,
+            last_line: String::new(),
+            reported_no_reader: false,
+        }
+    }
+
+    /// Get the target the output is written to.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Write the current state, unless it is the same as the last written state.
+    pub fn write(&mut self, workspaces: &[WorkspaceStatus], title: &str) -> WmResult {
+        let line = status_line(workspaces, title);
+        if line == self.last_line {
+            return Ok(());
+        }
+
+        if self.target == "STDOUT" {
+            let mut stdout = std::io::stdout();
+            writeln!(stdout, "{line}")?;
+            stdout.flush()?;
+        } else {
+            if self.file.is_none() {
+                // Opening a fifo which nobody reads from fails instead of blocking the window
+                // manager, the line is written once a reader shows up.
+                match OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .custom_flags(libc::O_NONBLOCK)
+                    .open(&self.target)
+                {
+                    Ok(file) => {
+                        self.file = Some(file);
+                        self.reported_no_reader = false;
+                    }
+                    Err(e) if e.raw_os_error() == Some(libc::ENXIO) => {
+                        if !self.reported_no_reader {
+                            logm!(
+                                LL_NORMAL,
+                                "Bar output {} has no reader yet, not writing to it.",
+                                self.target
+                            );
+                            self.reported_no_reader = true;
+                        }
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+
+            if let Some(file) = self.file.as_mut() {
+                // The reader of a fifo might have gone away, open the target again next time.
+                if let Err(e) = writeln!(file, "{line}") {
+                    self.file = None;
+                    return Err(e.into());
+                }
+            }
+        }
+
+        self.last_line = line;
+
+        Ok(())
+    }
+}
+
+/// Serialize the state of the workspaces and the title of the focused window into a single line
+/// of JSON.
+pub fn status_line(workspaces: &[WorkspaceStatus], title: &str) -> String {
+    let workspaces: Vec<String> = workspaces
+        .iter()
+        .map(|ws| {
+            format!(
+                "{{\"id\":{},\"name\":{},\"monitor\":{},\"open\":{},\"focused\":{}}}",
+                ws.id,
+                json_string(&ws.name),
+                ws.monitor,
+                ws.open,
+                ws.focused
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"workspaces\":[{}],\"title\":{}}}",
+        workspaces.join(","),
+        json_string(title)
+    )
+}
+
+/// Quote and escape a string for use in JSON.
+fn json_string(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if (c as u32) < 0x20 => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_json() {
+        let workspaces = vec![
+            WorkspaceStatus {
+                id: 1,
+                name: "web".into(),
+                monitor: 1,
+                open: true,
+                focused: true,
+            },
+            WorkspaceStatus {
+                id: 2,
+                name: "chat".into(),
+                monitor: 1,
+                open: false,
+                focused: false,
+            },
+        ];
+
+        assert_eq!(
+            status_line(&workspaces, "vim \"main.rs\"\n"),
+            r#"{"workspaces":[{"id":1,"name":"web","monitor":1,"open":true,"focused":true},{"id":2,"name":"chat","monitor":1,"open":false,"focused":false}],"title":"vim \"main.rs\"\n"}"#
+        );
+    }
+
+    #[test]
+    fn fifo_without_reader() {
+        let path = std::env::temp_dir().join(format!("crubwm-bar-{}", std::process::id()));
+        let c_path = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+
+        let mut output = BarOutput::new(path.to_str().unwrap());
+        assert!(output.write(&[], "title").is_ok());
+        assert!(output.write(&[], "title").is_ok());
+        assert!(output.file.is_none());
+        assert!(output.reported_no_reader);
+
+        std::fs::remove_file(path).unwrap_or(());
+    }
+}
@@ -1,20 +1,107 @@
+//! The icon tray segment shows the icons of system tray applets.
+//!
+//! The window manager acts as an XEMBED system tray: applets ask it to dock their icon windows,
+//! which are then reparented into the bar window. The segment itself only reserves space for the
+//! icons on the bar and decides where each of them should be placed, the icon windows draw
+//! themselves.
+use std::collections::HashMap;
+
 use cairo::Context;
 
-use crate::{config::IconTraySettings, errors::WmResult, wm::geometry::Geometry};
+use crate::{
+    config::IconTraySettings,
+    errors::WmResult,
+    wm::geometry::{Geometry, TextExtents},
+};
 
 #[derive(Clone, Debug)]
 pub struct IconTraySegment {
-    _icons: Vec<u32>,
+    /// Icon windows, in the order they were docked in.
+    icons: Vec<u32>,
+    /// Width and height of a single icon.
+    icon_size: u16,
+    /// Where each icon window has last been placed.
+    placed: HashMap<u32, Geometry>,
     _settings: IconTraySettings,
 }
 
 impl IconTraySegment {
+    /// Add a new icon to the end of the tray.
+    pub fn add_icon(&mut self, icon: u32) {
+        if !self.icons.contains(&icon) {
+            self.icons.push(icon)
+        }
+    }
+
+    /// Remove an icon from the tray, returning whether the tray contained it.
+    pub fn remove_icon(&mut self, icon: u32) -> bool {
+        self.placed.remove(&icon);
+        let len = self.icons.len();
+        self.icons.retain(|i| *i != icon);
+
+        len != self.icons.len()
+    }
+
+    pub fn icons(&self) -> &[u32] {
+        &self.icons
+    }
+
+    pub fn has_icon(&self, icon: u32) -> bool {
+        self.icons.contains(&icon)
+    }
+
+    /// Set the size of the icons, which are squares as tall as the bar.
+    pub fn set_icon_size(&mut self, size: u16) {
+        self.icon_size = size
+    }
+
+    /// Combined width of all the icons.
+    pub fn width(&self) -> f64 {
+        self.icons.len() as f64 * self.icon_size as f64
+    }
+
+    pub fn get_text_extents(&self) -> TextExtents {
+        TextExtents {
+            width: self.width(),
+            advance: self.width(),
+            ..Default::default()
+        }
+    }
+
+    /// Lay the icons out left-to-right, starting at `x`, returning only the icons whose geometry
+    /// has changed since the last time.
+    pub fn place_icons(&mut self, x: i16) -> Vec<(u32, Geometry)> {
+        let mut changed = Vec::new();
+
+        for (i, icon) in self.icons.iter().enumerate() {
+            let geometry = Geometry {
+                x: x + (i as u16 * self.icon_size) as i16,
+                y: 0,
+                width: self.icon_size,
+                height: self.icon_size,
+            };
+
+            if self.placed.insert(*icon, geometry) != Some(geometry) {
+                changed.push((*icon, geometry))
+            }
+        }
+
+        changed
+    }
+
+    /// Reserve the space for the icons, by moving past it.
     pub fn draw(
         &self,
-        _cr: &Context,
-        _position: Option<(f32, f32)>,
+        cr: &Context,
+        position: Option<(f32, f32)>,
         _geometry: Geometry,
     ) -> WmResult {
+        if let Some((x, y)) = position {
+            cr.move_to(x.into(), y.into());
+        }
+
+        cr.rel_move_to(self.width(), 0.);
+
         Ok(())
     }
 }
@@ -22,8 +109,40 @@ impl IconTraySegment {
 impl From<IconTraySettings> for IconTraySegment {
     fn from(s: IconTraySettings) -> Self {
         Self {
-            _icons: Vec::new(),
+            icons: Vec::new(),
+            icon_size: 0,
+            placed: HashMap::new(),
             _settings: s,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn place_icons() {
+        let mut tray = IconTraySegment::from(IconTraySettings);
+        tray.set_icon_size(20);
+        tray.add_icon(1);
+        tray.add_icon(2);
+
+        let placed: Vec<(u32, i16)> = tray
+            .place_icons(100)
+            .iter()
+            .map(|(i, g)| (*i, g.x))
+            .collect();
+        assert_eq!(placed, vec![(1, 100), (2, 120)]);
+        assert!(tray.place_icons(100).is_empty());
+
+        assert!(tray.remove_icon(1));
+        let placed: Vec<(u32, i16)> = tray
+            .place_icons(100)
+            .iter()
+            .map(|(i, g)| (*i, g.x))
+            .collect();
+        assert_eq!(placed, vec![(2, 100)]);
+        assert_eq!(tray.width(), 20.);
+    }
+}
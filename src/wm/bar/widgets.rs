@@ -1,10 +1,16 @@
-use std::{process::Command, time::UNIX_EPOCH, vec};
+use std::{
+    process::{Command, Stdio},
+    time::UNIX_EPOCH,
+    vec,
+};
 
-use cairo::Context;
+use cairo::{Context, ImageSurface};
 
 use crate::{
     config::WidgetSettings,
+    errm,
     errors::{Error, WmResult},
+    log::err,
     utils,
     wm::geometry::{Geometry, TextExtents},
 };
@@ -19,6 +25,17 @@ pub struct Widget {
     value: String,
     last_update: u64,
     settings: WidgetSettings,
+    /// Name of the active keyboard layout, shown by the `{keyboard_layout}` format token.
+    keyboard_layout: String,
+    /// Whether the `show_when` command succeeded on the last update. Hidden widgets take up no
+    /// space in the bar.
+    visible: bool,
+    /// The image loaded from the `file:` path of the icon, loaded once when the widget is
+    /// created. Without it, the icon is shown as text.
+    icon_image: Option<ImageSurface>,
+    /// The idle and total CPU times read by the last update of a widget with the "cpu" source,
+    /// the usage is measured since then.
+    cpu_times: Option<(u64, u64)>,
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +44,10 @@ enum FormatToken {
     Icon(String),
     Value(String),
     Separator(String),
+    /// The value shown as a graph, holding the width of the graph's filled part.
+    Graph(f64),
+    /// The icon, shown as an image.
+    Image(ImageSurface),
 }
 
 impl FormatToken {
@@ -36,29 +57,195 @@ impl FormatToken {
             FormatToken::Value(s) => s.clone(),
             FormatToken::Separator(s) => s.clone(),
             FormatToken::Literal(s) => s.to_string(),
+            FormatToken::Graph(_) | FormatToken::Image(_) => String::new(),
         }
     }
 }
 
+/// Return the width of the filled part of a graph which is `width` pixels wide and shows a value
+/// between 0 and 100, optionally followed by a `%`. Values out of the range are clamped.
+///
+/// Returns `None` if the value isn't a number.
+fn graph_fill(value: &str, width: f64) -> Option<f64> {
+    let number = value
+        .trim()
+        .trim_end_matches('%')
+        .trim_end()
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())?;
+
+    Some(width * number.clamp(0., 100.) / 100.)
+}
+
+/// Directory of the battery read by the "battery" widget source.
+const BATTERY_PATH: &str = "/sys/class/power_supply/BAT0";
+
+/// Value shown by widgets whose built-in source can't be read, for example a battery widget on a
+/// machine without a battery.
+const SOURCE_UNAVAILABLE: &str = "N/A";
+
+/// Return the idle and the total time the CPUs have spent since boot, from the contents of
+/// `/proc/stat`.
+fn cpu_times(stat: &str) -> Option<(u64, u64)> {
+    let times = stat
+        .lines()
+        .find(|line| line.starts_with("cpu "))?
+        .split_whitespace()
+        .skip(1)
+        .map(|time| time.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    // user, nice, system, idle, iowait, irq, softirq and steal, the guest times are already
+    // included in user and nice
+    if times.len() < 4 {
+        return None;
+    }
+    let idle = times[3] + times.get(4).copied().unwrap_or(0);
+    let total = times.iter().take(8).sum();
+
+    Some((idle, total))
+}
+
+/// Return the CPU usage, in percent, between two readings of `cpu_times`.
+fn cpu_usage(previous: (u64, u64), current: (u64, u64)) -> Option<f64> {
+    let idle = current.0.checked_sub(previous.0)?;
+    let total = current.1.checked_sub(previous.1)?;
+    if total == 0 {
+        return None;
+    }
+
+    Some(100. * total.saturating_sub(idle) as f64 / total as f64)
+}
+
+/// Return the memory usage, in percent, from the contents of `/proc/meminfo`. Memory which is
+/// available, such as the page cache, doesn't count as used.
+fn memory_usage(meminfo: &str) -> Option<f64> {
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name))?
+            .trim_start_matches(':')
+            .split_whitespace()
+            .next()?
+            .parse::<u64>()
+            .ok()
+    };
+    let total = field("MemTotal").filter(|total| *total > 0)?;
+    let available = field("MemAvailable")?;
+
+    Some(100. * total.saturating_sub(available) as f64 / total as f64)
+}
+
+/// Return the charge of a battery, in percent, from its directory in `/sys/class/power_supply`.
+fn battery_capacity(path: &std::path::Path) -> Option<f64> {
+    std::fs::read_to_string(path.join("capacity"))
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+}
+
+/// Load the image of an icon starting with `file:`, followed by the path to a PNG image.
+///
+/// Returns `None` for other icons, and for images which can't be loaded, which are then shown as
+/// text.
+fn load_icon_image(icon: &str) -> Option<ImageSurface> {
+    let path = icon.strip_prefix("file:")?;
+    let image = std::fs::File::open(path)
+        .map_err(|e| e.to_string())
+        .and_then(|mut file| ImageSurface::create_from_png(&mut file).map_err(|e| e.to_string()));
+
+    match image {
+        Ok(image) if image.height() > 0 => Some(image),
+        Ok(_) => None,
+        Err(e) => {
+            errm!("unable to load the icon image {path}, showing the icon as text: {e}");
+            None
+        }
+    }
+}
+
+/// Return the width of an image scaled to be `height` pixels tall.
+fn scaled_width(image: &ImageSurface, height: f64) -> f64 {
+    image.width() as f64 * height / image.height() as f64
+}
+
+/// Return the height icon images are drawn at, which is the height of the current font.
+fn icon_height(cr: &Context) -> WmResult<f64> {
+    let font = cr.font_extents()?;
+    Ok(font.ascent + font.descent)
+}
+
 impl Widget {
     pub fn update(&mut self) -> WmResult {
         let now = UNIX_EPOCH.elapsed()?.as_secs();
 
         if now - self.last_update >= self.settings.update_time as u64 || self.last_update == 0 {
-            self.value = String::from_utf8(
-                Command::new("/bin/sh")
-                    .args(["-c", &self.settings.command])
-                    .output()?
-                    .stdout,
-            )?
-            .trim()
-            .to_string();
+            if !self.settings.show_when.is_empty() {
+                // the output of the check would end up in the bar output on stdout otherwise
+                self.visible = Command::new("/bin/sh")
+                    .args(["-c", &self.settings.show_when])
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()?
+                    .success();
+            }
+            // the value of a hidden widget isn't shown, there's no need to run its command
+            if self.visible && self.settings.source.is_empty() {
+                self.value = String::from_utf8(
+                    Command::new("/bin/sh")
+                        .args(["-c", &self.settings.command])
+                        .output()?
+                        .stdout,
+                )?
+                .trim()
+                .to_string();
+            } else if self.visible {
+                self.value = match self.read_source() {
+                    Some(percent) => format!("{percent:.0}%"),
+                    None => SOURCE_UNAVAILABLE.to_string(),
+                };
+            }
             self.last_update = now
         }
 
         Ok(())
     }
 
+    /// Read the value of a widget with a built-in source, in percent.
+    fn read_source(&mut self) -> Option<f64> {
+        match self.settings.source.as_str() {
+            "battery" => battery_capacity(std::path::Path::new(BATTERY_PATH)),
+            "memory" => memory_usage(&std::fs::read_to_string("/proc/meminfo").ok()?),
+            "cpu" => {
+                let current = cpu_times(&std::fs::read_to_string("/proc/stat").ok()?)?;
+                // the first reading shows the usage since boot
+                let previous = self.cpu_times.replace(current).unwrap_or((0, 0));
+                cpu_usage(previous, current)
+            }
+            _ => None,
+        }
+    }
+
+    /// Take over the value of the same widget from before a config reload, so that it isn't blank
+    /// until its command runs again. Widgets are the same if their ids, commands and sources
+    /// match.
+    fn restore(&mut self, old: &Widget) -> bool {
+        if self.settings.id != old.settings.id
+            || self.settings.command != old.settings.command
+            || self.settings.source != old.settings.source
+            || self.settings.show_when != old.settings.show_when
+        {
+            return false;
+        }
+
+        self.value = old.value.clone();
+        self.last_update = old.last_update;
+        self.visible = old.visible;
+        self.cpu_times = old.cpu_times;
+        true
+    }
+
     fn _value_with_separator(&self) -> (String, String) {
         (
             format!("{} {}", self.settings.icon, self.value),
@@ -83,8 +270,17 @@ impl Widget {
             } else if char == '}' {
                 in_brace = false;
                 match &brace_value[..] {
-                    "icon" => output.push(FormatToken::Icon(self.settings.icon.clone())),
-                    "value" => output.push(FormatToken::Value(self.value.clone())),
+                    "icon" => match &self.icon_image {
+                        Some(image) => output.push(FormatToken::Image(image.clone())),
+                        None => output.push(FormatToken::Icon(self.settings.icon.clone())),
+                    },
+                    "value" => match self.graph_fill() {
+                        Some(fill) => output.push(FormatToken::Graph(fill)),
+                        None => output.push(FormatToken::Value(self.value.clone())),
+                    },
+                    "keyboard_layout" => {
+                        output.push(FormatToken::Value(self.keyboard_layout.clone()))
+                    }
                     "separator" => {
                         output.push(FormatToken::Separator(self.settings.separator.clone()))
                     }
@@ -103,12 +299,44 @@ impl Widget {
         Ok(output)
     }
 
+    /// Return the width of the filled part of the graph showing the value. Returns `None` if the
+    /// widget isn't shown as a graph, or if its value isn't a number, in which case the value is
+    /// shown as text instead.
+    fn graph_fill(&self) -> Option<f64> {
+        if self.settings.style != "graph" {
+            return None;
+        }
+
+        graph_fill(&self.value, self.settings.graph_width as f64)
+    }
+
     fn get_extent_info(&self, cr: &Context) -> WmResult<TextExtents> {
+        if !self.visible {
+            return Ok(TextExtents::default());
+        }
         utils::cairo_font_from_str(cr, &self.settings.font)?;
         let mut extents = TextExtents::default();
         let tokens = self.value()?;
 
         for token in tokens.iter() {
+            if let FormatToken::Graph(_) = token {
+                let width = self.settings.graph_width as f64;
+                extents += TextExtents {
+                    width,
+                    advance: width,
+                    ..Default::default()
+                };
+                continue;
+            }
+            if let FormatToken::Image(image) = token {
+                let width = scaled_width(image, icon_height(cr)?);
+                extents += TextExtents {
+                    width,
+                    advance: width,
+                    ..Default::default()
+                };
+                continue;
+            }
             let text = token.text();
             let text = &if &text[..] == " " {
                 "-".to_string()
@@ -168,6 +396,41 @@ impl Widget {
                     cr.set_source_rgb(r, g, b);
                     cr.show_text(token.text().as_str())?;
                 }
+                FormatToken::Graph(fill) => {
+                    let (x, y) = cr.current_point()?;
+                    let width = self.settings.graph_width as f64;
+                    // the graph takes up the middle half of the bar's height
+                    let top = geometry.height as f64 / 4.;
+                    let height = geometry.height as f64 / 2.;
+
+                    let (r, g, b) =
+                        utils::translate_color(self.settings.graph_background_color.clone())?;
+                    cr.set_source_rgb(r, g, b);
+                    cr.rectangle(x, top, width, height);
+                    cr.fill()?;
+                    let (r, g, b) = utils::translate_color(self.settings.graph_color.clone())?;
+                    cr.set_source_rgb(r, g, b);
+                    cr.rectangle(x, top, fill, height);
+                    cr.fill()?;
+
+                    cr.move_to(x + width, y);
+                }
+                FormatToken::Image(image) => {
+                    let (x, y) = cr.current_point()?;
+                    let height = icon_height(cr)?;
+                    let width = scaled_width(&image, height);
+                    let scale = height / image.height() as f64;
+
+                    // the image is centered vertically in the bar
+                    cr.save()?;
+                    cr.translate(x, (geometry.height as f64 - height) / 2.);
+                    cr.scale(scale, scale);
+                    cr.set_source_surface(&image, 0., 0.)?;
+                    cr.paint()?;
+                    cr.restore()?;
+
+                    cr.move_to(x + width, y);
+                }
             }
         }
 
@@ -182,7 +445,11 @@ impl From<Vec<WidgetSettings>> for WidgetSegment {
             ret.push(Widget {
                 value: "".to_string(),
                 last_update: 0,
+                icon_image: load_icon_image(&widget_settings.icon),
+                cpu_times: None,
                 settings: widget_settings,
+                keyboard_layout: String::new(),
+                visible: true,
             })
         }
         Self { widgets: ret }
@@ -197,11 +464,33 @@ impl WidgetSegment {
         Ok(())
     }
 
+    pub fn widgets(&self) -> &[Widget] {
+        &self.widgets
+    }
+
+    /// Seed the widgets with the values of the matching widgets from before a config reload.
+    pub fn restore(&mut self, old: &[Widget]) {
+        for widget in self.widgets.iter_mut() {
+            for old_widget in old {
+                if widget.restore(old_widget) {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Set the name of the active keyboard layout for all the widgets.
+    pub fn set_keyboard_layout(&mut self, layout: &str) {
+        for widget in self.widgets.iter_mut() {
+            widget.keyboard_layout = layout.to_string();
+        }
+    }
+
     pub fn _get_text(&self) -> String {
         let mut buffer = String::new();
         let mut last_sep = String::new();
 
-        for widget in self.widgets.iter() {
+        for widget in self.widgets.iter().filter(|widget| widget.visible) {
             let value = &widget._value_with_separator();
             buffer.push_str(&value.1);
             buffer.push_str(&value.0);
@@ -230,9 +519,187 @@ impl WidgetSegment {
             cr.move_to(x.into(), y.into())
         }
 
-        for widget in self.widgets.iter() {
+        for widget in self.widgets.iter().filter(|widget| widget.visible) {
             widget.draw(cr, None, geometry)?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn widget(id: &str, command: &str) -> WidgetSettings {
+        WidgetSettings {
+            id: id.into(),
+            command: command.into(),
+            ..Default::default()
+        }
+    }
+
+    fn values(segment: &WidgetSegment) -> Vec<String> {
+        segment
+            .widgets()
+            .iter()
+            .map(|w| {
+                w.value()
+                    .unwrap()
+                    .iter()
+                    .filter(|t| matches!(t, FormatToken::Value(_)))
+                    .map(|t| t.text())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn restore_after_reload() {
+        let settings = vec![widget("date", "date"), widget("battery", "acpi")];
+        let mut old = WidgetSegment::from(settings.clone());
+        old.widgets[0].value = "Mon".into();
+        old.widgets[0].last_update = 10;
+        old.widgets[1].value = "80%".into();
+        old.widgets[1].last_update = 20;
+
+        // the battery widget's command has changed, its old value can't be trusted
+        let mut new = WidgetSegment::from(vec![
+            widget("battery", "upower"),
+            widget("cpu", "top"),
+            settings[0].clone(),
+        ]);
+        new.restore(old.widgets());
+
+        assert_eq!(values(&new), vec!["", "", "Mon"]);
+        assert_eq!(new.widgets[2].last_update, 10);
+        assert_eq!(new.widgets[0].last_update, 0);
+    }
+
+    #[test]
+    fn hidden_widget_extents() {
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 100, 20).unwrap();
+        let cr = Context::new(&surface).unwrap();
+        let mut segment = WidgetSegment::from(vec![
+            WidgetSettings {
+                show_when: "false".into(),
+                ..widget("vpn", "echo connected")
+            },
+            widget("date", "echo Mon"),
+        ]);
+        segment.run_updates().unwrap();
+
+        assert!(!segment.widgets[0].visible);
+        assert!(segment.widgets[1].visible);
+        assert_eq!(values(&segment), vec!["", "Mon"]);
+        let hidden = segment.widgets[0].get_extent_info(&cr).unwrap();
+        assert_eq!(hidden.width, 0.);
+        assert_eq!(hidden.advance, 0.);
+
+        let total = segment.get_text_extents(&cr, 10.).unwrap();
+        let shown = segment.widgets[1].get_extent_info(&cr).unwrap();
+        assert!(shown.width > 0.);
+        assert_eq!(total.width, shown.width);
+    }
+
+    #[test]
+    fn graph_fill_width() {
+        assert_eq!(super::graph_fill("50", 80.), Some(40.));
+        assert_eq!(super::graph_fill(" 25% ", 80.), Some(20.));
+        assert_eq!(super::graph_fill("12.5", 80.), Some(10.));
+        assert_eq!(super::graph_fill("150", 80.), Some(80.));
+        assert_eq!(super::graph_fill("-5", 80.), Some(0.));
+        assert_eq!(super::graph_fill("muted", 80.), None);
+        assert_eq!(super::graph_fill("NaN", 80.), None);
+
+        // a value which isn't a number is shown as text
+        let mut segment = WidgetSegment::from(vec![WidgetSettings {
+            style: "graph".into(),
+            graph_width: 80,
+            ..widget("volume", "pamixer --get-volume")
+        }]);
+        segment.widgets[0].value = "muted".into();
+        assert_eq!(values(&segment), vec!["muted"]);
+        segment.widgets[0].value = "75".into();
+        assert_eq!(values(&segment), vec![""]);
+        assert!(segment.widgets[0]
+            .value()
+            .unwrap()
+            .iter()
+            .any(|token| matches!(token, FormatToken::Graph(fill) if *fill == 60.)));
+    }
+
+    #[test]
+    fn builtin_sources() {
+        let stat = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 50 0 25 400 25 0 0 0 0 0\n";
+        let first = cpu_times(stat).unwrap();
+        assert_eq!(first, (850, 1000));
+        let stat = "cpu  400 0 100 1250 50 0 0 0 0 0\n";
+        let second = cpu_times(stat).unwrap();
+        // 350 of the 800 ticks in between were spent working
+        assert_eq!(cpu_usage(first, second), Some(43.75));
+        assert_eq!(cpu_usage(second, second), None);
+        assert_eq!(cpu_times("intr 1 2 3"), None);
+
+        let meminfo = "MemTotal:       16000000 kB\nMemFree:         2000000 kB\nMemAvailable:    12000000 kB\n";
+        assert_eq!(memory_usage(meminfo), Some(25.));
+        assert_eq!(memory_usage("MemTotal: 0 kB\nMemAvailable: 0 kB"), None);
+
+        let path = std::env::temp_dir().join(format!("crubwm-battery-{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("capacity"), "87\n").unwrap();
+        assert_eq!(battery_capacity(&path), Some(87.));
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(battery_capacity(&path), None);
+
+        // built-in sources don't run the command
+        let mut segment = WidgetSegment::from(vec![WidgetSettings {
+            source: "memory".into(),
+            ..widget("memory", "echo never")
+        }]);
+        segment.run_updates().unwrap();
+        let value = &segment.widgets[0].value;
+        assert!(
+            value.ends_with('%') || value == SOURCE_UNAVAILABLE,
+            "{value}"
+        );
+    }
+
+    #[test]
+    fn icon_image() {
+        let path = std::env::temp_dir().join(format!("crubwm-icon-{}.png", std::process::id()));
+        let image = ImageSurface::create(cairo::Format::ARgb32, 32, 16).unwrap();
+        image
+            .write_to_png(&mut std::fs::File::create(&path).unwrap())
+            .unwrap();
+
+        let surface = ImageSurface::create(cairo::Format::ARgb32, 100, 20).unwrap();
+        let cr = Context::new(&surface).unwrap();
+        let segment = WidgetSegment::from(vec![
+            WidgetSettings {
+                icon: format!("file:{}", path.display()),
+                format: "{icon}".into(),
+                ..widget("image", "true")
+            },
+            WidgetSettings {
+                icon: "file:/nonexistent/icon.png".into(),
+                format: "{icon}".into(),
+                ..widget("missing", "true")
+            },
+        ]);
+        std::fs::remove_file(&path).unwrap();
+
+        // the image is loaded once, and scaled to the height of the font
+        let image = &segment.widgets[0];
+        assert!(image.icon_image.is_some());
+        let height = icon_height(&cr).unwrap();
+        assert_eq!(image.get_extent_info(&cr).unwrap().width, 2. * height);
+
+        // an image which can't be loaded is shown as text
+        let missing = &segment.widgets[1];
+        assert!(missing.icon_image.is_none());
+        assert_eq!(
+            missing.value().unwrap()[0].text(),
+            "file:/nonexistent/icon.png"
+        );
+    }
+}
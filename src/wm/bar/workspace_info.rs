@@ -17,6 +17,8 @@ use crate::{
 pub struct WorkspaceInfoSegment {
     /// Name of the workspace/what is displayed.
     name: String,
+    /// Icon or symbol of the workspace.
+    icon: String,
     /// Workspace number or id.
     workspace_id: WorkspaceId,
     /// Is the workspace focused?
@@ -25,6 +27,10 @@ pub struct WorkspaceInfoSegment {
     open: bool,
     /// Does the workspace seek urgent attention?
     _urgent: bool,
+    /// Is the workspace in "do not disturb" mode?
+    dnd: bool,
+    /// Horizontal range of the bar the workspace was last drawn at.
+    x_range: Option<(f64, f64)>,
 }
 
 /// The workspace info consists of different workspace info segments.
@@ -47,14 +53,23 @@ impl WorkspaceInfoSegment {
     pub fn new(name: impl AsRef<str>, id: impl Into<u32>) -> Self {
         Self {
             name: name.as_ref().to_string(),
+            icon: String::new(),
             workspace_id: id.into(),
             focused: false,
             open: false,
             _urgent: false,
+            dnd: false,
+            x_range: None,
         }
     }
 
-    fn value(&self, fmt: String) -> WmResult<String> {
+    /// Set the icon of the workspace.
+    pub fn with_icon(mut self, icon: impl AsRef<str>) -> Self {
+        self.icon = icon.as_ref().to_string();
+        self
+    }
+
+    fn value(&self, fmt: String, dnd_indicator: &str) -> WmResult<String> {
         let (name, workspace_id): (String, String) =
             (self.name.clone(), format!("{}", self.workspace_id));
         let mut output = String::new();
@@ -73,7 +88,9 @@ impl WorkspaceInfoSegment {
                 in_brace = false;
                 match &brace_value[..] {
                     "name" => output.push_str(&name),
+                    "icon" => output.push_str(&self.icon),
                     "id" => output.push_str(&workspace_id),
+                    "dnd" if self.dnd => output.push_str(dnd_indicator),
                     _ => (),
                 };
                 brace_value.clear();
@@ -90,13 +107,13 @@ impl WorkspaceInfoSegment {
     }
 
     fn draw(
-        &self,
+        &mut self,
         cr: &Context,
         settings: &WorkspaceSegmentSettings,
         geometry: Geometry,
     ) -> WmResult {
         utils::cairo_font_from_str(cr, &settings.font)?;
-        let text = self.value(settings.format.clone())?;
+        let text = self.value(settings.format.clone(), &settings.dnd_indicator)?;
         let extents: TextExtents = cr.text_extents(&format!("-{text}-"))?.into();
         let (x, y) = cr.current_point()?;
 
@@ -121,6 +138,7 @@ impl WorkspaceInfoSegment {
 
         cr.move_to(x, y);
         cr.show_text(&text)?;
+        self.x_range = Some((x, cr.current_point()?.0));
 
         Ok(())
     }
@@ -137,7 +155,7 @@ impl WorkspaceInfoSegment {
             cr.set_font_size(size);
         }
         let ext = cr
-            .text_extents(&self.value(settings.format.clone())?)?
+            .text_extents(&self.value(settings.format.clone(), &settings.dnd_indicator)?)?
             .into();
 
         Ok(ext)
@@ -165,6 +183,13 @@ impl WorkspaceInfo {
         Ok(())
     }
 
+    /// Mark the given workspaces as being in "do not disturb" mode, and the others as not.
+    pub fn set_dnd(&mut self, workspaces: &[WorkspaceId]) {
+        for segment in self.workspaces.iter_mut() {
+            segment.dnd = workspaces.contains(&segment.workspace_id);
+        }
+    }
+
     pub fn set_open(&mut self, ws: Option<WorkspaceId>) -> WmResult {
         if let Some(workspace_id) = ws {
             for segment in self.workspaces.iter_mut() {
@@ -178,7 +203,9 @@ impl WorkspaceInfo {
         let mut buffer = String::new();
 
         for workspace in self.workspaces.iter() {
-            buffer.push_str(&workspace.value(self.settings.format.clone())?)
+            buffer.push_str(
+                &workspace.value(self.settings.format.clone(), &self.settings.dnd_indicator)?,
+            )
         }
 
         Ok(buffer)
@@ -194,16 +221,29 @@ impl WorkspaceInfo {
         Ok(extents)
     }
 
-    pub fn draw(&self, cr: &Context, position: Option<(f32, f32)>, geometry: Geometry) -> WmResult {
+    pub fn draw(
+        &mut self,
+        cr: &Context,
+        position: Option<(f32, f32)>,
+        geometry: Geometry,
+    ) -> WmResult {
         if let Some((x, y)) = position {
             cr.move_to(x.into(), y.into());
         }
-        for part in self.workspaces.iter() {
+        for part in self.workspaces.iter_mut() {
             part.draw(cr, &self.settings, geometry)?
         }
 
         Ok(())
     }
+
+    /// Get the id of the workspace drawn at the given x coordinate of the bar, if any.
+    pub fn workspace_at(&self, x: f64) -> Option<WorkspaceId> {
+        self.workspaces
+            .iter()
+            .find(|part| matches!(part.x_range, Some((start, end)) if start <= x && x < end))
+            .map(|part| part.workspace_id)
+    }
 }
 
 #[cfg(test)]
@@ -218,4 +258,47 @@ mod tests {
 
         assert_eq!(str.as_bytes(), sstr.as_ref().to_str().unwrap().as_bytes());
     }
+
+    #[test]
+    fn icon_format() {
+        let segment = super::WorkspaceInfoSegment::new("term", 1u32).with_icon("\u{f120}");
+
+        assert_eq!(
+            segment
+                .value("{icon} {name}:{id}".to_string(), "dnd")
+                .unwrap(),
+            "\u{f120} term:1"
+        );
+    }
+
+    #[test]
+    fn dnd_format() {
+        let mut info = super::WorkspaceInfo::from(crate::config::WorkspaceSegmentSettings {
+            format: "{name}{dnd}".to_string(),
+            dnd_indicator: " (dnd)".to_string(),
+            ..Default::default()
+        });
+        info.add(super::WorkspaceInfoSegment::new("web", 1u32));
+        info.add(super::WorkspaceInfoSegment::new("chat", 2u32));
+        info.set_dnd(&[2]);
+
+        assert_eq!(info._get_text().unwrap(), "webchat (dnd)");
+    }
+
+    #[test]
+    fn workspace_at() {
+        let mut info =
+            super::WorkspaceInfo::from(crate::config::WorkspaceSegmentSettings::default());
+        let mut web = super::WorkspaceInfoSegment::new("web", 1u32);
+        web.x_range = Some((0., 30.));
+        let mut chat = super::WorkspaceInfoSegment::new("chat", 2u32);
+        chat.x_range = Some((30., 70.));
+        info.add(web);
+        info.add(chat);
+        info.add(super::WorkspaceInfoSegment::new("mail", 3u32));
+
+        assert_eq!(info.workspace_at(10.), Some(1));
+        assert_eq!(info.workspace_at(30.), Some(2));
+        assert_eq!(info.workspace_at(70.), None);
+    }
 }
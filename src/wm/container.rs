@@ -1,12 +1,18 @@
 #![allow(dead_code)]
 use std::{collections::VecDeque, sync::Arc};
 
-use x11rb::protocol::xproto::{ChangeWindowAttributesAux, ConfigureWindowAux, ConnectionExt};
+use x11rb::protocol::xproto::{
+    ChangeGCAux, ChangeWindowAttributesAux, ConfigureWindowAux, ConnectionExt, CreateGCAux,
+    Rectangle,
+};
 
 use crate::{
     config::Config,
     errors::{Error, WmResult},
-    wm::geometry::{ClientAttributes, Geometry},
+    wm::{
+        actions::Direction,
+        geometry::{ClientAttributes, Geometry, SizeHints},
+    },
 };
 
 pub struct ContainerTypeMask(u8);
@@ -30,6 +36,52 @@ impl ContainerTypeMask {
 /// Unique identifier for a client.
 pub type ClientId = u64;
 
+/// The smallest width or height a client can be shrunk to by its gaps and borders.
+const MIN_CLIENT_SIZE: u16 = 1;
+
+/// Shrink a width or height by a number of pixels, without going below `MIN_CLIENT_SIZE`.
+fn shrink(length: u16, by: u32) -> u16 {
+    (length as u32)
+        .saturating_sub(by)
+        .max(MIN_CLIENT_SIZE as u32) as u16
+}
+
+/// Return the rectangles of a border pixmap, which should be painted with the inner border color.
+///
+/// The border pixmap is tiled with its origin at the top left corner of the window, so the right
+/// and bottom parts of the border come right after the window, while the left and top parts
+/// wrap around to the end of the pixmap.
+fn inner_border_rectangles(width: u16, height: u16, border: u16, inner: u16) -> [Rectangle; 4] {
+    let (right, bottom) = (width + 2 * border - inner, height + 2 * border - inner);
+
+    [
+        Rectangle {
+            x: 0,
+            y: 0,
+            width: width + inner,
+            height: height + inner,
+        },
+        Rectangle {
+            x: right as i16,
+            y: 0,
+            width: inner,
+            height: height + inner,
+        },
+        Rectangle {
+            x: 0,
+            y: bottom as i16,
+            width: width + inner,
+            height: inner,
+        },
+        Rectangle {
+            x: right as i16,
+            y: bottom as i16,
+            width: inner,
+            height: inner,
+        },
+    ]
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Unique identifier for containers.
 pub struct ContainerId {
@@ -73,6 +125,7 @@ pub struct Client {
     process_id: u32,
     pub geometry: Geometry,
     pub attributes: ClientAttributes,
+    pub size_hints: SizeHints,
     client_id: ClientId,
 }
 
@@ -91,6 +144,7 @@ impl Client {
             geometry: geometry.into(),
             client_id: client_id.into(),
             attributes: attrs,
+            size_hints: SizeHints::default(),
         }
     }
 
@@ -107,6 +161,7 @@ impl Client {
             geometry: geometry.into(),
             client_id: client_id.into(),
             attributes,
+            size_hints: SizeHints::default(),
         }
     }
 
@@ -125,29 +180,99 @@ impl Client {
         self.geometry
     }
 
-    pub fn with_gaps(&self) -> Geometry {
-        let mut geom = self.geometry();
-        geom.x += self.attributes.gap_left as i16;
-        geom.y += self.attributes.gap_top as i16;
-        geom.width -= 2 * self.attributes.gap_right as u16;
-        geom.height -= 2 * self.attributes.gap_bottom as u16;
+    /// Return the geometry of the client shrunk by its gaps, where `screen` is the area its
+    /// layout splits between the tiled clients.
+    ///
+    /// Every side facing another client loses half of its gap, so that two clients next to each
+    /// other end up a whole gap apart. The sides on the edges of `screen` are left alone, the space
+    /// between them and the edges of the monitor is the outer gap.
+    pub fn with_gaps(&self, screen: Geometry) -> Geometry {
+        let geom = self.geometry();
+        let attributes = &self.attributes;
+        let (right_edge, bottom_edge) = (
+            geom.x as i32 + geom.width as i32,
+            geom.y as i32 + geom.height as i32,
+        );
+
+        let left = if geom.x > screen.x {
+            attributes.gap_left - attributes.gap_left / 2
+        } else {
+            0
+        };
+        let top = if geom.y > screen.y {
+            attributes.gap_top - attributes.gap_top / 2
+        } else {
+            0
+        };
+        let right = if right_edge < screen.x as i32 + screen.width as i32 {
+            attributes.gap_right / 2
+        } else {
+            0
+        };
+        let bottom = if bottom_edge < screen.y as i32 + screen.height as i32 {
+            attributes.gap_bottom / 2
+        } else {
+            0
+        };
 
-        geom
+        Geometry {
+            x: geom.x.saturating_add(left.min(i16::MAX as u32) as i16),
+            y: geom.y.saturating_add(top.min(i16::MAX as u32) as i16),
+            width: shrink(geom.width, left + right),
+            height: shrink(geom.height, top + bottom),
+        }
     }
     pub fn with_gaps_inner(&self) -> Geometry {
         let mut geom = self.geometry();
-        geom.x += self.attributes.gap_left as i16 / 2;
-        geom.y += self.attributes.gap_top as i16 / 2;
-        geom.width -= self.attributes.gap_right as u16;
-        geom.height -= self.attributes.gap_bottom as u16;
+        geom.x = geom.x.saturating_add(self.attributes.gap_left as i16 / 2);
+        geom.y = geom.y.saturating_add(self.attributes.gap_top as i16 / 2);
+        geom.width = shrink(geom.width, self.attributes.gap_right);
+        geom.height = shrink(geom.height, self.attributes.gap_bottom);
 
         geom
     }
 
+    /// Return the geometry of the client, with the size of the window inside of its borders fit
+    /// to the client's size hints.
+    ///
+    /// A tiled window isn't made any smaller than its minimum size and is centered in its
+    /// geometry, when it doesn't fill it. A floating window keeps its position and doesn't grow
+    /// past its maximum size either.
+    pub fn with_size_hints(&self, tiled: bool) -> Geometry {
+        let geom = self.geometry();
+        let borders = (2 * self.attributes.border_size).min(u16::MAX as u32) as u16;
+        let (width, height) = self.size_hints.apply(
+            geom.width.saturating_sub(borders),
+            geom.height.saturating_sub(borders),
+            !tiled,
+        );
+        let (width, height) = (
+            width.saturating_add(borders),
+            height.saturating_add(borders),
+        );
+        if !tiled {
+            return Geometry {
+                width,
+                height,
+                ..geom
+            };
+        }
+
+        let center = |start: i16, cell: u16, length: u16| {
+            (start as i32 + (cell as i32 - length as i32) / 2) as i16
+        };
+        Geometry {
+            x: center(geom.x, geom.width, width),
+            y: center(geom.y, geom.height, height),
+            width,
+            height,
+        }
+    }
+
     pub fn with_borders(&self) -> (Geometry, u32, u16, u16, u16) {
-        let mut geom = self.with_gaps();
-        geom.width -= 2 * self.attributes.border_size as u16;
-        geom.height -= 2 * self.attributes.border_size as u16;
+        let mut geom = self.geometry();
+        geom.width = shrink(geom.width, 2 * self.attributes.border_size);
+        geom.height = shrink(geom.height, 2 * self.attributes.border_size);
         let bytes = self.attributes.border_color.to_le_bytes();
 
         (
@@ -169,6 +294,16 @@ impl Client {
         )
     }
 
+    pub fn inner_border_color(&self) -> (u16, u16, u16) {
+        let bytes = self.attributes.inner_border_color.to_le_bytes();
+
+        (
+            (bytes[2] as u16) << 8 | (bytes[2] as u16),
+            (bytes[1] as u16) << 8 | (bytes[1] as u16),
+            (bytes[0] as u16) << 8 | (bytes[0] as u16),
+        )
+    }
+
     pub fn border_width(&self) -> u32 {
         self.attributes.border_size
     }
@@ -182,7 +317,8 @@ impl Client {
         connection: Arc<C>,
         default_colormap: u32,
     ) -> WmResult {
-        connection.configure_window(self.window_id(), &self.with_borders().0.into())?;
+        let geometry = self.with_borders().0;
+        connection.configure_window(self.window_id(), &geometry.into())?;
 
         let border_colors = self.border_color();
         let border_size = self.border_width();
@@ -195,10 +331,16 @@ impl Client {
             )?
             .reply()?
             .pixel;
-        connection.change_window_attributes(
-            self.window_id(),
-            &ChangeWindowAttributesAux::new().border_pixel(pixel),
-        )?;
+
+        if self.attributes.inner_border_size == 0 {
+            connection.change_window_attributes(
+                self.window_id(),
+                &ChangeWindowAttributesAux::new().border_pixel(pixel),
+            )?;
+        } else {
+            self.draw_double_border(connection.clone(), default_colormap, geometry, pixel)?;
+        }
+
         connection.configure_window(
             self.window_id(),
             &ConfigureWindowAux::new().border_width(Some(border_size)),
@@ -207,6 +349,64 @@ impl Client {
 
         Ok(())
     }
+
+    /// Paint the border with a pixmap, which has the inner border drawn over the outer border
+    /// color.
+    fn draw_double_border<C: x11rb::connection::Connection>(
+        &self,
+        connection: Arc<C>,
+        default_colormap: u32,
+        geometry: Geometry,
+        outer_pixel: u32,
+    ) -> WmResult {
+        let window = self.window_id();
+        let border = self.border_width() as u16;
+        let inner = self.attributes.inner_border_size.min(border as u32) as u16;
+        let (width, height) = (geometry.width + 2 * border, geometry.height + 2 * border);
+
+        let inner_colors = self.inner_border_color();
+        let inner_pixel = connection
+            .alloc_color(
+                default_colormap,
+                inner_colors.0,
+                inner_colors.1,
+                inner_colors.2,
+            )?
+            .reply()?
+            .pixel;
+        let depth = connection.get_geometry(window)?.reply()?.depth;
+
+        let pixmap = connection.generate_id()?;
+        let gc = connection.generate_id()?;
+        connection.create_pixmap(depth, pixmap, window, width, height)?;
+        connection.create_gc(gc, pixmap, &CreateGCAux::new().foreground(outer_pixel))?;
+        connection.poly_fill_rectangle(
+            pixmap,
+            gc,
+            &[Rectangle {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            }],
+        )?;
+        connection.change_gc(gc, &ChangeGCAux::new().foreground(inner_pixel))?;
+        connection.poly_fill_rectangle(
+            pixmap,
+            gc,
+            &inner_border_rectangles(geometry.width, geometry.height, border, inner),
+        )?;
+        connection.change_window_attributes(
+            window,
+            &ChangeWindowAttributesAux::new().border_pixmap(pixmap),
+        )?;
+
+        connection.free_gc(gc)?;
+        connection.free_pixmap(pixmap)?;
+        connection.free_colors(default_colormap, 0, &[inner_pixel])?;
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -221,6 +421,8 @@ pub struct Container {
     container_type: ContainerType,
     container_id: ContainerId,
     last_position: Option<(i32, i32)>,
+    /// How many tiled containers were in front of this one when it was floated.
+    tiled_index: Option<usize>,
 }
 
 impl Container {
@@ -240,6 +442,7 @@ impl Container {
             container_type,
             container_id: id.into(),
             last_position: None,
+            tiled_index: None,
         }
     }
 
@@ -460,6 +663,76 @@ impl ContainerList {
         id
     }
 
+    /// Given a Client and a container type mask, create a new container and insert it right before
+    /// or right after the container with the given id.
+    pub fn insert_next_to<C: Into<Client>, I: Into<u8>, ID: Into<ContainerId>>(
+        &mut self,
+        client: C,
+        container_type_mask: I,
+        next_to: ID,
+        after: bool,
+    ) -> WmResult<ContainerId> {
+        let next_to = next_to.into();
+        let index = self.inner_find(next_to).ok_or_else(|| {
+            Error::Generic(format!("container list error: unable to find {next_to}"))
+        })?;
+        let id = self.new_id();
+        let cont = Container::new(client, id, container_type_mask);
+        self.containers
+            .insert(if after { index + 1 } else { index }, cont);
+
+        Ok(id)
+    }
+
+    /// Float the `Container` with the given `ContainerId`, remembering its position among the
+    /// tiled containers, so that `tile` can put it back there.
+    pub fn float<C: Into<ContainerId>>(&mut self, container_id: C) -> WmResult {
+        let c = container_id.into();
+        let index = self
+            .inner_find(c)
+            .ok_or_else(|| Error::Generic(format!("container list error: unable to find {c}")))?;
+        let tiled_index = self
+            .containers
+            .iter()
+            .take(index)
+            .filter(|c| c.is_in_layout())
+            .count();
+
+        let container = &mut self.containers[index];
+        container.change_to_floating()?;
+        container.tiled_index = Some(tiled_index);
+
+        Ok(())
+    }
+
+    /// Tile the floating `Container` with the given `ContainerId` again. With `keep_slot`, it's
+    /// moved back to the position among the tiled containers it had before `float`, or to the end
+    /// of the list, if there aren't that many tiled containers anymore.
+    pub fn tile<C: Into<ContainerId>>(&mut self, container_id: C, keep_slot: bool) -> WmResult {
+        let c = container_id.into();
+        let index = self
+            .inner_find(c)
+            .ok_or_else(|| Error::Generic(format!("container list error: unable to find {c}")))?;
+        self.containers[index].change_to_layout()?;
+        let tiled_index = match self.containers[index].tiled_index.take() {
+            Some(tiled_index) if keep_slot => tiled_index,
+            _ => return Ok(()),
+        };
+
+        let container = self.containers.remove(index).unwrap();
+        let new_index = self
+            .containers
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_in_layout())
+            .nth(tiled_index)
+            .map(|(i, _)| i)
+            .unwrap_or(self.containers.len());
+        self.containers.insert(new_index, container);
+
+        Ok(())
+    }
+
     /// Given to `ContainerId`s, first validate them and them swap the `Container`s in place.
     pub fn swap<I: Into<ContainerId>>(&mut self, a: I, b: I) -> WmResult {
         let a = a.into();
@@ -496,6 +769,11 @@ impl ContainerList {
         self.containers.iter()
     }
 
+    /// Return the number of `Container`s that are of the type of `InLayout`.
+    pub fn in_layout_count(&self) -> usize {
+        self.containers.iter().filter(|x| x.is_in_layout()).count()
+    }
+
     /// Mutably iterate over the `Container`s in the container list, while also returning the
     /// number of `Container`s that are of the type of `InLayout`.
     pub fn iter_in_layout_mut(
@@ -585,6 +863,98 @@ impl ContainerList {
         Err("container list error: unable to get next container!".into())
     }
 
+    /// Starting from the `Container` with the given `ContainerId`, walk the list forwards or
+    /// backwards, wrapping around, and return the first other `Container` for which `filter`
+    /// returns true.
+    pub fn next_matching_for_id<C: Into<ContainerId>>(
+        &self,
+        id: C,
+        forwards: bool,
+        filter: impl Fn(&Container) -> bool,
+    ) -> WmResult<Option<&Container>> {
+        let id = id.into();
+        let index = self
+            .inner_find(id)
+            .ok_or_else(|| Error::Generic(format!("container list error: unable to find {id}")))?;
+        let len = self.containers.len();
+
+        Ok((1..len)
+            .map(|offset| {
+                if forwards {
+                    (index + offset) % len
+                } else {
+                    (index + len - offset) % len
+                }
+            })
+            .filter_map(|i| self.containers.get(i))
+            .find(|c| filter(c)))
+    }
+
+    /// Return an immutable reference to the in-layout `Container` whose geometry contains the point
+    /// at `x` and `y`, if there is one.
+    pub fn in_layout_at(&self, x: i16, y: i16) -> Option<&Container> {
+        self.containers
+            .iter()
+            .filter(|c| c.is_in_layout())
+            .find(|c| {
+                let g = c.data().geometry();
+                let (x, y) = (x as i32, y as i32);
+                x >= g.x as i32
+                    && x < g.x as i32 + g.width as i32
+                    && y >= g.y as i32
+                    && y < g.y as i32 + g.height as i32
+            })
+    }
+
+    /// Return an immutable reference to the closest in-layout `Container` lying in a spatial
+    /// `Direction` from the `Container` with the given `ContainerId`.
+    ///
+    /// Containers are compared by the centers of their geometries. The distance along the
+    /// direction is added to twice the distance across it, so that containers in the same row or
+    /// column are preferred, ties are broken by the Euclidean distance. Returns `None` for
+    /// non-spatial directions or if there's no such container.
+    pub fn nearest_in_direction<C: Into<ContainerId>>(
+        &self,
+        id: C,
+        direction: Direction,
+    ) -> WmResult<Option<&Container>> {
+        let from = self.find(id)?;
+        let center = |c: &Container| {
+            let g = c.data().geometry();
+            (
+                g.x as i32 + g.width as i32 / 2,
+                g.y as i32 + g.height as i32 / 2,
+            )
+        };
+        let (x, y) = center(from);
+
+        let nearest = self
+            .containers
+            .iter()
+            .filter(|c| c.is_in_layout() && c.container_id != from.container_id)
+            .filter_map(|c| {
+                let (cx, cy) = center(c);
+                let (along, across) = match direction {
+                    Direction::Left => (x - cx, cy - y),
+                    Direction::Right => (cx - x, cy - y),
+                    Direction::Up => (y - cy, cx - x),
+                    Direction::Down => (cy - y, cx - x),
+                    Direction::Next | Direction::Previous => return None,
+                };
+
+                if along > 0 {
+                    let euclidean = along * along + across * across;
+                    Some(((along + 2 * across.abs(), euclidean), c))
+                } else {
+                    None
+                }
+            })
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, c)| c);
+
+        Ok(nearest)
+    }
+
     /// Given an already existing `Container`, generate a new id for it and insert it into the back
     /// of the list, returning the new `ContainerId`.
     pub fn container_insert_back(&mut self, mut container: Container) -> WmResult<ContainerId> {
@@ -593,4 +963,256 @@ impl ContainerList {
         self.containers.push_back(container);
         Ok(new_id)
     }
+
+    /// Given an already existing `Container`, generate a new id for it and insert it into the
+    /// front of the list, returning the new `ContainerId`.
+    pub fn container_insert_front(&mut self, mut container: Container) -> WmResult<ContainerId> {
+        let new_id = self.new_id();
+        container.container_id = new_id;
+        self.containers.push_front(container);
+        Ok(new_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> (ContainerList, Vec<ContainerId>) {
+        let config = Config::default();
+        let mut list = ContainerList::new(1);
+        let ids = [(0, 0), (100, 0), (0, 100), (100, 100)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (x, y))| {
+                let geometry = Geometry {
+                    x,
+                    y,
+                    width: 100,
+                    height: 100,
+                };
+                let client = Client::new_without_process_id(i as u32 + 1, geometry, 0u64, &config);
+                list.insert_back(client, ContainerTypeMask::TILING)
+            })
+            .collect();
+
+        (list, ids)
+    }
+
+    #[test]
+    fn size_hints_in_layout() {
+        let geometry = Geometry {
+            x: 10,
+            y: 20,
+            width: 500,
+            height: 300,
+        };
+        let mut client = Client::new_without_process_id(1u32, geometry, 0u64, &Config::default());
+        client.attributes.border_size = 2;
+        client.size_hints = SizeHints {
+            base_size: (4, 4),
+            size_increment: (6, 13),
+            max_size: Some((200, 200)),
+            ..Default::default()
+        };
+
+        // the window is rounded down to whole increments and centered in its cell
+        let tiled = client.with_size_hints(true);
+        assert_eq!(
+            tiled,
+            Geometry {
+                x: 10,
+                y: 23,
+                width: 500,
+                height: 294,
+            }
+        );
+        // floating windows keep their position and honor the maximum size
+        let floating = client.with_size_hints(false);
+        assert_eq!((floating.x, floating.y), (10, 20));
+        assert_eq!((floating.width, floating.height), (200, 203));
+
+        client.size_hints.min_size = (600, 100);
+        let tiled = client.with_size_hints(true);
+        assert_eq!((tiled.x, tiled.width), (-42, 604));
+    }
+
+    #[test]
+    fn large_gaps_and_borders() {
+        let config = Config::default();
+        let geometry = Geometry {
+            x: 10,
+            y: 10,
+            width: 40,
+            height: 30,
+        };
+        let screen = Geometry {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 100,
+        };
+        let mut client = Client::new_without_process_id(1u32, geometry, 0u64, &config);
+        client.attributes.gap_right = 100;
+        client.attributes.gap_bottom = 40_000;
+        client.attributes.border_size = 100;
+
+        let gaps = client.with_gaps(screen);
+        assert_eq!(
+            (gaps.width, gaps.height),
+            (MIN_CLIENT_SIZE, MIN_CLIENT_SIZE)
+        );
+        let inner = client.with_gaps_inner();
+        assert_eq!(
+            (inner.width, inner.height),
+            (MIN_CLIENT_SIZE, MIN_CLIENT_SIZE)
+        );
+        let (borders, ..) = client.with_borders();
+        assert_eq!(
+            (borders.width, borders.height),
+            (MIN_CLIENT_SIZE, MIN_CLIENT_SIZE)
+        );
+
+        client.attributes.gap_right = 5;
+        client.attributes.gap_bottom = 5;
+        client.attributes.border_size = 2;
+        let gaps = client.with_gaps(screen);
+        assert_eq!((gaps.width, gaps.height), (38, 28));
+        let (borders, ..) = client.with_borders();
+        assert_eq!((borders.width, borders.height), (36, 26));
+    }
+
+    #[test]
+    fn in_layout_at() {
+        let (list, ids) = grid();
+
+        assert_eq!(list.in_layout_at(150, 50).map(|c| *c.id()), Some(ids[1]));
+        assert_eq!(list.in_layout_at(0, 199).map(|c| *c.id()), Some(ids[2]));
+        assert_eq!(list.in_layout_at(100, 100).map(|c| *c.id()), Some(ids[3]));
+        assert!(list.in_layout_at(200, 50).is_none());
+        assert!(list.in_layout_at(-1, 0).is_none());
+    }
+
+    #[test]
+    fn insert_next_to() {
+        let (mut list, ids) = grid();
+        let config = Config::default();
+        let geometry = Geometry::default();
+
+        let client = Client::new_without_process_id(5u32, geometry, 0u64, &config);
+        let before = list
+            .insert_next_to(client, ContainerTypeMask::TILING, ids[1], false)
+            .unwrap();
+        let client = Client::new_without_process_id(6u32, geometry, 0u64, &config);
+        let after = list
+            .insert_next_to(client, ContainerTypeMask::TILING, ids[1], true)
+            .unwrap();
+
+        let order: Vec<ContainerId> = list.iter().map(|c| *c.id()).collect();
+        assert_eq!(order, vec![ids[0], before, ids[1], after, ids[2], ids[3]]);
+        assert_eq!(list.find(before).unwrap().data().window_id(), Some(5));
+        assert_eq!(list.find(after).unwrap().data().window_id(), Some(6));
+        assert!(!ids.contains(&before) && !ids.contains(&after) && before != after);
+
+        let client = Client::new_without_process_id(7u32, geometry, 0u64, &config);
+        assert!(list
+            .insert_next_to(client, ContainerTypeMask::TILING, (2, 1), true)
+            .is_err());
+    }
+
+    #[test]
+    fn float_and_tile_keep_slot() {
+        let (mut list, ids) = grid();
+        let order = |list: &ContainerList| -> Vec<Option<u32>> {
+            list.iter().map(|c| c.data().window_id()).collect()
+        };
+
+        list.float(ids[1]).unwrap();
+        assert!(list.find(ids[1]).unwrap().is_floating());
+        list.tile(ids[1], true).unwrap();
+        assert!(list.find(ids[1]).unwrap().is_in_layout());
+        assert_eq!(order(&list), vec![Some(1), Some(2), Some(3), Some(4)]);
+
+        // the floating window is moved to the end of the list while it floats
+        list.float(ids[1]).unwrap();
+        let container = list.remove(ids[1]).unwrap();
+        let id = list.container_insert_back(container).unwrap();
+        list.tile(id, true).unwrap();
+        assert_eq!(order(&list), vec![Some(1), Some(2), Some(3), Some(4)]);
+
+        list.float(id).unwrap();
+        let container = list.remove(id).unwrap();
+        let id = list.container_insert_back(container).unwrap();
+        list.tile(id, false).unwrap();
+        assert_eq!(order(&list), vec![Some(1), Some(3), Some(4), Some(2)]);
+
+        // not enough tiled windows are left for the old slot
+        list.float(id).unwrap();
+        list.float(ids[0]).unwrap();
+        list.float(ids[2]).unwrap();
+        list.tile(id, true).unwrap();
+        assert_eq!(order(&list), vec![Some(1), Some(3), Some(4), Some(2)]);
+    }
+
+    #[test]
+    fn nearest_in_direction() {
+        let (list, ids) = grid();
+
+        let right = list.nearest_in_direction(ids[0], Direction::Right).unwrap();
+        assert_eq!(right.map(|c| *c.id()), Some(ids[1]));
+        let down = list.nearest_in_direction(ids[1], Direction::Down).unwrap();
+        assert_eq!(down.map(|c| *c.id()), Some(ids[3]));
+        let left = list.nearest_in_direction(ids[2], Direction::Left).unwrap();
+        assert!(left.is_none());
+    }
+
+    #[test]
+    fn nearest_in_direction_tie() {
+        let config = Config::default();
+        let mut list = ContainerList::new(1);
+        // both candidates score 160 to the right of the first container, the second one is closer
+        let ids: Vec<ContainerId> = [(0, 100), (100, 130), (40, 160)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (x, y))| {
+                let geometry = Geometry {
+                    x,
+                    y,
+                    width: 20,
+                    height: 20,
+                };
+                let client = Client::new_without_process_id(i as u32 + 1, geometry, 0u64, &config);
+                list.insert_back(client, ContainerTypeMask::TILING)
+            })
+            .collect();
+
+        let right = list.nearest_in_direction(ids[0], Direction::Right).unwrap();
+        assert_eq!(right.map(|c| *c.id()), Some(ids[2]));
+    }
+
+    #[test]
+    fn swap_right_in_grid() {
+        let (mut list, ids) = grid();
+
+        let right = *list
+            .nearest_in_direction(ids[2], Direction::Right)
+            .unwrap()
+            .unwrap()
+            .id();
+        assert_eq!(right, ids[3]);
+        list.swap(ids[2], right).unwrap();
+
+        let order: Vec<ContainerId> = list.iter().map(|c| *c.id()).collect();
+        assert_eq!(order, vec![ids[0], ids[1], ids[3], ids[2]]);
+    }
+
+    #[test]
+    fn inner_border() {
+        let rects = inner_border_rectangles(10, 8, 3, 1).map(|r| (r.x, r.y, r.width, r.height));
+
+        assert_eq!(
+            rects,
+            [(0, 0, 11, 9), (15, 0, 1, 9), (0, 13, 11, 1), (15, 13, 1, 1)]
+        );
+    }
 }
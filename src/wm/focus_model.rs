@@ -0,0 +1,170 @@
+//! The parts of the `focus_model` setting shared by everything which changes the focus: what the
+//! pointer focuses, and whether the pointer follows the focus.
+use crate::{config::settings::Settings, errors::WmResult, wm::geometry::Geometry};
+
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{ConnectionExt, InputFocus},
+    CURRENT_TIME, NONE,
+};
+
+/// The requests used to move the focus and the pointer.
+pub trait FocusConnection {
+    /// Give the input focus to a window.
+    fn set_focus(&self, window: u32) -> WmResult;
+    /// Move the pointer to a position on the root window.
+    fn warp_to(&self, root: u32, x: i16, y: i16) -> WmResult;
+}
+
+impl<C: Connection> FocusConnection for C {
+    fn set_focus(&self, window: u32) -> WmResult {
+        self.set_input_focus(InputFocus::PARENT, window, CURRENT_TIME)?;
+        Ok(())
+    }
+
+    fn warp_to(&self, root: u32, x: i16, y: i16) -> WmResult {
+        self.warp_pointer(NONE, root, 0, 0, 0, 0, x, y)?;
+        Ok(())
+    }
+}
+
+/// A way of the pointer to focus a window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointerFocus {
+    /// The pointer moved into the window.
+    Enter,
+    /// The window was clicked.
+    Click,
+}
+
+/// Does the pointer focus a window in this way with the focus model from the settings?
+pub fn focused_by_pointer(settings: &Settings, how: PointerFocus) -> bool {
+    match how {
+        PointerFocus::Enter => settings.focus_follows_pointer(),
+        PointerFocus::Click => settings.click_to_focus(),
+    }
+}
+
+/// Focus a window, if there is one, after it or its workspace got focused without the pointer,
+/// for example using the keyboard. The pointer is moved to the middle of `area` if it follows the
+/// focus, so that the focus doesn't jump back to the window under the pointer.
+pub fn focus_without_pointer(
+    connection: &impl FocusConnection,
+    settings: &Settings,
+    root: u32,
+    window: Option<u32>,
+    area: Geometry,
+) -> WmResult {
+    if let Some(window) = window {
+        connection.set_focus(window)?;
+    }
+    if settings.pointer_follows_focus() {
+        let (x, y) = area.center();
+        connection.warp_to(root, x, y)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::RefCell;
+
+    const ROOT: u32 = 1;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Call {
+        Focus(u32),
+        Warp(i16, i16),
+    }
+
+    #[derive(Default)]
+    struct MockConnection(RefCell<Vec<Call>>);
+
+    impl FocusConnection for MockConnection {
+        fn set_focus(&self, window: u32) -> WmResult {
+            self.0.borrow_mut().push(Call::Focus(window));
+            Ok(())
+        }
+
+        fn warp_to(&self, root: u32, x: i16, y: i16) -> WmResult {
+            assert_eq!(root, ROOT);
+            self.0.borrow_mut().push(Call::Warp(x, y));
+            Ok(())
+        }
+    }
+
+    fn settings(focus_model: &str) -> Settings {
+        Settings {
+            focus_model: focus_model.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn focus_across_monitors() {
+        // two 1920x1080 monitors next to each other, the window is on the right one
+        let right = Geometry {
+            x: 1920,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let window = Geometry {
+            x: 2000,
+            y: 100,
+            width: 800,
+            height: 600,
+        };
+
+        let expected = [
+            ("sloppy", vec![Call::Focus(10)]),
+            ("click", vec![Call::Focus(10)]),
+            (
+                "pointer-follows-focus",
+                vec![Call::Focus(10), Call::Warp(2400, 400)],
+            ),
+        ];
+        for (model, calls) in expected {
+            let connection = MockConnection::default();
+            focus_without_pointer(&connection, &settings(model), ROOT, Some(10), window).unwrap();
+            assert_eq!(connection.0.into_inner(), calls, "{model}");
+        }
+
+        // switching to the workspace of the other monitor
+        let expected = [
+            ("sloppy", vec![]),
+            ("click", vec![]),
+            ("pointer-follows-focus", vec![Call::Warp(2880, 540)]),
+        ];
+        for (model, calls) in expected {
+            let connection = MockConnection::default();
+            focus_without_pointer(&connection, &settings(model), ROOT, None, right).unwrap();
+            assert_eq!(connection.0.into_inner(), calls, "{model}");
+        }
+    }
+
+    #[test]
+    fn pointer_focus() {
+        let expected = [
+            ("sloppy", true, false),
+            ("click", false, true),
+            ("pointer-follows-focus", true, false),
+        ];
+        for (model, enter, click) in expected {
+            let settings = settings(model);
+            assert_eq!(
+                focused_by_pointer(&settings, PointerFocus::Enter),
+                enter,
+                "{model}"
+            );
+            assert_eq!(
+                focused_by_pointer(&settings, PointerFocus::Click),
+                click,
+                "{model}"
+            );
+        }
+    }
+}
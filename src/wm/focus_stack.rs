@@ -51,6 +51,17 @@ impl FocusStack {
         }
     }
 
+    /// Return the most recently focused client other than `current`, forgetting the clients for
+    /// which `is_alive` returns false.
+    pub fn last_client_except(
+        &mut self,
+        current: Option<u32>,
+        is_alive: impl Fn(u32) -> bool,
+    ) -> Option<u32> {
+        self.data.retain(|c| is_alive(*c));
+        self.data.iter().copied().find(|c| Some(*c) != current)
+    }
+
     pub fn remove_client(&mut self, c: u32) {
         let tuple = self.data.iter().enumerate().find(|(_, d)| d == &&c);
 
@@ -59,3 +70,23 @@ impl FocusStack {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_client() {
+        let mut stack = FocusStack::new(0);
+        stack.set_focused_client(1);
+        stack.set_focused_client(2);
+        assert_eq!(stack.last_client_except(Some(2), |_| true), Some(1));
+
+        stack.set_focused_client(1);
+        assert_eq!(stack.last_client_except(Some(1), |_| true), Some(2));
+
+        stack.set_focused_client(3);
+        assert_eq!(stack.last_client_except(Some(3), |c| c != 1), Some(2));
+        assert_eq!(stack.last_client_except(Some(3), |c| c != 2), None);
+    }
+}
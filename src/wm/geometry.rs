@@ -15,6 +15,73 @@ pub struct Geometry {
 }
 
 impl Geometry {
+    /// Does the point lie within the geometry? The right and bottom edges are outside of it.
+    pub fn contains_point(&self, x: i16, y: i16) -> bool {
+        let (x, y) = (x as i32, y as i32);
+
+        x >= self.x as i32
+            && x < self.x as i32 + self.width as i32
+            && y >= self.y as i32
+            && y < self.y as i32 + self.height as i32
+    }
+
+    /// Return the point in the middle of the geometry.
+    pub fn center(&self) -> (i16, i16) {
+        (
+            (self.x as i32 + self.width as i32 / 2) as i16,
+            (self.y as i32 + self.height as i32 / 2) as i16,
+        )
+    }
+
+    /// Return the geometry with `margin` pixels taken away from every side, as long as there is
+    /// anything left in the middle.
+    pub fn shrunk_by(self, margin: u32) -> Self {
+        let margin_x = margin.min(self.width.saturating_sub(1) as u32 / 2) as u16;
+        let margin_y = margin.min(self.height.saturating_sub(1) as u32 / 2) as u16;
+
+        Self {
+            x: self.x.saturating_add(margin_x as i16),
+            y: self.y.saturating_add(margin_y as i16),
+            width: self.width - 2 * margin_x,
+            height: self.height - 2 * margin_y,
+        }
+    }
+
+    /// Return a geometry of the given size, centered at the point, moved and shrunk as little as
+    /// possible to fit inside of `area`.
+    pub fn centered_within(width: u16, height: u16, center: (i16, i16), area: Self) -> Self {
+        let width = width.min(area.width);
+        let height = height.min(area.height);
+        let place = |center: i16, length: u16, start: i16, area_length: u16| {
+            let start = start as i32;
+            let end = start + area_length as i32 - length as i32;
+            (center as i32 - length as i32 / 2).clamp(start, end) as i16
+        };
+
+        Self {
+            x: place(center.0, width, area.x, area.width),
+            y: place(center.1, height, area.y, area.height),
+            width,
+            height,
+        }
+    }
+
+    /// Return the geometry with the given number of pixels taken away from each side, keeping at
+    /// least a single pixel in each direction.
+    pub fn padded(self, top: u16, bottom: u16, left: u16, right: u16) -> Self {
+        let top = top.min(self.height.saturating_sub(1));
+        let bottom = bottom.min(self.height.saturating_sub(1) - top);
+        let left = left.min(self.width.saturating_sub(1));
+        let right = right.min(self.width.saturating_sub(1) - left);
+
+        Self {
+            x: self.x.saturating_add(left as i16),
+            y: self.y.saturating_add(top as i16),
+            width: self.width - left - right,
+            height: self.height - top - bottom,
+        }
+    }
+
     pub fn minus_bar(self, other: Self) -> Self {
         // +--------------------------------------+
         // |xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx|
@@ -32,25 +99,115 @@ impl Geometry {
         // |--------------------------------------|
         // |**************************************|
         // |--------------------------------------+
-        let mut height = self.height;
-        let width = self.width;
+        //
+        // Only the part of the bar which overlaps the geometry is taken away, so that a bar lying
+        // in the padding of a monitor doesn't take any more space.
+        let (top, bottom) = (self.y as i32, self.y as i32 + self.height as i32);
+        let (bar_top, bar_bottom) = (other.y as i32, other.y as i32 + other.height as i32);
 
         let mut y_0 = self.y;
-
-        if y_0 == other.y {
-            height -= other.height;
-            y_0 += other.height as i16;
+        let height = if bar_top <= top {
+            let cut = (bar_bottom - top).clamp(0, self.height as i32);
+            y_0 += cut as i16;
+            self.height - cut as u16
         } else {
-            height -= other.height
-        }
+            self.height - (bottom - bar_top).clamp(0, self.height as i32) as u16
+        };
 
         Self {
             x: self.x,
             y: y_0,
-            width,
+            width: self.width,
             height,
         }
     }
+
+    /// Return the part of the geometry which isn't covered by the space reserved by a dock.
+    ///
+    /// `root_width` and `root_height` are the size of the root window, whose edges the strut is
+    /// relative to.
+    pub fn minus_strut(self, strut: &Strut, root_width: u16, root_height: u16) -> Self {
+        let (x, y) = (self.x as i32, self.y as i32);
+        let (width, height) = (self.width as i32, self.height as i32);
+        let overlaps = |(start, end): (u32, u32), from: i32, length: i32| {
+            (start as i64) < (from + length) as i64 && (end as i64) >= from as i64
+        };
+        let cut = |reserved: i32, length: i32| reserved.max(0).min(length);
+
+        let mut left = 0;
+        let mut right = 0;
+        let mut top = 0;
+        let mut bottom = 0;
+        if strut.left > 0 && overlaps(strut.left_range, y, height) {
+            left = cut(strut.left as i32 - x, width);
+        }
+        if strut.right > 0 && overlaps(strut.right_range, y, height) {
+            right = cut(
+                x + width - (root_width as i32 - strut.right as i32),
+                width - left,
+            );
+        }
+        if strut.top > 0 && overlaps(strut.top_range, x, width) {
+            top = cut(strut.top as i32 - y, height);
+        }
+        if strut.bottom > 0 && overlaps(strut.bottom_range, x, width) {
+            bottom = cut(
+                y + height - (root_height as i32 - strut.bottom as i32),
+                height - top,
+            );
+        }
+
+        Self {
+            x: (x + left) as i16,
+            y: (y + top) as i16,
+            width: (width - left - right) as u16,
+            height: (height - top - bottom) as u16,
+        }
+    }
+}
+
+/// Space reserved by a dock window on the edges of the root window, as advertised in its
+/// `_NET_WM_STRUT_PARTIAL` or `_NET_WM_STRUT` property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Strut {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+    /// The first and last y coordinate the left strut spans.
+    pub left_range: (u32, u32),
+    /// The first and last y coordinate the right strut spans.
+    pub right_range: (u32, u32),
+    /// The first and last x coordinate the top strut spans.
+    pub top_range: (u32, u32),
+    /// The first and last x coordinate the bottom strut spans.
+    pub bottom_range: (u32, u32),
+}
+
+impl Strut {
+    /// Create a strut from the values of a `_NET_WM_STRUT_PARTIAL`(12 values) or `_NET_WM_STRUT`(4
+    /// values) property. A strut without the ranges spans the whole edge.
+    pub fn from_values(values: &[u32]) -> Option<Self> {
+        if values.len() < 4 || values[..4].iter().all(|v| *v == 0) {
+            return None;
+        }
+
+        let range = |i: usize| match values.get(i..i + 2) {
+            Some(&[start, end]) if values.len() >= 12 => (start, end),
+            _ => (0, u32::MAX),
+        };
+
+        Some(Self {
+            left: values[0],
+            right: values[1],
+            top: values[2],
+            bottom: values[3],
+            left_range: range(4),
+            right_range: range(6),
+            top_range: range(8),
+            bottom_range: range(10),
+        })
+    }
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -62,6 +219,8 @@ pub struct ClientAttributes {
 
     pub border_size: u32,
     pub border_color: u32,
+    pub inner_border_size: u32,
+    pub inner_border_color: u32,
 }
 
 impl std::fmt::Display for Geometry {
@@ -119,6 +278,104 @@ impl From<&WmSizeHints> for Geometry {
     }
 }
 
+/// The sizes a window asks to be given, from its `WM_NORMAL_HINTS` property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeHints {
+    pub min_size: (u16, u16),
+    pub max_size: Option<(u16, u16)>,
+    /// Size the increments are counted from.
+    pub base_size: (u16, u16),
+    pub size_increment: (u16, u16),
+}
+
+impl Default for SizeHints {
+    fn default() -> Self {
+        Self {
+            min_size: (0, 0),
+            max_size: None,
+            base_size: (0, 0),
+            size_increment: (1, 1),
+        }
+    }
+}
+
+impl SizeHints {
+    /// Return the largest size, no larger than `width` and `height`, which is a whole number of
+    /// increments bigger than the base size, but at least the minimum size. With `limit`, the size
+    /// is also kept below the maximum size.
+    pub fn apply(&self, width: u16, height: u16, limit: bool) -> (u16, u16) {
+        let fit = |length: u16, min: u16, max: Option<u16>, base: u16, increment: u16| {
+            let length = match max.filter(|_| limit) {
+                Some(max) => length.min(max),
+                None => length,
+            };
+            let length = if length > base {
+                length - (length - base) % increment.max(1)
+            } else {
+                length
+            };
+
+            length.max(min).max(1)
+        };
+
+        (
+            fit(
+                width,
+                self.min_size.0,
+                self.max_size.map(|m| m.0),
+                self.base_size.0,
+                self.size_increment.0,
+            ),
+            fit(
+                height,
+                self.min_size.1,
+                self.max_size.map(|m| m.1),
+                self.base_size.1,
+                self.size_increment.1,
+            ),
+        )
+    }
+}
+
+impl From<&WmSizeHints> for SizeHints {
+    fn from(o: &WmSizeHints) -> Self {
+        let size = |s: (i32, i32)| {
+            (
+                s.0.clamp(0, u16::MAX as i32) as u16,
+                s.1.clamp(0, u16::MAX as i32) as u16,
+            )
+        };
+        // either one of the minimum and the base size stands in for the other one, when missing
+        let min_size = o.min_size.or(o.base_size).map(size).unwrap_or((0, 0));
+        let base_size = o.base_size.or(o.min_size).map(size).unwrap_or((0, 0));
+        let size_increment = o
+            .size_increment
+            .map(size)
+            .map(|(w, h)| (w.max(1), h.max(1)))
+            .unwrap_or((1, 1));
+
+        Self {
+            min_size,
+            max_size: o.max_size.map(size).filter(|(w, h)| *w > 0 && *h > 0),
+            base_size,
+            size_increment,
+        }
+    }
+}
+
+impl ClientAttributes {
+    /// Return the same attributes, with all the gaps set to zero.
+    pub fn without_gaps(self) -> Self {
+        Self {
+            gap_top: 0,
+            gap_bottom: 0,
+            gap_left: 0,
+            gap_right: 0,
+            ..self
+        }
+    }
+}
+
 impl From<Config> for ClientAttributes {
     fn from(c: Config) -> Self {
         let gaps = c.settings.get_gaps();
@@ -131,6 +388,8 @@ impl From<Config> for ClientAttributes {
             gap_right: gaps.3,
             border_size: border,
             border_color,
+            inner_border_size: c.settings.get_inner_border(),
+            inner_border_color: c.settings.convert_inner_border_color(),
         }
     }
 }
@@ -204,3 +463,155 @@ impl std::ops::Sub for Geometry {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_hints() {
+        let mut wm_hints = WmSizeHints::new();
+        wm_hints.base_size = Some((4, 4));
+        wm_hints.size_increment = Some((6, 13));
+        wm_hints.max_size = Some((400, 400));
+        let hints = SizeHints::from(&wm_hints);
+        assert_eq!(hints.min_size, (4, 4));
+
+        assert_eq!(hints.apply(496, 296, false), (496, 290));
+        assert_eq!(hints.apply(496, 296, true), (400, 290));
+        assert_eq!(hints.apply(2, 9, false), (4, 4));
+
+        wm_hints.min_size = Some((100, 50));
+        let hints = SizeHints::from(&wm_hints);
+        assert_eq!(hints.base_size, (4, 4));
+        assert_eq!(hints.apply(60, 200, false), (100, 199));
+        assert_eq!(SizeHints::default().apply(123, 45, true), (123, 45));
+    }
+
+    #[test]
+    fn contains_point() {
+        let left = Geometry {
+            x: 0,
+            y: 0,
+            width: 1000,
+            height: 800,
+        };
+        let right = Geometry { x: 1000, ..left };
+
+        assert!(left.contains_point(999, 0));
+        assert!(!left.contains_point(1000, 0));
+        assert!(right.contains_point(1000, 799));
+        assert!(!right.contains_point(1500, 800));
+        assert_eq!(right.center(), (1500, 400));
+    }
+
+    #[test]
+    fn centered_within() {
+        let monitor = Geometry {
+            x: 1920,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+
+        assert_eq!(
+            Geometry::centered_within(800, 400, (2500, 500), monitor),
+            Geometry {
+                x: 2100,
+                y: 300,
+                width: 800,
+                height: 400
+            }
+        );
+        // a window at the pointer near the edge of the monitor is kept on the monitor
+        assert_eq!(
+            Geometry::centered_within(800, 400, (3800, 1070), monitor),
+            Geometry {
+                x: 3040,
+                y: 680,
+                width: 800,
+                height: 400
+            }
+        );
+        // and it's never bigger than the monitor
+        assert_eq!(
+            Geometry::centered_within(4000, 400, (0, 0), monitor),
+            Geometry {
+                x: 1920,
+                y: 0,
+                width: 1920,
+                height: 400
+            }
+        );
+    }
+
+    #[test]
+    fn minus_bar() {
+        let monitor = Geometry {
+            x: 0,
+            y: 100,
+            width: 1000,
+            height: 800,
+        };
+        let top = Geometry {
+            height: 20,
+            ..monitor
+        };
+        let bottom = Geometry { y: 880, ..top };
+
+        assert_eq!(
+            monitor.minus_bar(top),
+            Geometry {
+                y: 120,
+                height: 780,
+                ..monitor
+            }
+        );
+        assert_eq!(
+            monitor.minus_bar(bottom),
+            Geometry {
+                height: 780,
+                ..monitor
+            }
+        );
+        // windows tiled in what's left end right above the bottom bar
+        let screen = monitor.minus_bar(top).minus_bar(bottom);
+        assert_eq!(screen.y + screen.height as i16, bottom.y);
+    }
+
+    #[test]
+    fn strut() {
+        assert_eq!(Strut::from_values(&[0, 0, 0, 0]), None);
+
+        // a 30 pixel panel on the top of the left one of two 1000x800 monitors
+        let strut = Strut::from_values(&[0, 0, 30, 0, 0, 0, 0, 0, 0, 999, 0, 0]).unwrap();
+        let left = Geometry {
+            x: 0,
+            y: 0,
+            width: 1000,
+            height: 800,
+        };
+        let right = Geometry { x: 1000, ..left };
+
+        assert_eq!(
+            left.minus_strut(&strut, 2000, 800),
+            Geometry {
+                y: 30,
+                height: 770,
+                ..left
+            }
+        );
+        assert_eq!(right.minus_strut(&strut, 2000, 800), right);
+
+        // a dock spanning the whole right edge only affects the right monitor
+        let strut = Strut::from_values(&[0, 50, 0, 0]).unwrap();
+        assert_eq!(left.minus_strut(&strut, 2000, 800), left);
+        assert_eq!(
+            right.minus_strut(&strut, 2000, 800),
+            Geometry {
+                width: 950,
+                ..right
+            }
+        );
+    }
+}
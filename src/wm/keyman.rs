@@ -9,7 +9,15 @@ use super::actions::Action;
 struct ManagedKeybind {
     mask: u16,
     codes: Vec<u8>,
-    action: Action,
+    actions: Vec<Action>,
+}
+
+impl ManagedKeybind {
+    /// Does this keybind only run the `abort` action? Such keybinds are only grabbed while there
+    /// is something to abort and match regardless of the modifiers being held.
+    fn is_abort(&self) -> bool {
+        self.actions == [Action::Abort]
+    }
 }
 
 #[derive(Debug, Default)]
@@ -23,7 +31,7 @@ impl KeyManager {
     pub fn init(&mut self, dpy: *mut Display, keybinds: &Keybinds) -> WmResult {
         let mut managed_keybinds: Vec<ManagedKeybind> = Vec::new();
 
-        for (names, action) in keybinds.get_names_and_actions() {
+        for (names, actions) in keybinds.get_names_and_actions() {
             let (mut mask, mut key_codes) = (0, Vec::new());
             for name in names {
                 let mut keysym = Keysym::lookup_string(dpy, name)?;
@@ -37,7 +45,7 @@ impl KeyManager {
             managed_keybinds.push(ManagedKeybind {
                 mask,
                 codes: key_codes,
-                action,
+                actions,
             })
         }
 
@@ -48,7 +56,7 @@ impl KeyManager {
 
     pub fn get_floating_modifier(&self) -> Option<u16> {
         for managed in self.managed_keybinds.iter() {
-            if matches!(managed.action, Action::ToggleFloat) {
+            if managed.actions.contains(&Action::ToggleFloat) {
                 // println!("mask is: {}", managed.mask);
                 return Some(managed.mask);
             }
@@ -58,14 +66,18 @@ impl KeyManager {
     }
 
     /// Get a list of modifier key masks and a list of key codes.
-    /// These values are used to "grab" these keys in the X server.
+    /// These values are used to "grab" these keys in the X server. The `abort` keybinds are left
+    /// out, see [`KeyManager::abort_grabs`].
     pub fn get_codes_to_grab(
         &self,
         dpy: *mut Display,
         keybinds: &Keybinds,
     ) -> WmResult<Vec<(u16, Vec<u8>)>> {
         let mut ret = Vec::new();
-        for each in keybinds.get_names() {
+        for (each, actions) in keybinds.get_names_and_actions() {
+            if actions == [Action::Abort] {
+                continue;
+            }
             let mut masked_keys_pair = (0, Vec::new());
             for name in each {
                 let mut keysym = Keysym::lookup_string(dpy, name)?;
@@ -81,16 +93,48 @@ impl KeyManager {
         Ok(ret)
     }
 
+    /// Modifier masks and key codes of the `abort` keybinds, which are only grabbed while a
+    /// transient state, such as dragging a window, is active.
+    ///
+    /// The keys are grabbed with every combination of modifiers, except for those which other
+    /// keybinds already use, so that releasing the grabs leaves the other keybinds alone.
+    pub fn abort_grabs(&self) -> Vec<(u16, u8)> {
+        let mut grabs = Vec::new();
+        for code in self
+            .managed_keybinds
+            .iter()
+            .filter(|keybind| keybind.is_abort())
+            .flat_map(|keybind| keybind.codes.iter().copied())
+        {
+            for mask in 0..=u8::MAX as u16 {
+                let taken = self.managed_keybinds.iter().any(|keybind| {
+                    !keybind.is_abort() && keybind.codes == [code] && keybind.mask == mask
+                });
+                if !taken && !grabs.contains(&(mask, code)) {
+                    grabs.push((mask, code));
+                }
+            }
+        }
+
+        grabs
+    }
+
+    /// Forget the keys pressed so far.
+    pub fn reset(&mut self) {
+        self.keys.clear();
+        self.mask = 0;
+    }
+
     /// What to do on key press.
     pub fn on_key_press(
         &mut self,
         ev: &x11rb::protocol::xproto::KeyPressEvent,
-    ) -> WmResult<Option<Action>> {
+    ) -> WmResult<Option<Vec<Action>>> {
         self.keys.push(ev.detail);
         self.mask = ev.state;
         for keybind in &self.managed_keybinds {
-            if self.keys == keybind.codes && self.mask == keybind.mask {
-                return Ok(Some(keybind.action.clone()));
+            if self.keys == keybind.codes && (self.mask == keybind.mask || keybind.is_abort()) {
+                return Ok(Some(keybind.actions.clone()));
             }
         }
 
@@ -98,8 +142,45 @@ impl KeyManager {
     }
 
     pub fn on_key_release(&mut self, _ev: &x11rb::protocol::xproto::KeyReleaseEvent) -> WmResult {
-        self.keys.clear();
-        self.mask = 0;
+        self.reset();
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abort_grabs() {
+        let (escape, q) = (9, 24);
+        let (shift, mod4) = (1, 64);
+        let key_manager = KeyManager {
+            managed_keybinds: vec![
+                ManagedKeybind {
+                    mask: 0,
+                    codes: vec![escape],
+                    actions: vec![Action::Abort],
+                },
+                ManagedKeybind {
+                    mask: mod4 | shift,
+                    codes: vec![escape],
+                    actions: vec![Action::Kill],
+                },
+                ManagedKeybind {
+                    mask: mod4,
+                    codes: vec![q],
+                    actions: vec![Action::Kill],
+                },
+            ],
+            ..Default::default()
+        };
+
+        let grabs = key_manager.abort_grabs();
+        assert_eq!(grabs.len(), 255);
+        assert!(grabs.contains(&(0, escape)));
+        assert!(grabs.contains(&(mod4, escape)));
+        assert!(!grabs.contains(&(mod4 | shift, escape)));
+        assert!(grabs.iter().all(|(_, code)| *code == escape));
+    }
+}
@@ -9,6 +9,13 @@ use crate::{
 use std::sync::Arc;
 use x11rb::protocol::xproto::ConnectionExt;
 
+/// The part of the screen's width taken by the master window, by default.
+pub const DEFAULT_MASTER_RATIO: f32 = 0.5;
+/// The smallest part of the screen's width the master window can take.
+pub const MIN_MASTER_RATIO: f32 = 0.1;
+/// The largest part of the screen's width the master window can take.
+pub const MAX_MASTER_RATIO: f32 = 0.9;
+
 pub struct LayoutMask;
 
 impl LayoutMask {
@@ -16,10 +23,12 @@ impl LayoutMask {
     pub const TILING_EQUAL_VERTICAL: u64 = 1 << 1;
     pub const TILING_MASTER_STACK: u64 = 1 << 2;
     pub const STACKING: u64 = 1 << 3;
+    pub const TILING_DWINDLE: u64 = 1 << 4;
     pub const ALL: u64 = LayoutMask::TILING_EQUAL_HORIZONTAL
         | LayoutMask::TILING_EQUAL_VERTICAL
         | LayoutMask::TILING_MASTER_STACK
-        | LayoutMask::STACKING;
+        | LayoutMask::STACKING
+        | LayoutMask::TILING_DWINDLE;
 
     pub fn from_slice(slice: &[String]) -> WmResult<u64> {
         let mut mask = 0u64;
@@ -46,9 +55,59 @@ pub trait Layout<'a> {
         connection: Arc<C>,
         default_colormap: I,
         focused_client: Option<u32>,
+        master: MasterSettings,
     ) -> WmResult;
 }
 
+/// Settings of the master tile, used by the master-stack layout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MasterSettings {
+    /// Number of windows sharing the master area.
+    pub count: usize,
+    /// Fraction of the screen's width taken by the master tile.
+    pub ratio: f32,
+    /// Whether a lone window fills the whole screen instead of just the master's part.
+    pub fill_when_alone: bool,
+    /// How the windows next to the master are laid out.
+    pub stack_orientation: StackOrientation,
+}
+
+impl Default for MasterSettings {
+    fn default() -> Self {
+        Self {
+            count: 1,
+            ratio: DEFAULT_MASTER_RATIO,
+            fill_when_alone: true,
+            stack_orientation: StackOrientation::default(),
+        }
+    }
+}
+
+/// Direction in which the stack of the master-stack layout is split between its windows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StackOrientation {
+    /// The stack windows are placed below each other.
+    #[default]
+    Vertical,
+    /// The stack windows are placed next to each other.
+    Horizontal,
+}
+
+impl TryFrom<&str> for StackOrientation {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: &str) -> WmResult<Self> {
+        match value.to_lowercase().as_str() {
+            "vertical" => Ok(Self::Vertical),
+            "horizontal" => Ok(Self::Horizontal),
+            _ => Err(format!(
+                "layout error: {value} is not a valid stack orientation, expected vertical or horizontal."
+            )
+            .into()),
+        }
+    }
+}
+
 #[allow(clippy::enum_variant_names)]
 #[allow(unused)]
 #[repr(u64)]
@@ -58,6 +117,26 @@ pub enum LayoutType {
     TilingEqualVertical = LayoutMask::TILING_EQUAL_VERTICAL,
     TilingMasterStack = LayoutMask::TILING_MASTER_STACK,
     Stacking = LayoutMask::STACKING,
+    TilingDwindle = LayoutMask::TILING_DWINDLE,
+}
+
+impl LayoutType {
+    /// Names of all the available layouts.
+    pub const NAMES: &'static [&'static str] = &[
+        "tiling_equal_horizontal",
+        "tiling_equal_vertical",
+        "tiling_master_stack",
+        "stacking",
+        "tiling_dwindle",
+    ];
+
+    /// Short aliases for the layouts, in the same order as `NAMES`.
+    pub const ALIASES: &'static [&'static str] = &["eh", "ev", "ms", "st", "dw"];
+
+    /// Return the name of the layout, as listed in `NAMES`.
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[(*self as u64).trailing_zeros() as usize]
+    }
 }
 
 impl Default for LayoutType {
@@ -75,6 +154,7 @@ impl TryFrom<u64> for LayoutType {
             LayoutMask::TILING_EQUAL_VERTICAL => Ok(Self::TilingEqualVertical),
             LayoutMask::TILING_MASTER_STACK => Ok(Self::TilingMasterStack),
             LayoutMask::STACKING => Ok(Self::Stacking),
+            LayoutMask::TILING_DWINDLE => Ok(Self::TilingDwindle),
             _ => Err("layout error: invalid layout id.".into()),
         }
     }
@@ -84,12 +164,18 @@ impl TryFrom<&str> for LayoutType {
     type Error = crate::errors::Error;
     fn try_from(str: &str) -> Result<Self, Self::Error> {
         match str.to_lowercase().as_str() {
-            "tiling_equal_horizontal" => Ok(Self::TilingEqualHorizontal),
-            "tiling_equal_vertical" => Ok(Self::TilingEqualVertical),
-            "tiling_master_stack" => Ok(Self::TilingMasterStack),
-            "stacking" => Ok(Self::Stacking),
+            "tiling_equal_horizontal" | "eh" => Ok(Self::TilingEqualHorizontal),
+            "tiling_equal_vertical" | "ev" => Ok(Self::TilingEqualVertical),
+            "tiling_master_stack" | "ms" => Ok(Self::TilingMasterStack),
+            "stacking" | "st" => Ok(Self::Stacking),
+            "tiling_dwindle" | "dw" => Ok(Self::TilingDwindle),
             _ => {
-                Err(format!("layout error: \"{str}\" is not recognized as a valid layout.").into())
+                Err(format!(
+                    "layout error: \"{str}\" is not recognized as a valid layout. Valid layouts are: {}. Valid aliases are: {}",
+                    Self::NAMES.join(", "),
+                    Self::ALIASES.join(", ")
+                )
+                .into())
             }
         }
     }
@@ -103,6 +189,7 @@ impl<'a> Layout<'a> for LayoutType {
         connection: Arc<C>,
         default_colormap: I,
         focused_clinet: Option<u32>,
+        master: MasterSettings,
     ) -> WmResult {
         let default_colormap = default_colormap.into();
         match &self {
@@ -113,24 +200,27 @@ impl<'a> Layout<'a> for LayoutType {
                 }
                 let screen = screen.into();
 
-                let width = screen.width / len as u16;
-                let mut ii = -1;
+                let mut ii = 0;
 
                 for each in iter {
                     match each.data_mut() {
                         ContainerType::Empty(g) => {
+                            let (offset, width) = segment(screen.width, len, ii);
                             ii += 1;
                             g.y = screen.y;
-                            g.x = screen.x + (width as i16 * ii as i16);
+                            g.x = screen.x + offset as i16;
                             g.width = width;
                             g.height = screen.height;
                         }
                         ContainerType::InLayout(c) => {
+                            let (offset, width) = segment(screen.width, len, ii);
                             ii += 1;
-                            c.geometry.x = screen.x + (width as i16 * ii as i16);
+                            c.geometry.x = screen.x + offset as i16;
                             c.geometry.y = screen.y;
                             c.geometry.width = width;
                             c.geometry.height = screen.height;
+                            c.geometry = c.with_gaps(screen);
+                            c.geometry = c.with_size_hints(true);
                             c.draw_borders(connection.clone(), default_colormap)?;
                             connection.map_subwindows(c.window_id())?;
                             connection.map_window(c.window_id())?;
@@ -149,25 +239,28 @@ impl<'a> Layout<'a> for LayoutType {
 
                 let screen = screen.into();
 
-                let height = screen.height / len as u16;
-                let mut ii = -1;
+                let mut ii = 0;
 
                 for each in iter {
                     match each.data_mut() {
                         ContainerType::Floating(_) => (),
                         ContainerType::Empty(g) => {
+                            let (offset, height) = segment(screen.height, len, ii);
                             ii += 1;
                             g.x = screen.x;
-                            g.y = screen.y + height as i16 * ii as i16;
+                            g.y = screen.y + offset as i16;
                             g.width = screen.width;
                             g.height = height;
                         }
                         ContainerType::InLayout(c) => {
+                            let (offset, height) = segment(screen.height, len, ii);
                             ii += 1;
                             c.geometry.x = screen.x;
-                            c.geometry.y = screen.y + (height as i16 * ii as i16);
+                            c.geometry.y = screen.y + offset as i16;
                             c.geometry.width = screen.width;
                             c.geometry.height = height;
+                            c.geometry = c.with_gaps(screen);
+                            c.geometry = c.with_size_hints(true);
                             c.draw_borders(connection.clone(), default_colormap)?;
                             connection.map_subwindows(c.window_id())?;
                             connection.map_window(c.window_id())?;
@@ -183,76 +276,61 @@ impl<'a> Layout<'a> for LayoutType {
                     return Ok(());
                 }
 
-                let screen: Geometry = screen.into();
-                if len == 1 {
-                    for one in iter {
-                        match one.data_mut() {
-                            ContainerType::Empty(g) => {
-                                g.x = screen.x;
-                                g.y = screen.y;
-                                g.width = screen.width;
-                                g.height = screen.height;
-                            }
-                            ContainerType::InLayout(c) => {
-                                c.geometry.x = screen.x;
-                                c.geometry.y = screen.y;
-                                c.geometry.width = screen.width;
-                                c.geometry.height = screen.height;
-                                c.draw_borders(connection.clone(), default_colormap)?;
-                                connection.map_subwindows(c.window_id())?;
-                                connection.map_window(c.window_id())?;
-                            }
-                            _ => {}
-                        };
-                    }
-                    Ok(())
-                } else {
-                    let height = screen.height / (len - 1) as u16;
-                    let width = screen.width / 2;
-
-                    let mut ii = -2;
-                    for each in iter {
-                        match each.data_mut() {
-                            ContainerType::Empty(g) => {
-                                ii += 1;
-                                if ii == -1 {
-                                    g.x = 0;
-                                    g.y = 0;
-                                    g.width = width;
-                                    g.height = screen.height;
-                                } else {
-                                    g.x = width as i16;
-                                    g.y = height as i16 * ii;
-                                    g.width = width;
-                                    g.height = height;
-                                }
-                            }
-                            ContainerType::InLayout(c) => {
-                                ii += 1;
-                                if ii == -1 {
-                                    c.geometry.x = screen.x;
-                                    c.geometry.y = screen.y;
-                                    c.geometry.width = screen.width / 2;
-                                    c.geometry.height = screen.height;
-                                    c.draw_borders(connection.clone(), default_colormap)?;
-                                    connection.map_subwindows(c.window_id())?;
-                                    connection.map_window(c.window_id())?;
-                                } else {
-                                    c.geometry.x = screen.x + width as i16 - 1;
-                                    c.geometry.y = screen.y + height as i16 * ii;
-                                    c.geometry.width = screen.width / 2;
-                                    c.geometry.height = height;
-                                    c.draw_borders(connection.clone(), default_colormap)?;
-                                    connection.map_subwindows(c.window_id())?;
-                                    connection.map_window(c.window_id())?;
-                                }
-                            }
-                            _ => {}
+                let screen = screen.into();
+                let geometries = master_stack(screen, len, master);
+                let mut ii = 0;
+
+                for each in iter {
+                    match each.data_mut() {
+                        ContainerType::Floating(_) => (),
+                        ContainerType::Empty(g) => {
+                            *g = geometries[ii];
+                            ii += 1;
+                        }
+                        ContainerType::InLayout(c) => {
+                            c.geometry = geometries[ii];
+                            ii += 1;
+                            c.geometry = c.with_gaps(screen);
+                            c.geometry = c.with_size_hints(true);
+                            c.draw_borders(connection.clone(), default_colormap)?;
+                            connection.map_subwindows(c.window_id())?;
+                            connection.map_window(c.window_id())?;
                         }
                     }
+                }
 
-                    Ok(())
+                Ok(())
+            }
+            Self::TilingDwindle => {
+                let (len, iter) = cs;
+                if len == 0 {
+                    return Ok(());
                 }
+
+                let screen = screen.into();
+                let geometries = dwindle(screen, len);
+                let mut ii = 0;
+
+                for each in iter {
+                    match each.data_mut() {
+                        ContainerType::Floating(_) => (),
+                        ContainerType::Empty(g) => {
+                            *g = geometries[ii];
+                            ii += 1;
+                        }
+                        ContainerType::InLayout(c) => {
+                            c.geometry = geometries[ii];
+                            ii += 1;
+                            c.geometry = c.with_gaps(screen);
+                            c.geometry = c.with_size_hints(true);
+                            c.draw_borders(connection.clone(), default_colormap)?;
+                            connection.map_subwindows(c.window_id())?;
+                            connection.map_window(c.window_id())?;
+                        }
+                    }
+                }
+
+                Ok(())
             }
             Self::Stacking => {
                 let screen = screen.into();
@@ -268,6 +346,7 @@ impl<'a> Layout<'a> for LayoutType {
                                 c.geometry.y = screen.y;
                                 c.geometry.width = screen.width;
                                 c.geometry.height = screen.height;
+                                c.geometry = c.with_size_hints(true);
 
                                 c.draw_borders(connection.clone(), default_colormap)?;
                                 connection.map_subwindows(c.window_id())?;
@@ -291,8 +370,426 @@ impl<'a> Layout<'a> for LayoutType {
     }
 }
 
+/// Split `total` pixels into `parts` consecutive segments and return the offset and the length of
+/// the segment at `index`.
+///
+/// The remainder of the division is distributed across the first segments, one pixel each, so that
+/// the segments cover exactly `total` pixels, without any gaps or overlaps.
+fn segment(total: u16, parts: usize, index: usize) -> (u16, u16) {
+    let parts = parts.max(1) as u16;
+    let index = index as u16;
+    let base = total / parts;
+    let remainder = total % parts;
+
+    let length = base + u16::from(index < remainder);
+    let offset = base * index + index.min(remainder);
+
+    (offset, length)
+}
+
+/// Return the width of the master window, given the width of the screen and the master ratio.
+///
+/// The ratio is clamped between `MIN_MASTER_RATIO` and `MAX_MASTER_RATIO`.
+fn master_width(total: u16, ratio: f32) -> u16 {
+    let ratio = ratio.clamp(MIN_MASTER_RATIO, MAX_MASTER_RATIO);
+
+    (total as f32 * ratio).round() as u16
+}
+
+/// Split the screen into the master area, taking `ratio` of the screen's width, and the stack,
+/// which takes the rest of it. The first `master.count` tiles, but at least one, split the master
+/// area from top to bottom, the other tiles split the stack in the stack orientation. When there
+/// are no tiles left for the stack, the master area takes the whole screen, unless
+/// `fill_when_alone` is false, in which case it only takes the master's part.
+fn master_stack(screen: Geometry, count: usize, master: MasterSettings) -> Vec<Geometry> {
+    let masters = master.count.clamp(1, count.max(1));
+    let stacked = count.saturating_sub(masters);
+
+    let master_width = if stacked == 0 && master.fill_when_alone {
+        screen.width
+    } else {
+        master_width(screen.width, master.ratio)
+    };
+    let master_area = Geometry {
+        width: master_width,
+        ..screen
+    };
+    let stack = Geometry {
+        x: screen.x + master_width as i16,
+        width: screen.width - master_width,
+        ..screen
+    };
+    let mut tiles = Vec::with_capacity(count);
+
+    for ii in 0..masters {
+        let (offset, height) = segment(master_area.height, masters, ii);
+        tiles.push(Geometry {
+            y: master_area.y + offset as i16,
+            height,
+            ..master_area
+        });
+    }
+
+    for ii in 0..stacked {
+        tiles.push(match master.stack_orientation {
+            StackOrientation::Vertical => {
+                let (offset, height) = segment(stack.height, stacked, ii);
+                Geometry {
+                    y: stack.y + offset as i16,
+                    height,
+                    ..stack
+                }
+            }
+            StackOrientation::Horizontal => {
+                let (offset, width) = segment(stack.width, stacked, ii);
+                Geometry {
+                    x: stack.x + offset as i16,
+                    width,
+                    ..stack
+                }
+            }
+        });
+    }
+
+    tiles
+}
+
+/// Split the screen into `count` tiles, each of them taking half of the space left over by the
+/// tile before it. The cuts alternate between vertical and horizontal, starting with a vertical
+/// one, the last tile takes all the remaining space.
+fn dwindle(screen: Geometry, count: usize) -> Vec<Geometry> {
+    let mut rest = screen;
+    let mut tiles = Vec::with_capacity(count);
+
+    for ii in 0..count {
+        if ii + 1 == count {
+            tiles.push(rest);
+            break;
+        }
+
+        let mut tile = rest;
+        if ii % 2 == 0 {
+            let (_, width) = segment(rest.width, 2, 0);
+            tile.width = width;
+            rest.x += width as i16;
+            rest.width -= width;
+        } else {
+            let (_, height) = segment(rest.height, 2, 0);
+            tile.height = height;
+            rest.y += height as i16;
+            rest.height -= height;
+        }
+        tiles.push(tile);
+    }
+
+    tiles
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_names() {
+        for name in LayoutType::NAMES {
+            assert!(LayoutType::try_from(*name).is_ok());
+        }
+    }
+
+    #[test]
+    fn layout_name_round_trip() {
+        for name in LayoutType::NAMES {
+            assert_eq!(LayoutType::try_from(*name).unwrap().name(), *name);
+        }
+        assert_eq!(LayoutType::try_from("dw").unwrap().name(), "tiling_dwindle");
+    }
+
+    #[test]
+    fn layout_aliases() {
+        for (alias, name) in LayoutType::ALIASES.iter().zip(LayoutType::NAMES) {
+            let from_alias = LayoutType::try_from(*alias).unwrap();
+            let from_name = LayoutType::try_from(*name).unwrap();
+            assert_eq!(from_alias as u64, from_name as u64);
+        }
+        assert!(matches!(
+            LayoutType::try_from("MS"),
+            Ok(LayoutType::TilingMasterStack)
+        ));
+
+        let err = LayoutType::try_from("grid").unwrap_err().message();
+        for alias in LayoutType::ALIASES {
+            assert!(err.contains(alias));
+        }
+    }
+
+    #[test]
+    fn segments_cover_screen() {
+        for total in [1920u16, 1080, 1366, 1001] {
+            for parts in 2..=7 {
+                let segments: Vec<(u16, u16)> =
+                    (0..parts).map(|i| segment(total, parts, i)).collect();
+
+                let sum: u16 = segments.iter().map(|(_, length)| length).sum();
+                assert_eq!(sum, total, "{total} split into {parts}");
+
+                let mut next_offset = 0;
+                for (offset, length) in segments.iter() {
+                    assert_eq!(*offset, next_offset);
+                    next_offset += length;
+                }
+
+                let min = segments.iter().map(|s| s.1).min().unwrap();
+                let max = segments.iter().map(|s| s.1).max().unwrap();
+                assert!(max - min <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn master_widths() {
+        assert_eq!(master_width(1920, DEFAULT_MASTER_RATIO), 960);
+        assert_eq!(master_width(1921, DEFAULT_MASTER_RATIO), 961);
+        assert_eq!(master_width(1920, 0.6), 1152);
+        assert_eq!(master_width(1000, 0.0), 100);
+        assert_eq!(master_width(1000, 1.0), 900);
+    }
+
+    #[test]
+    fn master_stack_alone() {
+        let screen = Geometry {
+            x: 10,
+            y: 20,
+            width: 1000,
+            height: 800,
+        };
+
+        let filling = MasterSettings {
+            ratio: 0.6,
+            ..Default::default()
+        };
+        let not_filling = MasterSettings {
+            fill_when_alone: false,
+            ..filling
+        };
+
+        assert_eq!(master_stack(screen, 1, filling), vec![screen]);
+        assert_eq!(
+            master_stack(screen, 1, not_filling),
+            vec![Geometry {
+                width: 600,
+                ..screen
+            }]
+        );
+
+        let tiles = master_stack(screen, 3, not_filling);
+        assert_eq!(tiles[0], master_stack(screen, 1, not_filling)[0]);
+        assert_eq!((tiles[1].x, tiles[1].y, tiles[1].width), (610, 20, 400));
+        assert_eq!((tiles[2].y, tiles[2].height), (420, 400));
+    }
+
+    #[test]
+    fn master_stack_orientations() {
+        let screen = Geometry {
+            x: 10,
+            y: 20,
+            width: 1000,
+            height: 801,
+        };
+        let area = |tiles: &[Geometry]| -> u32 {
+            tiles.iter().map(|t| t.width as u32 * t.height as u32).sum()
+        };
+
+        let tiles = master_stack(screen, 3, MasterSettings::default());
+        assert_eq!(
+            tiles,
+            vec![
+                Geometry {
+                    width: 500,
+                    ..screen
+                },
+                Geometry {
+                    x: 510,
+                    y: 20,
+                    width: 500,
+                    height: 401
+                },
+                Geometry {
+                    x: 510,
+                    y: 421,
+                    width: 500,
+                    height: 400
+                },
+            ]
+        );
+        assert_eq!(area(&tiles), screen.width as u32 * screen.height as u32);
+
+        let tiles = master_stack(
+            screen,
+            3,
+            MasterSettings {
+                stack_orientation: StackOrientation::Horizontal,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            tiles,
+            vec![
+                Geometry {
+                    width: 500,
+                    ..screen
+                },
+                Geometry {
+                    x: 510,
+                    y: 20,
+                    width: 250,
+                    height: 801
+                },
+                Geometry {
+                    x: 760,
+                    y: 20,
+                    width: 250,
+                    height: 801
+                },
+            ]
+        );
+        assert_eq!(area(&tiles), screen.width as u32 * screen.height as u32);
+
+        assert!(matches!(
+            StackOrientation::try_from("Horizontal"),
+            Ok(StackOrientation::Horizontal)
+        ));
+        assert!(StackOrientation::try_from("diagonal").is_err());
+    }
+
+    #[test]
+    fn master_stack_nmaster() {
+        let screen = Geometry {
+            x: 10,
+            y: 20,
+            width: 1000,
+            height: 800,
+        };
+        let two_masters = MasterSettings {
+            count: 2,
+            ..Default::default()
+        };
+
+        let tiles = master_stack(screen, 4, two_masters);
+        assert_eq!(
+            tiles,
+            vec![
+                Geometry {
+                    x: 10,
+                    y: 20,
+                    width: 500,
+                    height: 400
+                },
+                Geometry {
+                    x: 10,
+                    y: 420,
+                    width: 500,
+                    height: 400
+                },
+                Geometry {
+                    x: 510,
+                    y: 20,
+                    width: 500,
+                    height: 400
+                },
+                Geometry {
+                    x: 510,
+                    y: 420,
+                    width: 500,
+                    height: 400
+                },
+            ]
+        );
+
+        // Without windows left for the stack, the masters share the whole screen.
+        let tiles = master_stack(screen, 2, two_masters);
+        assert_eq!((tiles[0].width, tiles[0].height), (1000, 400));
+        assert_eq!((tiles[1].y, tiles[1].width), (420, 1000));
+
+        // At least one window is always the master.
+        let no_masters = MasterSettings {
+            count: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            master_stack(screen, 3, no_masters),
+            master_stack(screen, 3, MasterSettings::default())
+        );
+    }
+
+    #[test]
+    fn dwindle_halves() {
+        let screen = Geometry {
+            x: 10,
+            y: 20,
+            width: 1921,
+            height: 1080,
+        };
+
+        let tiles = dwindle(screen, 4);
+        assert_eq!(tiles.len(), 4);
+        assert_eq!(
+            (tiles[0].x, tiles[0].width, tiles[0].height),
+            (10, 961, 1080)
+        );
+        assert_eq!((tiles[1].x, tiles[1].y, tiles[1].width), (971, 20, 960));
+        assert_eq!(tiles[1].height, 540);
+        assert_eq!(
+            (tiles[2].y, tiles[2].width, tiles[2].height),
+            (560, 480, 540)
+        );
+        assert_eq!((tiles[3].x, tiles[3].y), (1451, 560));
+
+        let area: u32 = tiles.iter().map(|t| t.width as u32 * t.height as u32).sum();
+        assert_eq!(area, screen.width as u32 * screen.height as u32);
+        assert_eq!(dwindle(screen, 1), vec![screen]);
+    }
+
+    #[test]
+    fn equal_horizontal_gaps() {
+        use crate::{config::Config, wm::container::Client};
+
+        let screen = Geometry {
+            x: 0,
+            y: 20,
+            width: 1000,
+            height: 600,
+        };
+        let clients: Vec<Geometry> = (0..2)
+            .map(|ii| {
+                let (offset, width) = segment(screen.width, 2, ii);
+                let tile = Geometry {
+                    x: screen.x + offset as i16,
+                    width,
+                    ..screen
+                };
+                let mut client =
+                    Client::new_without_process_id(ii as u32, tile, ii as u64, &Config::default());
+                client.attributes.gap_top = 10;
+                client.attributes.gap_bottom = 10;
+                client.attributes.gap_left = 10;
+                client.attributes.gap_right = 10;
+
+                client.with_gaps(screen)
+            })
+            .collect();
+
+        // the windows are a whole gap apart and as wide as each other
+        let gutter = clients[1].x - (clients[0].x + clients[0].width as i16);
+        assert_eq!(gutter, 10);
+        assert_eq!(clients[0].width, clients[1].width);
+        // the sides on the edges of the screen are left for the outer gap
+        assert_eq!(clients[0].x, screen.x);
+        assert_eq!(clients[1].x + clients[1].width as i16, 1000);
+        for client in clients {
+            assert_eq!((client.y, client.height), (screen.y, screen.height));
+        }
+    }
+
     #[test]
     fn isize_u64() {
         let num: u64 = isize::max_value() as u64;
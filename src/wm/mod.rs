@@ -1,8 +1,14 @@
-use std::{rc::Rc, thread::spawn};
+use std::{
+    collections::VecDeque,
+    rc::Rc,
+    thread::{sleep, spawn},
+    time::{Duration, Instant},
+};
 
 use x11rb::{
     connection::Connection,
     protocol::{
+        randr::Notify,
         xproto::{AtomEnum, ConnectionExt, PropMode},
         Event,
     },
@@ -11,7 +17,7 @@ use x11rb::{
 use crate::{
     config::Config,
     errm,
-    errors::WmResult,
+    errors::{Error, WmResult},
     log::{err, log, LL_NORMAL},
     logm,
     wm::state::State,
@@ -21,6 +27,7 @@ pub mod actions;
 pub mod atoms;
 pub mod bar;
 pub mod container;
+pub mod focus_model;
 pub mod focus_stack;
 pub mod geometry;
 pub mod keyman;
@@ -29,6 +36,33 @@ pub mod monitors;
 pub mod state;
 pub mod workspace;
 
+/// How long an error is remembered for, when looking for repeated errors.
+const ERROR_REPEAT_WINDOW: Duration = Duration::from_secs(5);
+
+/// Errors which recently occurred while handling events, used by the `abort-on-repeated` error
+/// policy.
+#[derive(Default)]
+struct RecentErrors(VecDeque<(Instant, String)>);
+
+impl RecentErrors {
+    /// Remember an error message, returning how many times the same message has been recorded
+    /// within `ERROR_REPEAT_WINDOW`, including this time.
+    fn record(&mut self, message: String, now: Instant) -> usize {
+        while let Some((time, _)) = self.0.front() {
+            if now.duration_since(*time) > ERROR_REPEAT_WINDOW {
+                self.0.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let count = self.0.iter().filter(|(_, m)| *m == message).count() + 1;
+        self.0.push_back((now, message));
+
+        count
+    }
+}
+
 /// The WM struct, holding all the necessary state and information for and about the operation of
 /// the window manager.
 pub struct Wm {
@@ -37,11 +71,14 @@ pub struct Wm {
     /// Window manager's state. Holds information about X server connection, clients, workspaces
     /// geometries, etc...
     state: State,
+    /// Errors which recently occurred while handling events.
+    recent_errors: RecentErrors,
 }
 
 impl Wm {
-    /// Create a new window manager instance.
-    pub fn new(config: Config) -> WmResult<Self> {
+    /// Create a new window manager instance, replacing the running window manager if `replace`
+    /// is set.
+    pub fn new(config: Config, replace: bool) -> WmResult<Self> {
         let c = config.clone();
         let display_name = match c.settings.display_name.is_empty() {
             false => Some(c.settings.display_name.as_str()),
@@ -51,9 +88,13 @@ impl Wm {
         let config = Rc::new(config);
 
         // create the state manager here.
-        let state = State::new(display_name, config.clone())?;
+        let state = State::new(display_name, config.clone(), replace)?;
 
-        Ok(Self { config, state })
+        Ok(Self {
+            config,
+            state,
+            recent_errors: RecentErrors::default(),
+        })
     }
 
     /// Run the window manager, this instantiates the event loop, constructs workspaces and does
@@ -65,6 +106,10 @@ impl Wm {
         self.state.init_workspaces()?;
         // after setting up monitors and workspaces, setup up status bar
         self.state.setup_bars()?;
+        // fit the workspaces between the bars, docks and the outer gap
+        self.state.fit_all_workspaces()?;
+        // point out bars and workspaces which ended up somewhere else than configured
+        self.state.warn_about_monitor_settings();
         // check for all open windows and manage them
         // self.state.become_wm()?;
         // notify the window manager of the keybinds
@@ -72,7 +117,7 @@ impl Wm {
         // run the hooks after creating wm
 
         // run the bar update thread
-        let bar_windows = self.state.bar_windows();
+        let bar_windows = self.state.shared_bar_windows();
         let conn = self.state.connection();
 
         let bar_atom = self
@@ -86,43 +131,67 @@ impl Wm {
 
         logm!(LL_NORMAL, "Hello world {}", xd);
 
+        let interval = Duration::from_millis(self.config.settings.bar_update_interval.into());
         logm!(
             LL_NORMAL,
-            "Setting up bar update thread. Status bars will automatically be updated every second.",
+            "Setting up bar update thread. Status bars will automatically be updated every {} ms.",
+            interval.as_millis()
         );
         let _ = spawn(move || {
-            let mut last_time = std::time::Instant::now();
             let mut switch = 0;
             loop {
-                if last_time.elapsed().as_secs() >= 1 {
-                    last_time = std::time::Instant::now();
-                    for win in bar_windows.iter() {
-                        if conn
-                            .change_property(
-                                PropMode::REPLACE,
-                                *win,
-                                bar_atom,
-                                AtomEnum::INTEGER,
-                                8,
-                                1,
-                                &[switch],
-                            )
-                            .is_ok()
-                        {}
-                    }
-                    switch = if switch.eq(&1) { 0 } else { 1 };
-                    conn.flush().unwrap();
+                // sleep instead of polling the clock, so the thread only wakes up once per update
+                sleep(interval);
+                // the bars are created again after monitor changes and config reloads, holding the
+                // lock keeps them from being destroyed while the updates are sent
+                let bar_windows = bar_windows.lock().unwrap();
+                for win in bar_windows.iter() {
+                    let _ = conn.change_property(
+                        PropMode::REPLACE,
+                        *win,
+                        bar_atom,
+                        AtomEnum::INTEGER,
+                        8,
+                        1,
+                        &[switch],
+                    );
+                }
+                switch = if switch.eq(&1) { 0 } else { 1 };
+                // the main loop takes care of handling a lost connection, the thread only stops
+                let flushed = conn.flush();
+                drop(bar_windows);
+                if let Err(e) = flushed {
+                    errm!("stopping the bar update thread: {}", e);
+                    break;
                 }
             }
         });
 
         self.state.update_bars()?;
 
+        // run the event loop, don't stop on errors, just report them and keep going. The only
+        // exception is losing the connection to the X server, after which there is nothing left to
+        // do.
+        logm!(LL_NORMAL, "Starting the event loop.");
+        let result = self.event_loop();
+        if let Err(e) = &result {
+            if e.is_connection_error() {
+                errm!(
+                    "lost the connection to the X server, exiting: {}",
+                    e.message()
+                );
+            }
+        }
+
+        result
+    }
+
+    /// Wait for events and handle them, until an unrecoverable error occurs or another window
+    /// manager replaces this one.
+    fn event_loop(&mut self) -> WmResult {
         let mut first = false;
         let mut ran = false;
 
-        // run the event loop, don't stop on errors, just report them and keep going.
-        logm!(LL_NORMAL, "Starting the event loop.");
         loop {
             if !first {
                 first = true;
@@ -130,21 +199,91 @@ impl Wm {
                 self.config.start_hooks.run_after()?;
                 ran = true;
             }
+            let housekeeping = [
+                self.state.advance_slide(),
+                self.state.repin_windows(),
+                self.state.escalate_pending_kills(),
+                self.state.end_visual_bell(),
+                self.state.update_active_window(),
+            ];
+            for result in housekeeping {
+                self.check(result, None)?;
+            }
             self.state.connection().flush()?;
-            self.state.update_bars()?;
+            let result = self.state.update_bars_if_needed();
+            self.check(result, None)?;
             let event = self.state.connection().wait_for_event()?;
 
             let mut ev_option = Some(event);
 
             while let Some(ev) = ev_option {
-                if let Err(e) = self.handle_event(ev) {
-                    errm!("{}", e);
+                if let Event::SelectionClear(e) = &ev {
+                    if self.state.is_replaced(e) {
+                        logm!(LL_NORMAL, "Replaced by another window manager, exiting.");
+                        return Ok(());
+                    }
+                }
+                // Property changes on the bar windows come from the bar update thread, they don't
+                // change anything the bars show.
+                let bar_update = matches!(
+                    &ev,
+                    Event::PropertyNotify(e) if self.state.bar_windows().contains(&e.window)
+                );
+                if !bar_update {
+                    self.state.invalidate_bars();
                 }
+                let event_description = match self.state.settings().error_policy.as_str() {
+                    "log-verbose" => Some(format!("{ev:?}")),
+                    _ => None,
+                };
+                let result = self.handle_event(ev);
+                self.check(result, event_description)?;
                 ev_option = self.state.connection().poll_for_event()?;
             }
+
+            let result = self.state.flush_pending_layouts();
+            self.check(result, None)?;
+        }
+    }
+
+    /// Report the error of a step of the event loop. Returns the error only if the window manager
+    /// should stop, which is always the case for a lost connection to the X server.
+    fn check(&mut self, result: WmResult, event_description: Option<String>) -> WmResult {
+        match result {
+            Err(e) if e.is_connection_error() => Err(e),
+            Err(e) => self.report_error(e, event_description),
+            Ok(()) => Ok(()),
         }
     }
 
+    /// Report an error which occurred while handling an event, according to the `error_policy`
+    /// setting. Returns the error if the window manager should stop.
+    fn report_error(&mut self, error: Error, event_description: Option<String>) -> WmResult {
+        match self.state.settings().error_policy.as_str() {
+            "log-verbose" => {
+                errm!(
+                    "{} (while handling {})",
+                    error,
+                    event_description.unwrap_or_default()
+                );
+            }
+            "abort-on-repeated" => {
+                errm!("{}", error);
+                let limit = self.state.settings().error_repeat_limit as usize;
+                let count = self.recent_errors.record(error.message(), Instant::now());
+                if limit > 0 && count >= limit {
+                    errm!("the same error occurred {count} times in a short time, exiting.");
+                    return Err(error);
+                }
+            }
+            _ => {
+                errm!("{}", error);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Event handler. Decide what to do with incoming X11 Events.
     fn handle_event(&mut self, event: Event) -> WmResult {
         match event {
@@ -183,7 +322,7 @@ impl Wm {
             }
             Event::EnterNotify(e) => {
                 logm!(LL_NORMAL, "Handling enter notify for window {}", e.event,);
-                self.state.handle_enter_event(e.event)?;
+                self.state.handle_enter_event(&e)?;
             }
             Event::LeaveNotify(_) => {}
             Event::MotionNotify(e) => {
@@ -221,21 +360,32 @@ impl Wm {
                     "Received a client message from window {}",
                     e.window,
                 );
+                self.state.handle_client_message(&e)?;
             }
             Event::Expose(e) => {
                 logm!(LL_NORMAL, "Exposure event on window {}", e.window,);
             }
-            Event::UnmapNotify(_e) => {
-                logm!(LL_NORMAL, "Window {} has been unmapped", _e.window,);
+            Event::UnmapNotify(e) => {
+                logm!(LL_NORMAL, "Window {} has been unmapped", e.window,);
+                // only the tray icons' own unmap notifications, the ones sent to the root window
+                // also include icons unmapped by being docked
+                if e.event == e.window {
+                    self.state.undock_tray_icon(e.window, false)?;
+                }
+                self.state.remove_strut(e.window)?;
             }
             Event::DestroyNotify(e) => {
                 logm!( LL_NORMAL, "Window {} has been destroyed, this window will no longer be managed by the window manager.", e.window);
+                self.state.undock_tray_icon(e.window, true)?;
+                self.state.remove_strut(e.window)?;
                 self.state.unmanage_window(e.window)?;
             }
             Event::PropertyNotify(e) => {
                 let bar_widnows = self.state.bar_windows();
                 if bar_widnows.contains(&e.window) {
-                    self.state.update_bars()?;
+                    self.state.update_bars_if_needed()?;
+                } else if self.state.is_layout_property(e.window, e.atom) {
+                    self.state.update_layout_from_property()?;
                 } else {
                     logm!(
                         LL_NORMAL,
@@ -245,9 +395,41 @@ impl Wm {
                     );
                 }
             }
+            Event::XkbBellNotify(e) => {
+                logm!(LL_NORMAL, "A bell rang in window {}", e.window);
+                self.state.handle_bell()?;
+            }
+            Event::RandrScreenChangeNotify(_) => {
+                logm!(LL_NORMAL, "Handling a screen change notification");
+                self.state.handle_screen_change()?;
+            }
+            Event::RandrNotify(e) if e.sub_code == Notify::OUTPUT_CHANGE => {
+                logm!(LL_NORMAL, "Handling an output change notification");
+                self.state.handle_screen_change()?;
+            }
             _ev => {}
         };
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_errors() {
+        let mut errors = RecentErrors::default();
+        let start = Instant::now();
+
+        assert_eq!(errors.record("a".into(), start), 1);
+        assert_eq!(errors.record("b".into(), start), 1);
+        assert_eq!(errors.record("a".into(), start + Duration::from_secs(1)), 2);
+
+        // the first two errors are forgotten by now
+        let later = start + ERROR_REPEAT_WINDOW + Duration::from_millis(500);
+        assert_eq!(errors.record("a".into(), later), 2);
+        assert_eq!(errors.record("b".into(), later), 1);
+    }
+}
@@ -2,6 +2,7 @@
 use x11rb::protocol::randr::{MonitorInfo, Output};
 
 use crate::{
+    config::{Config, MonitorPadding},
     errors::WmResult,
     log::{log, LL_FULL},
     logm,
@@ -14,10 +15,16 @@ pub type MonitorId = u32;
 pub struct Monitor {
     size: Geometry,
     id: MonitorId,
+    /// Name of the monitor, as reported by RandR, for example "HDMI-1".
+    name: String,
     outputs: Vec<Output>,
     workspaces: Vec<WorkspaceId>,
     open_workspace: Option<WorkspaceId>,
+    /// The workspace which was focused on this monitor most recently.
+    last_focused_workspace: Option<WorkspaceId>,
     focused: bool,
+    /// Space left empty on the edges of the monitor, from its `monitor_set` settings.
+    padding: MonitorPadding,
 }
 
 impl Monitor {
@@ -25,10 +32,13 @@ impl Monitor {
         Self {
             size,
             id,
+            name: String::new(),
             outputs,
             workspaces: Vec::new(),
             open_workspace: None,
+            last_focused_workspace: None,
             focused: false,
+            padding: MonitorPadding::default(),
         }
     }
 
@@ -87,6 +97,27 @@ impl Monitor {
         }
     }
 
+    /// Remember a workspace of this monitor as the one which was focused on it most recently.
+    pub fn set_last_focused_workspace(&mut self, id: WorkspaceId) -> WmResult {
+        if !self.contains(&id) {
+            return Err("This workspace is not located in on this monitor.".into());
+        }
+        self.last_focused_workspace = Some(id);
+
+        Ok(())
+    }
+
+    /// Return the workspace which should be focused when the focus returns to this monitor.
+    ///
+    /// That's the workspace which was focused on this monitor most recently, or the open workspace
+    /// if no workspace has been focused on it yet.
+    pub fn workspace_to_focus(&self) -> WmResult<WorkspaceId> {
+        match self.last_focused_workspace {
+            Some(id) => Ok(id),
+            None => self.get_open_workspace(),
+        }
+    }
+
     pub fn is_focused(&self) -> bool {
         self.focused
     }
@@ -95,15 +126,477 @@ impl Monitor {
         self.focused = focus;
     }
 
+    /// Get the physical size of the monitor, which the bars are placed on.
     pub fn size(&self) -> Geometry {
         self.size
     }
 
+    /// Get the part of the monitor the workspaces can use, which is its size without its padding.
+    pub fn usable_size(&self) -> Geometry {
+        let p = self.padding;
+        self.size.padded(p.top, p.bottom, p.left, p.right)
+    }
+
+    pub fn set_padding(&mut self, padding: MonitorPadding) {
+        self.padding = padding;
+    }
+
     pub fn id(&self) -> u32 {
         self.id
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    /// Is this monitor one of the monitors listed in the `manage_monitors` setting?
+    ///
+    /// Monitors are listed either by their name or by their index, counting from 0. An empty list
+    /// means that all the monitors are managed.
+    pub fn is_listed(&self, index: usize, listed: &[String]) -> bool {
+        listed.is_empty()
+            || listed
+                .iter()
+                .any(|m| *m == self.name || m.parse::<usize>() == Ok(index))
+    }
+
     pub fn contains(&self, workspace: &u32) -> bool {
         self.workspaces.contains(workspace)
     }
+
+    /// Return the id of the nth workspace of this monitor, counting from 1.
+    pub fn nth_workspace(&self, n: usize) -> Option<WorkspaceId> {
+        n.checked_sub(1)
+            .and_then(|index| self.workspaces.get(index))
+            .copied()
+    }
+
+    /// Return the workspace of this monitor which comes after, or before, the given one, wrapping
+    /// around at the ends and passing over the workspaces for which `skip` returns true. Returns
+    /// `None` if the workspace isn't on this monitor, or if all the other workspaces are skipped.
+    pub fn adjacent_workspace(
+        &self,
+        id: WorkspaceId,
+        forward: bool,
+        skip: impl Fn(WorkspaceId) -> bool,
+    ) -> Option<WorkspaceId> {
+        let index = self.workspaces.iter().position(|w| *w == id)?;
+        let len = self.workspaces.len();
+
+        (1..len)
+            .map(|offset| {
+                if forward {
+                    (index + offset) % len
+                } else {
+                    (index + len - offset) % len
+                }
+            })
+            .filter_map(|i| self.workspaces.get(i).copied())
+            .find(|w| !skip(*w))
+    }
+}
+
+/// Return the index, in `monitors`, of the monitor which a workspace's `monitor` setting refers
+/// to, or `None` if the setting is empty.
+///
+/// Workspaces set to a monitor which doesn't exist, or isn't managed, go to the first monitor.
+pub fn configured_monitor(setting: &str, monitors: &[Monitor]) -> Option<usize> {
+    if setting.is_empty() {
+        return None;
+    }
+    let monitor_number = setting.parse::<u32>().unwrap_or(0);
+
+    Some(
+        monitors
+            .iter()
+            .position(|monitor| monitor.id() == monitor_number + 1)
+            .unwrap_or(0),
+    )
+}
+
+/// Return the index, in `monitors`, of the monitor a workspace goes to after the monitors have
+/// changed, and whether it only goes there because its configured monitor is gone.
+///
+/// A workspace stays on the monitor it was on, found by name in `previous`, while that monitor is
+/// still there, so that workspaces moved by hand or by rebalancing keep their place. Other
+/// workspaces go to their configured monitor, or to the first monitor if it's gone.
+pub fn monitor_after_change(
+    previous: Option<&str>,
+    setting: &str,
+    monitors: &[Monitor],
+) -> (usize, bool) {
+    if let Some(index) = previous.and_then(|name| monitors.iter().position(|m| m.name() == name)) {
+        return (index, false);
+    }
+    if setting.is_empty() {
+        return (0, false);
+    }
+
+    let monitor_number = setting.parse::<u32>().unwrap_or(0);
+    match monitors.iter().position(|m| m.id() == monitor_number + 1) {
+        Some(index) => (index, false),
+        None => (0, true),
+    }
+}
+
+/// Assign the workspaces to the monitors again, according to their `monitor` setting. Each
+/// workspace without the setting goes to the monitor with the fewest workspaces at that point.
+///
+/// Monitors whose open workspace went to another monitor are left without an open workspace.
+/// Returns the index of the monitor each of the workspaces went to.
+pub fn rebalance_workspaces(
+    config: &Config,
+    workspaces: &[WorkspaceId],
+    monitors: &mut [Monitor],
+) -> Vec<usize> {
+    if monitors.is_empty() {
+        return Vec::new();
+    }
+
+    let configured: Vec<Option<usize>> = workspaces
+        .iter()
+        .map(|id| {
+            config
+                .workspace_settings
+                .get(*id)
+                .and_then(|settings| configured_monitor(&settings.monitor, monitors))
+        })
+        .collect();
+    let mut counts = vec![0; monitors.len()];
+    configured
+        .iter()
+        .flatten()
+        .for_each(|index| counts[*index] += 1);
+    let indices: Vec<usize> = configured
+        .into_iter()
+        .map(|index| {
+            index.unwrap_or_else(|| {
+                let least = (0..counts.len()).min_by_key(|i| counts[*i]).unwrap_or(0);
+                counts[least] += 1;
+                least
+            })
+        })
+        .collect();
+
+    for monitor in monitors.iter_mut() {
+        monitor.workspaces.clear();
+    }
+    for (id, index) in workspaces.iter().zip(indices.iter()) {
+        monitors[*index].add_workspace(*id);
+    }
+    for monitor in monitors.iter_mut() {
+        if !monitor
+            .open_workspace
+            .is_some_and(|id| monitor.contains(&id))
+        {
+            monitor.open_workspace = None;
+        }
+        if !monitor
+            .last_focused_workspace
+            .is_some_and(|id| monitor.contains(&id))
+        {
+            monitor.last_focused_workspace = None;
+        }
+    }
+
+    indices
+}
+
+/// Describe every workspace and bar from the config which is set to a monitor that doesn't
+/// exist, and every monitor setting given for a monitor name which doesn't exist.
+///
+/// Monitors are counted from 0 in the config file, while monitor ids count from 1, so the monitor
+/// with the id 1 is monitor 0 in the config file.
+pub fn check_monitor_settings(config: &Config, monitors: &[Monitor]) -> Vec<String> {
+    let exists = |index: u32| monitors.iter().any(|m| m.id() == index + 1);
+    let valid = match monitors.len() {
+        0 => "there are no monitors".to_string(),
+        1 => "the only monitor is 0".to_string(),
+        n => format!("valid monitors are 0 to {}", n - 1),
+    };
+    let mut warnings = Vec::new();
+
+    for workspace in config.workspace_settings.clone() {
+        let monitor = &workspace.monitor;
+        match monitor.parse::<u32>() {
+            _ if monitor.is_empty() => {}
+            Ok(index) if exists(index) => {}
+            Ok(_) => warnings.push(format!(
+                "workspace {} is set to monitor {monitor}, which doesn't exist, it is put onto the first monitor instead; {valid}.",
+                workspace.identifier
+            )),
+            Err(_) => warnings.push(format!(
+                "workspace {} is set to monitor \"{monitor}\", which isn't a monitor number, it is put onto the first monitor instead; {valid}.",
+                workspace.identifier
+            )),
+        }
+    }
+
+    for bar in config.bar_settings.clone() {
+        if !exists(bar.monitor) {
+            warnings.push(format!(
+                "bar {} is set to monitor {}, which doesn't exist, the bar isn't shown; {valid}.",
+                bar.identifier, bar.monitor
+            ));
+        }
+    }
+
+    for settings in config.monitor_settings.clone() {
+        if !monitors.iter().any(|m| m.name() == settings.name) {
+            warnings.push(format!(
+                "there are settings for the monitor \"{}\", which doesn't exist, they are ignored.",
+                settings.name
+            ));
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nth_workspace() {
+        let mut first = Monitor::new(Geometry::default(), 1, Vec::new());
+        let mut second = Monitor::new(Geometry::default(), 2, Vec::new());
+        (1..=3).for_each(|id| first.add_workspace(id));
+        (4..=6).for_each(|id| second.add_workspace(id));
+
+        assert_eq!(first.nth_workspace(1), Some(1));
+        assert_eq!(first.nth_workspace(3), Some(3));
+        assert_eq!(second.nth_workspace(1), Some(4));
+        assert_eq!(second.nth_workspace(3), Some(6));
+        assert_eq!(second.nth_workspace(0), None);
+        assert_eq!(second.nth_workspace(4), None);
+    }
+
+    #[test]
+    fn monitor_after_change() {
+        let mut first = Monitor::new(Geometry::default(), 1, Vec::new());
+        let mut second = Monitor::new(Geometry::default(), 2, Vec::new());
+        first.set_name("eDP-1".into());
+        second.set_name("HDMI-1".into());
+        let monitors = [first, second];
+
+        // workspaces stay on monitors which are still there, whatever their setting says
+        assert_eq!(
+            super::monitor_after_change(Some("HDMI-1"), "0", &monitors),
+            (1, false)
+        );
+        assert_eq!(
+            super::monitor_after_change(Some("eDP-1"), "1", &monitors),
+            (0, false)
+        );
+        // those of a monitor which went away, or which were displaced before, go to the
+        // configured one
+        assert_eq!(
+            super::monitor_after_change(Some("DP-2"), "1", &monitors),
+            (1, false)
+        );
+        assert_eq!(
+            super::monitor_after_change(None, "1", &monitors),
+            (1, false)
+        );
+        assert_eq!(
+            super::monitor_after_change(Some("DP-2"), "", &monitors),
+            (0, false)
+        );
+        // and to the first monitor while the configured one is gone
+        assert_eq!(
+            super::monitor_after_change(Some("DP-2"), "2", &monitors),
+            (0, true)
+        );
+    }
+
+    #[test]
+    fn workspace_to_focus() {
+        let mut first = Monitor::new(Geometry::default(), 1, Vec::new());
+        let mut second = Monitor::new(Geometry::default(), 2, Vec::new());
+        (1..=3).for_each(|id| first.add_workspace(id));
+        (4..=6).for_each(|id| second.add_workspace(id));
+        first.set_open_workspace(None).unwrap();
+        second.set_open_workspace(None).unwrap();
+        assert_eq!(second.workspace_to_focus().unwrap(), 4);
+
+        // focus workspace 1 on the first monitor, then workspace 5 on the second one
+        first.set_last_focused_workspace(1).unwrap();
+        second.set_open_workspace(Some(5)).unwrap();
+        second.set_last_focused_workspace(5).unwrap();
+
+        // the first monitor's open workspace changes without the workspace being focused
+        first.set_open_workspace(Some(2)).unwrap();
+
+        assert_eq!(first.workspace_to_focus().unwrap(), 1);
+        assert_eq!(second.workspace_to_focus().unwrap(), 5);
+        assert!(first.set_last_focused_workspace(5).is_err());
+    }
+
+    #[test]
+    fn is_listed() {
+        let mut monitor = Monitor::new(Geometry::default(), 2, Vec::new());
+        monitor.set_name("HDMI-1".into());
+
+        assert!(monitor.is_listed(1, &[]));
+        assert!(monitor.is_listed(1, &["HDMI-1".into()]));
+        assert!(monitor.is_listed(1, &["eDP-1".into(), "1".into()]));
+        assert!(!monitor.is_listed(1, &["eDP-1".into(), "0".into()]));
+    }
+
+    #[test]
+    fn adjacent_workspace() {
+        let mut monitor = Monitor::new(Geometry::default(), 1, Vec::new());
+        (4..=6).for_each(|id| monitor.add_workspace(id));
+
+        let no_skip = |_| false;
+
+        assert_eq!(monitor.adjacent_workspace(4, true, no_skip), Some(5));
+        assert_eq!(monitor.adjacent_workspace(6, true, no_skip), Some(4));
+        assert_eq!(monitor.adjacent_workspace(4, false, no_skip), Some(6));
+        assert_eq!(monitor.adjacent_workspace(5, false, no_skip), Some(4));
+        assert_eq!(monitor.adjacent_workspace(1, true, no_skip), None);
+    }
+
+    #[test]
+    fn adjacent_workspace_skip_cycle() {
+        let config =
+            crate::parsers::ConfigParser::parse_str("workspace_set 5 skip_cycle true").unwrap();
+        let skip = |id| {
+            config
+                .workspace_settings
+                .get(id)
+                .map(|settings| settings.skip_cycle)
+                .unwrap_or(false)
+        };
+        let mut monitor = Monitor::new(Geometry::default(), 1, Vec::new());
+        (4..=6).for_each(|id| monitor.add_workspace(id));
+
+        assert_eq!(monitor.adjacent_workspace(4, true, skip), Some(6));
+        assert_eq!(monitor.adjacent_workspace(6, false, skip), Some(4));
+        // a skipped workspace can still be left
+        assert_eq!(monitor.adjacent_workspace(5, true, skip), Some(6));
+
+        let mut monitor = Monitor::new(Geometry::default(), 1, Vec::new());
+        (4..=5).for_each(|id| monitor.add_workspace(id));
+        assert_eq!(monitor.adjacent_workspace(4, true, skip), None);
+    }
+
+    #[test]
+    fn monitor_settings() {
+        let config = crate::parsers::ConfigParser::parse_str(
+            "workspace_set 1 monitor 1\n\
+             workspace_set 2 monitor 2\n\
+             workspace_set 3 monitor second\n\
+             bar_set 0 monitor 0\n\
+             bar_set 1 monitor 2",
+        )
+        .unwrap();
+        let monitors: Vec<Monitor> = (1..=2)
+            .map(|id| Monitor::new(Geometry::default(), id, Vec::new()))
+            .collect();
+
+        let warnings = check_monitor_settings(&config, &monitors);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].starts_with("workspace 2 is set to monitor 2"));
+        assert!(warnings[1].starts_with("workspace 3 is set to monitor \"second\""));
+        assert!(warnings[2].starts_with("bar 1 is set to monitor 2"));
+        assert!(warnings
+            .iter()
+            .all(|w| w.ends_with("valid monitors are 0 to 1.")));
+
+        assert!(check_monitor_settings(&config, &monitors[..1])
+            .iter()
+            .any(|w| w.starts_with("workspace 1") && w.ends_with("the only monitor is 0.")));
+
+        let config =
+            crate::parsers::ConfigParser::parse_str("monitor_set HDMI-1 padding 0 0 10 10")
+                .unwrap();
+        let mut tv = Monitor::new(Geometry::default(), 1, Vec::new());
+        assert_eq!(check_monitor_settings(&config, &[tv.clone()]).len(), 1);
+        tv.set_name("HDMI-1".to_string());
+        assert!(check_monitor_settings(&config, &[tv]).is_empty());
+    }
+
+    #[test]
+    fn rebalance_workspaces() {
+        let config = crate::parsers::ConfigParser::parse_str(
+            "workspace_set 1 monitor 0\n\
+             workspace_set 2 monitor 1\n\
+             workspace_set 3 monitor 1",
+        )
+        .unwrap();
+        let ids: Vec<WorkspaceId> = (1..=6).collect();
+        let mut monitors: Vec<Monitor> = (1..=2)
+            .map(|id| Monitor::new(Geometry::default(), id, Vec::new()))
+            .collect();
+
+        // every workspace piled up on the first monitor
+        ids.iter().for_each(|id| monitors[0].add_workspace(*id));
+        monitors[0].set_open_workspace(Some(3)).unwrap();
+        monitors[0].set_last_focused_workspace(3).unwrap();
+
+        let indices = super::rebalance_workspaces(&config, &ids, &mut monitors);
+        assert_eq!(indices, vec![0, 1, 1, 0, 0, 1]);
+        assert_eq!(monitors[0].workspaces, vec![1, 4, 5]);
+        assert_eq!(monitors[1].workspaces, vec![2, 3, 6]);
+        // workspace 3 isn't on the first monitor anymore
+        assert!(monitors[0].get_open_workspace().is_err());
+        assert!(monitors[0].workspace_to_focus().is_err());
+
+        // a workspace set to a monitor which doesn't exist goes to the first monitor
+        let mut single = vec![Monitor::new(Geometry::default(), 1, Vec::new())];
+        assert_eq!(
+            super::rebalance_workspaces(&config, &ids, &mut single),
+            vec![0; 6]
+        );
+    }
+
+    #[test]
+    fn padded_monitor() {
+        let size = Geometry {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let mut monitor = Monitor::new(size, 1, Vec::new());
+        monitor.set_padding(MonitorPadding {
+            top: 20,
+            bottom: 20,
+            left: 40,
+            right: 40,
+        });
+
+        // the bars are placed onto the physical size of the monitor, like in `setup_bars`
+        let bar = Geometry {
+            height: 30,
+            ..monitor.size()
+        };
+        assert_eq!(bar.width, 1920);
+        assert_eq!(
+            monitor.usable_size().minus_bar(bar),
+            Geometry {
+                x: 40,
+                y: 30,
+                width: 1840,
+                height: 1030
+            }
+        );
+
+        // a bar lying in the padding doesn't take any more space
+        let bar = Geometry {
+            y: 1070,
+            height: 10,
+            ..monitor.size()
+        };
+        assert_eq!(monitor.usable_size().minus_bar(bar), monitor.usable_size());
+
+        monitor.set_padding(MonitorPadding::default());
+        assert_eq!(monitor.usable_size(), size);
+    }
 }
@@ -2,48 +2,70 @@ use cairo::{XCBConnection as CairoConnection, XCBDrawable, XCBSurface, XCBVisual
 use x11::xlib::{Display, XOpenDisplay};
 use x11rb::{
     connection::Connection,
+    properties::{WmClass, WmSizeHints},
     protocol::{
-        randr::get_monitors,
+        randr::{get_monitors, ConnectionExt as _, NotifyMask},
+        xkb::{self, ConnectionExt as _},
         xproto::{
-            ButtonIndex, ChangeWindowAttributesAux, ConfigureWindowAux, ConnectionExt,
-            CreateWindowAux, EventMask, FocusInEvent, GrabMode, InputFocus, KeyPressEvent,
-            KeyReleaseEvent, Screen, StackMode, WindowClass,
+            Allow, AtomEnum, ButtonIndex, ChangeWindowAttributesAux, ClientMessageEvent,
+            ConfigureWindowAux, ConnectionExt, CreateWindowAux, EventMask, FocusInEvent, GrabMode,
+            InputFocus, KeyPressEvent, KeyReleaseEvent, NotifyDetail, NotifyMode, PropMode, Screen,
+            SelectionClearEvent, SetMode, StackMode, WindowClass,
         },
+        Event,
     },
+    wrapper::ConnectionExt as _,
     xcb_ffi::XCBConnection,
     CURRENT_TIME, NONE,
 };
 
 use crate::{
-    config::{Config, Keybinds},
+    config::{
+        rules::RuleEffects,
+        settings::{convert_color, Settings},
+        BarPosition, Config, Keybinds,
+    },
     errm,
     errors::{Error, WmResult},
     ffi::find_xcb_visualtype,
     log::{err, log, LL_FULL, LL_NORMAL},
     logm,
     parsers::ConfigParser,
-    wm::actions::{Action, Direction},
+    wm::actions::{Action, Corner, Direction},
     wm::atoms::AtomManager,
-    wm::bar::Bar,
-    wm::geometry::Geometry,
+    wm::bar::{
+        output::{BarOutput, WorkspaceStatus},
+        widgets::Widget,
+        Bar,
+    },
+    wm::focus_model::{self, PointerFocus},
+    wm::geometry::{Geometry, SizeHints, Strut},
     wm::keyman::KeyManager,
     wm::layouts::LayoutMask,
-    wm::monitors::Monitor,
+    wm::monitors::{
+        check_monitor_settings, monitor_after_change, rebalance_workspaces, Monitor, MonitorId,
+    },
     wm::workspace::Workspaces,
     wm::workspace::{Workspace, WorkspaceId},
     wm::{
         atoms,
-        container::{Client, ClientId},
+        container::{Client, ClientId, Container, ContainerId},
     },
 };
 
+use std::collections::HashMap;
 use std::rc::Rc;
-use std::{ffi::CStr, sync::Arc};
+use std::time::{Duration, Instant};
+use std::{
+    ffi::CStr,
+    sync::{Arc, Mutex},
+};
 
 use super::{
-    atoms::PropertyReturnValue,
+    atoms::{PropertyReturnValue, WmClassWrapper},
     container::{ContainerType, ContainerTypeMask},
-    layouts::LayoutType,
+    focus_stack::FocusStack,
+    layouts::{LayoutType, StackOrientation},
 };
 
 pub struct State {
@@ -55,15 +77,198 @@ pub struct State {
     key_manager: KeyManager,
     last_client_id: ClientId,
     atoms: Rc<AtomManager>,
+    /// The `WM_S<screen>` manager selection atom.
+    wm_selection: u32,
+    /// The window owning the manager selection, for as long as this window manager is running.
+    wm_selection_window: u32,
     is_dragging: bool,
     is_resizing: bool,
+    /// Is a tiled window being dragged onto another tile?
+    is_reordering: bool,
+    /// The window outlining the tile a dragged tiled window would be dropped onto.
+    drop_indicator: Option<u32>,
+    /// Are the keys of the `abort` keybinds grabbed?
+    abort_keys_grabbed: bool,
     config: Rc<Config>,
     monitors: Vec<Monitor>,
+    /// Monitors left alone because they aren't listed in the `manage_monitors` setting.
+    unmanaged_monitors: Vec<Monitor>,
+    /// Workspaces moved off their configured monitor because it went away, which go back to it
+    /// once it's there again.
+    displaced_workspaces: Vec<WorkspaceId>,
     floating_modifier: u16,
     default_colormap: u32,
     xcb_connection: Rc<CairoConnection>,
-    bar_windows: Vec<u32>,
+    /// Windows of the bars, shared with the bar update thread so that it follows the bars when
+    /// they are created again.
+    bar_windows: Arc<Mutex<Vec<u32>>>,
     bars: Vec<Bar>,
+    /// The bar window which owns the system tray selection, if there is one.
+    tray_window: Option<u32>,
+    /// Windows which requested to be kept above all the other windows.
+    above_windows: Vec<u32>,
+    /// Windows which requested to be kept below all the other windows.
+    below_windows: Vec<u32>,
+    /// Windows which are shown on all workspaces of their monitor.
+    sticky_windows: Vec<u32>,
+    /// Clients of all the workspaces, the most recently focused first.
+    focus_history: FocusStack,
+    /// Dock windows reserving space on the edges of the screen, along with the space they
+    /// reserve.
+    struts: Vec<(u32, Strut)>,
+    /// Windows which swallowed another window, along with the container of the swallowed window.
+    swallowed_windows: Vec<(u32, Container)>,
+    /// Whether the bars have been redrawn since the last change to the window manager's state.
+    bars_up_to_date: bool,
+    /// Last known position of the pointer, used to tell apart real pointer movement from windows
+    /// being moved under a pointer which stayed in place.
+    pointer_position: Option<(i16, i16)>,
+    /// Windows which are pinned to a corner of their workspace's screen.
+    pinned_windows: Vec<(u32, Corner)>,
+    /// State output for external bars, if enabled.
+    bar_output: Option<BarOutput>,
+    /// The workspace on which a window of the given WM_CLASS was last seen.
+    last_workspaces: HashMap<String, WorkspaceId>,
+    /// Windows moved onto the `fullscreen_workspace`, along with the workspaces they came from.
+    fullscreen_origins: HashMap<u32, WorkspaceId>,
+    /// Windows covering their whole monitor, along with what's needed to restore them.
+    fullscreen_windows: HashMap<u32, SavedGeometry>,
+    /// Whether the configured gaps are used, see the `toggle_gaps` action.
+    gaps_enabled: bool,
+    /// Index of the active layout in the `keyboard_layouts` setting.
+    keyboard_layout: usize,
+    /// Windows being closed by the `kill_unresponsive` action.
+    pending_kills: Vec<PendingKill>,
+    /// Commands started by the `execute_on` action, whose windows haven't been opened yet.
+    pending_spawns: PendingSpawns,
+    /// Workspaces whose layout should be applied once all the pending events are handled.
+    pending_layouts: Vec<WorkspaceId>,
+    /// Windows covering a monitor below everything else, showing the `background` color of an
+    /// empty workspace.
+    background_windows: HashMap<MonitorId, u32>,
+    /// When the windows of a workspace last finished sliding off the screen.
+    last_animation: Option<Instant>,
+    /// The workspace whose windows are sliding off the screen.
+    slide: Option<Slide>,
+    /// The window whose border is flashing because of the visual bell, along with the time at
+    /// which its border color is restored.
+    visual_bell: Option<(u32, Instant)>,
+    /// The window last published in the `_NET_ACTIVE_WINDOW` property, `NONE` if no client was
+    /// focused, or `None` if the property hasn't been published yet.
+    active_window: Option<u32>,
+}
+
+/// The geometry of a window before it was made fullscreen and whether it was tiled.
+#[derive(Debug, Clone, Copy)]
+struct SavedGeometry {
+    geometry: Geometry,
+    tiled: bool,
+}
+
+/// What happens to the first window of a process started by an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpawnTarget {
+    /// The window is put onto the workspace, without switching to it, by `execute_on`.
+    Workspace(WorkspaceId),
+    /// The window floats at the geometry, on the workspace whose monitor the geometry lies on, by
+    /// `spawn_float_terminal`.
+    Floating(WorkspaceId, Geometry),
+}
+
+/// Processes started by the `execute_on` and `spawn_float_terminal` actions, along with what
+/// happens to their first windows and the times they were started at.
+#[derive(Debug, Default)]
+struct PendingSpawns(Vec<(u32, SpawnTarget, Instant)>);
+
+impl PendingSpawns {
+    /// Remember what should happen to the first window of a process.
+    fn add(&mut self, process_id: u32, target: SpawnTarget, now: Instant) {
+        self.0.push((process_id, target, now));
+    }
+
+    /// Return the target of the first process of `process_ids` which has a pending spawn,
+    /// forgetting the spawn. Spawns older than `SPAWN_TIMEOUT` are dropped.
+    fn take(
+        &mut self,
+        process_ids: impl IntoIterator<Item = u32>,
+        now: Instant,
+    ) -> Option<SpawnTarget> {
+        self.0
+            .retain(|(_, _, started)| now.duration_since(*started) < SPAWN_TIMEOUT);
+
+        for pid in process_ids {
+            if let Some(index) = self.0.iter().position(|(p, _, _)| *p == pid) {
+                return Some(self.0.remove(index).1);
+            }
+        }
+
+        None
+    }
+}
+
+/// The windows of a workspace sliding off the screen, before the workspace is hidden.
+#[derive(Debug)]
+struct Slide {
+    workspace_id: WorkspaceId,
+    /// The windows and where they were before sliding.
+    windows: Vec<(u32, Geometry)>,
+    /// How far the windows move horizontally in total.
+    offset: i32,
+    start: Instant,
+    duration: Duration,
+}
+
+impl Slide {
+    /// Return the frame of the animation which is due at `now`, `ANIMATION_FRAMES` once it's over.
+    fn frame(&self, now: Instant) -> u32 {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= self.duration {
+            return ANIMATION_FRAMES;
+        }
+
+        (elapsed.as_micros() * ANIMATION_FRAMES as u128 / self.duration.as_micros().max(1)) as u32
+    }
+}
+
+/// A step in closing a window which might not respond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KillStep {
+    /// Ask the window to close using `WM_DELETE_WINDOW`.
+    Delete,
+    /// Send SIGTERM to the window's process.
+    Terminate,
+    /// Send SIGKILL to the window's process, or disconnect the window's client if its process is
+    /// unknown.
+    Kill,
+}
+
+/// A window being closed by the `kill_unresponsive` action, along with the last step taken and
+/// the time at which the next step should be taken.
+#[derive(Debug)]
+struct PendingKill {
+    window: u32,
+    process_id: Option<u32>,
+    step: KillStep,
+    deadline: Instant,
+}
+
+impl PendingKill {
+    /// Take the next step if the deadline has passed, returning the step to take.
+    ///
+    /// The process is skipped straight to `Kill` if its process id is unknown.
+    fn escalate(&mut self, now: Instant, timeout: Duration) -> Option<KillStep> {
+        if now < self.deadline || self.step == KillStep::Kill {
+            return None;
+        }
+
+        self.step = match (self.step, self.process_id) {
+            (KillStep::Delete, Some(_)) => KillStep::Terminate,
+            _ => KillStep::Kill,
+        };
+        self.deadline = now + timeout;
+
+        Some(self.step)
+    }
 }
 
 // Mask for any key
@@ -76,13 +281,34 @@ const MIN_WIDTH: u16 = 160;
 const MIN_HEIGHT: u16 = 90;
 // Dragging speed
 const DRAG_SPEED_COEFFICIENT: f32 = 1.5;
+// Position to which windows which are kept mapped are moved to when hidden
+const OFF_SCREEN_POSITION: i32 = -32000;
+// _NET_WM_STATE client message actions
+const NET_WM_STATE_REMOVE: u32 = 0;
+const NET_WM_STATE_ADD: u32 = 1;
+const NET_WM_STATE_TOGGLE: u32 = 2;
+// _NET_SYSTEM_TRAY_OPCODE message asking the tray to dock an icon
+const SYSTEM_TRAY_REQUEST_DOCK: u32 = 0;
+// _XEMBED message telling an icon it has been embedded
+const XEMBED_EMBEDDED_NOTIFY: u32 = 0;
+// How much the master ratio changes with every `increase_master_ratio` or `decrease_master_ratio`
+const MASTER_RATIO_STEP: f32 = 0.05;
+// How long a replaced window manager gets to exit, before giving up on replacing it
+const REPLACE_TIMEOUT: Duration = Duration::from_secs(5);
+// How long the `execute_on` action waits for the window of a started command
+const SPAWN_TIMEOUT: Duration = Duration::from_secs(30);
+// How many steps windows take to slide off the screen when switching workspaces
+const ANIMATION_FRAMES: u32 = 8;
+// The longest windows can take to slide off the screen
+const MAX_ANIMATION_DURATION: u32 = 500;
 
 impl State {
     /// Connect to the X server and create WM state.
     ///
     /// If a name of the display is given, use that display, otherwise use the display from the
-    /// DISPLAY environmental variable.
-    pub fn new(name: Option<&str>, config: Rc<Config>) -> WmResult<Self> {
+    /// DISPLAY environmental variable. Fails if another window manager is already running, unless
+    /// `replace` is set and the other window manager agrees to exit.
+    pub fn new(name: Option<&str>, config: Rc<Config>, replace: bool) -> WmResult<Self> {
         let (connection, screen_index) =
             XCBConnection::connect(name.map(|s| unsafe { CStr::from_ptr(s.as_ptr() as _) }))?;
         let display = unsafe {
@@ -112,11 +338,35 @@ impl State {
 
         let root_window = connection.setup().roots[screen_index].root;
         let default_colormap = connection.setup().roots[screen_index].default_colormap;
-        connection.change_window_attributes(root_window, &change)?;
-        connection.flush()?;
-
         let atoms = Rc::new(AtomManager::init_atoms(&connection)?);
 
+        let (wm_selection, wm_selection_window) =
+            Self::acquire_wm_selection(&connection, screen_index, &atoms, replace)?;
+
+        // only one client can redirect the root window's substructure, this catches window
+        // managers which don't own the manager selection
+        if connection
+            .change_window_attributes(root_window, &change)?
+            .check()
+            .is_err()
+        {
+            return Err(
+                "x11 error: another window manager is already running on this display.".into(),
+            );
+        }
+        Self::set_supporting_wm_check(&connection, root_window, &atoms)?;
+        // get notified about bells, for the visual bell, which only works with XKB
+        if let Err(e) = Self::select_bell_events(&connection) {
+            errm!("the visual bell is unavailable: {}", e);
+        }
+        // get notified about monitors being plugged in and unplugged
+        connection.randr_query_version(1, 5)?.reply()?;
+        connection.randr_select_input(
+            root_window,
+            NotifyMask::SCREEN_CHANGE | NotifyMask::OUTPUT_CHANGE,
+        )?;
+        connection.flush()?;
+
         Ok(Self {
             connection: Arc::<XCBConnection>::new(connection),
             dpy: display,
@@ -126,18 +376,208 @@ impl State {
             key_manager: KeyManager::default(),
             last_client_id: 0,
             atoms,
+            wm_selection,
+            wm_selection_window,
             is_dragging: false,
             is_resizing: false,
+            is_reordering: false,
+            drop_indicator: None,
+            abort_keys_grabbed: false,
             config,
             monitors: Vec::new(),
+            unmanaged_monitors: Vec::new(),
+            displaced_workspaces: Vec::new(),
             floating_modifier: 64,
             default_colormap,
             xcb_connection: Rc::new(xcb_connection),
-            bar_windows: Vec::new(),
+            bar_windows: Arc::new(Mutex::new(Vec::new())),
             bars: Vec::new(),
+            tray_window: None,
+            focus_history: FocusStack::new(root_window),
+            struts: Vec::new(),
+            above_windows: Vec::new(),
+            below_windows: Vec::new(),
+            sticky_windows: Vec::new(),
+            swallowed_windows: Vec::new(),
+            bars_up_to_date: false,
+            pointer_position: None,
+            pinned_windows: Vec::new(),
+            bar_output: None,
+            last_workspaces: HashMap::new(),
+            fullscreen_origins: HashMap::new(),
+            fullscreen_windows: HashMap::new(),
+            gaps_enabled: true,
+            keyboard_layout: 0,
+            pending_kills: Vec::new(),
+            pending_spawns: PendingSpawns::default(),
+            pending_layouts: Vec::new(),
+            background_windows: HashMap::new(),
+            last_animation: None,
+            slide: None,
+            visual_bell: None,
+            active_window: None,
         })
     }
 
+    /// Make sure this is the only window manager running on the screen, by acquiring the ICCCM
+    /// `WM_S<screen>` manager selection for a new unmapped window. Returns the selection atom and
+    /// the window.
+    ///
+    /// If another window manager owns the selection, either fail or, with `replace` set, take the
+    /// selection away from it and wait for its window to be destroyed, which happens when it exits.
+    fn acquire_wm_selection(
+        connection: &XCBConnection,
+        screen_index: usize,
+        atoms: &AtomManager,
+        replace: bool,
+    ) -> WmResult<(u32, u32)> {
+        let root = connection.setup().roots[screen_index].root;
+        let name = format!("WM_S{screen_index}");
+        let selection = connection
+            .intern_atom(false, name.as_bytes())?
+            .reply()?
+            .atom;
+
+        let previous_owner = connection.get_selection_owner(selection)?.reply()?.owner;
+        if previous_owner != NONE {
+            if !replace {
+                return Err(Error::Generic(
+                    "another window manager is already running on this display, run crubwm with --replace to replace it.".into(),
+                ));
+            }
+            logm!(
+                LL_NORMAL,
+                "Replacing the window manager owning window {previous_owner}."
+            );
+            let watch = ChangeWindowAttributesAux::new().event_mask(EventMask::STRUCTURE_NOTIFY);
+            connection.change_window_attributes(previous_owner, &watch)?;
+        }
+
+        let window = connection.generate_id()?;
+        connection.create_window(
+            0,
+            window,
+            root,
+            -1,
+            -1,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            0,
+            &CreateWindowAux::new(),
+        )?;
+        connection.set_selection_owner(window, selection, CURRENT_TIME)?;
+        if connection.get_selection_owner(selection)?.reply()?.owner != window {
+            return Err(Error::Generic(
+                "x11 error: unable to acquire the window manager selection.".into(),
+            ));
+        }
+
+        if previous_owner != NONE {
+            let start = Instant::now();
+            loop {
+                match connection.poll_for_event()? {
+                    Some(Event::DestroyNotify(e)) if e.window == previous_owner => break,
+                    Some(_) => {}
+                    None if start.elapsed() > REPLACE_TIMEOUT => return Err(Error::Generic(
+                        "the running window manager did not exit in time, unable to replace it."
+                            .into(),
+                    )),
+                    None => std::thread::sleep(Duration::from_millis(10)),
+                }
+            }
+        }
+
+        // let everyone know there is a new window manager
+        let manager = atoms.get("MANAGER").unwrap().id();
+        let event =
+            ClientMessageEvent::new(32, root, manager, [CURRENT_TIME, selection, window, 0, 0]);
+        connection.send_event(false, root, EventMask::STRUCTURE_NOTIFY, event)?;
+
+        Ok((selection, window))
+    }
+
+    /// Ask XKB to report every bell which rings on the core keyboard.
+    fn select_bell_events(connection: &XCBConnection) -> WmResult {
+        connection.xkb_use_extension(1, 0)?.reply()?;
+        connection
+            .xkb_select_events(
+                xkb::ID::USE_CORE_KBD.into(),
+                0u16,
+                xkb::EventType::BELL_NOTIFY,
+                0u16,
+                0u16,
+                &xkb::SelectEventsAux::new(),
+            )?
+            .check()?;
+
+        Ok(())
+    }
+
+    /// Let clients know that an EWMH compliant window manager is running, by creating an unmapped
+    /// window named "crubwm", which both the root window and the window itself point to in their
+    /// `_NET_SUPPORTING_WM_CHECK` property, and by listing the supported atoms in the
+    /// `_NET_SUPPORTED` property of the root window.
+    fn set_supporting_wm_check(
+        connection: &XCBConnection,
+        root: u32,
+        atoms: &AtomManager,
+    ) -> WmResult {
+        let window = connection.generate_id()?;
+        connection.create_window(
+            0,
+            window,
+            root,
+            -1,
+            -1,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            0,
+            &CreateWindowAux::new(),
+        )?;
+
+        let check = atoms.get("_NET_SUPPORTING_WM_CHECK").unwrap().id();
+        for target in [root, window] {
+            connection.change_property32(
+                PropMode::REPLACE,
+                target,
+                check,
+                AtomEnum::WINDOW,
+                &[window],
+            )?;
+        }
+        connection.change_property8(
+            PropMode::REPLACE,
+            window,
+            atoms.get("_NET_WM_NAME").unwrap().id(),
+            atoms.get("UTF8_STRING").unwrap().id(),
+            b"crubwm",
+        )?;
+
+        let supported: Vec<u32> = atoms::SUPPORTED
+            .iter()
+            .filter_map(|name| atoms.get(name))
+            .map(|atom| atom.id())
+            .collect();
+        connection.change_property32(
+            PropMode::REPLACE,
+            root,
+            atoms.get("_NET_SUPPORTED").unwrap().id(),
+            AtomEnum::ATOM,
+            &supported,
+        )?;
+
+        Ok(())
+    }
+
+    /// Has another window manager taken the manager selection away, in order to replace this one?
+    pub fn is_replaced(&self, event: &SelectionClearEvent) -> bool {
+        event.selection == self.wm_selection && event.owner == self.wm_selection_window
+    }
+
     /// Initiate the `KeyManager` with the Keybindings loaded in from a configuration file.
     pub fn init_keyman(&mut self, binds: Keybinds) -> WmResult {
         logm!(LL_NORMAL, "Initializing keyboard manager.");
@@ -147,6 +587,7 @@ impl State {
         // ungrab any key with any modifier
         self.connection()
             .ungrab_key(ANY_KEY_MASK, self.root_window(), ANY_MOD_KEY_MASK)?;
+        self.abort_keys_grabbed = false;
 
         if let Some(mask) = self.key_manager.get_floating_modifier() {
             logm!(LL_FULL, "Floating modifier mask is {mask}",);
@@ -169,9 +610,19 @@ impl State {
     }
 
     pub fn bar_windows(&self) -> Vec<u32> {
+        self.bar_windows.lock().unwrap().clone()
+    }
+
+    /// Return the list of bar windows which is kept up to date when the bars are created again.
+    pub fn shared_bar_windows(&self) -> Arc<Mutex<Vec<u32>>> {
         self.bar_windows.clone()
     }
 
+    /// Return the settings of the loaded config, which are replaced when the config is reloaded.
+    pub fn settings(&self) -> &Settings {
+        &self.config.settings
+    }
+
     /// Get the information about the current root of our display.
     fn root_screen(&self) -> &Screen {
         &self.connection.setup().roots[self.screen_index]
@@ -204,30 +655,77 @@ impl State {
 
     /// Try and return the value of the `_NET_WM_NAME` or `WM_NAME` properties for the currently focused window.
     fn focused_window_name(&self) -> WmResult<String> {
-        let mut ret_str: Option<String> = None;
         let ws = self.get_focused_workspace()?;
-        if let Some(win) = ws.focus.focused_client() {
-            if let Some(atom) = self.atoms.get("_NET_WM_NAME") {
-                let ret = atom.get_property(win, self.connection(), None)?;
-                if let Some(first) = ret.first() {
-                    let str: String = first.clone().try_into()?;
-                    if !str.is_empty() && ret_str.is_none() {
-                        ret_str = Some(str);
-                    }
+        let name = match ws.focus.focused_client() {
+            Some(win) => self.window_name(win)?,
+            None => None,
+        };
+
+        Ok(name.unwrap_or_else(|| "N/A".to_string()))
+    }
+
+    /// Return the value of the `_NET_WM_NAME` property of a window, falling back to `WM_NAME`.
+    fn window_name(&self, win: u32) -> WmResult<Option<String>> {
+        let mut ret_str: Option<String> = None;
+        if let Some(atom) = self.atoms.get("_NET_WM_NAME") {
+            let ret = atom.get_property(win, self.connection(), None)?;
+            if let Some(first) = ret.first() {
+                let str: String = first.clone().try_into()?;
+                if !str.is_empty() && ret_str.is_none() {
+                    ret_str = Some(str);
                 }
             }
-            if let Some(atom) = self.atoms.get("WM_NAME") {
-                let ret = atom.get_property(win, self.connection(), None)?;
-                if let Some(first) = ret.first() {
-                    let str: String = first.clone().try_into()?;
-                    if !str.is_empty() && ret_str.is_none() {
-                        ret_str = Some(str);
-                    }
+        }
+        if let Some(atom) = self.atoms.get("WM_NAME") {
+            let ret = atom.get_property(win, self.connection(), None)?;
+            if let Some(first) = ret.first() {
+                let str: String = first.clone().try_into()?;
+                if !str.is_empty() && ret_str.is_none() {
+                    ret_str = Some(str);
                 }
             }
         }
 
-        Ok(ret_str.unwrap_or_else(|| "N/A".to_string()))
+        Ok(ret_str)
+    }
+
+    /// Return the class part of the WM_CLASS property of a window, if the window has one.
+    fn window_class(&self, window: u32) -> Option<String> {
+        let class = WmClass::get(self.connection.as_ref(), window)
+            .ok()?
+            .reply()
+            .ok()?;
+        String::from_utf8(class.class().to_vec())
+            .ok()
+            .filter(|class| !class.is_empty())
+    }
+
+    /// Return the sizes a window asks for in its `WM_NORMAL_HINTS` property, or no constraints at
+    /// all, if it doesn't have one.
+    fn size_hints(&self, window: u32) -> SizeHints {
+        WmSizeHints::get_normal_hints(self.connection.as_ref(), window)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|hints| SizeHints::from(&hints))
+            .unwrap_or_default()
+    }
+
+    /// Look up what the window rules from the config want to happen to a new window, based on
+    /// its `WM_CLASS` instance and class and its title.
+    fn rule_effects(&self, window: u32) -> RuleEffects {
+        let class = WmClass::get(self.connection.as_ref(), window)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|class| WmClassWrapper::from_class(&class));
+        let (instance, class) = match class {
+            Some(class) => (class.instance, class.class),
+            None => (None, None),
+        };
+        let title = self.window_name(window).ok().flatten();
+
+        self.config
+            .rules
+            .effects_for(instance.as_deref(), class.as_deref(), title.as_deref())
     }
 
     /// Go through all workspaces, if they contain a given window: return the reference to the
@@ -238,6 +736,14 @@ impl State {
             .find(|workspace| workspace.contains_window(wid))
     }
 
+    /// Is the window managed by the window manager?
+    ///
+    /// Override-redirect windows, such as menus and tooltips, and the bar windows are never
+    /// managed. Events for them are ignored.
+    fn is_managed_window(&self, wid: u32) -> bool {
+        self.workspace_for_window(wid).is_some()
+    }
+
     // Get a pointer to Xlib display structure. This method is used for handling keyboard
     // events(KeyPress and KeyRelease events).
     fn display(&mut self) -> *mut Display {
@@ -291,9 +797,14 @@ impl State {
         self.setup_monitors()?;
         for workspace_settings in self.config.workspace_settings.clone().into_iter() {
             let layout_mask = LayoutMask::from_slice(&workspace_settings.allowed_layouts)?;
-            let (monitor_index, screen_size) =
-                self.get_screen_size_for_workspace(workspace_settings.monitor.clone())?;
-            let workspace = Workspace::new(
+            let (monitor_index, displaced) =
+                monitor_after_change(None, &workspace_settings.monitor, &self.monitors);
+            if displaced {
+                self.displaced_workspaces
+                    .push(workspace_settings.identifier);
+            }
+            let screen_size = self.monitors[monitor_index].usable_size();
+            let mut workspace = Workspace::new(
                 workspace_settings.name.clone(),
                 workspace_settings.identifier,
                 layout_mask,
@@ -301,6 +812,11 @@ impl State {
                 screen_size,
                 self.monitors[monitor_index].id(),
             );
+            workspace.set_master_ratio(workspace_settings.master_ratio);
+            workspace.set_master_fill_when_alone(self.config.settings.master_fill_when_alone);
+            workspace.set_stack_orientation(StackOrientation::try_from(
+                workspace_settings.stack_orientation.as_str(),
+            )?);
 
             logm!(LL_FULL, "Setting up a new workspace: {workspace:?}",);
             self.workspaces.push(workspace);
@@ -311,51 +827,239 @@ impl State {
                 errm!("{}", e);
             }
         }
+        self.update_desktops()?;
 
         self.focus_workspace(self.workspaces[0].id, true)?;
 
-        Ok(())
+        self.update_backgrounds()
     }
 
-    /// Helper function to determine which output id should go to which worksapce.
-    fn get_screen_size_for_workspace(
-        &self,
-        monitor_number_string: String,
-    ) -> WmResult<(usize, Geometry)> {
-        // TODO: if this fails a warning should be returned.
-        let monitor_number = monitor_number_string.parse::<usize>().unwrap_or(0);
+    /// Log a warning for every workspace and bar which is set to a monitor that doesn't exist.
+    pub fn warn_about_monitor_settings(&self) {
+        let all_monitors: Vec<Monitor> = self
+            .monitors
+            .iter()
+            .chain(self.unmanaged_monitors.iter())
+            .cloned()
+            .collect();
 
-        if let Some(monitor) = self.monitors.get(monitor_number) {
-            return Ok((monitor_number, monitor.size()));
+        for warning in check_monitor_settings(&self.config, &all_monitors) {
+            errm!("config warning: {warning}");
         }
-
-        Err(format!("worksapce error: unable to construct workspace: monitor with index {monitor_number_string} not found.").into())
     }
 
     /// Create and setup monitors for workspaces.
+    ///
+    /// Monitors which aren't listed in the `manage_monitors` setting are left alone. Monitor ids
+    /// follow the order in which RandR reports the monitors, regardless of which of them are
+    /// managed.
     fn setup_monitors(&mut self) -> WmResult {
         logm!(LL_NORMAL, "Setting up monitors...");
         let monitor_reply =
             get_monitors(self.connection().as_ref(), self.root_window(), false)?.reply()?;
+        let listed = self.config.settings.manage_monitors();
         let mut current_monitor_id = 0u32;
 
         for monitor_info in monitor_reply.monitors {
             current_monitor_id += 1;
-            let monitor = Monitor::from_monitor_info(monitor_info, current_monitor_id)?;
-            logm!(LL_FULL, "Discovered monitor: {monitor:?}");
-            self.monitors.push(monitor)
+            let name = self
+                .connection()
+                .get_atom_name(monitor_info.name)?
+                .reply()
+                .map(|reply| String::from_utf8_lossy(&reply.name).into_owned())
+                .unwrap_or_default();
+            let mut monitor = Monitor::from_monitor_info(monitor_info, current_monitor_id)?;
+            if let Some(settings) = self.config.monitor_settings.get(&name) {
+                monitor.set_padding(settings.padding);
+            }
+            monitor.set_name(name);
+
+            if monitor.is_listed(current_monitor_id as usize - 1, &listed) {
+                logm!(LL_FULL, "Discovered monitor: {monitor:?}");
+                self.monitors.push(monitor)
+            } else {
+                logm!(LL_NORMAL, "Leaving monitor {} alone.", monitor.name());
+                self.unmanaged_monitors.push(monitor)
+            }
+        }
+
+        if self.monitors.is_empty() {
+            return Err(format!(
+                "monitor error: none of the monitors listed in manage_monitors ({}) exist.",
+                listed.join(", ")
+            )
+            .into());
         }
 
         Ok(())
     }
 
+    /// Handle a change of the connected monitors, after a monitor has been plugged in, unplugged
+    /// or reconfigured.
+    ///
+    /// The monitors are enumerated again. Workspaces stay on their monitor if it's still there.
+    /// Workspaces of monitors which are gone go to the monitor from their settings, or to the first
+    /// monitor if that one is gone too, along with all of their windows, and go back once their
+    /// monitor is there again. The bars are then created again for the new monitors.
+    pub fn handle_screen_change(&mut self) -> WmResult {
+        let old_monitors = std::mem::take(&mut self.monitors);
+        let old_unmanaged_monitors = std::mem::take(&mut self.unmanaged_monitors);
+        if let Err(e) = self.setup_monitors() {
+            self.monitors = old_monitors;
+            self.unmanaged_monitors = old_unmanaged_monitors;
+            return Err(e);
+        }
+
+        // RandR reports all kinds of changes, only the monitors themselves matter here
+        let layout = |monitors: &[Monitor]| -> Vec<(String, Geometry)> {
+            monitors
+                .iter()
+                .map(|m| (m.name().to_string(), m.size()))
+                .collect()
+        };
+        if layout(&old_monitors) == layout(&self.monitors)
+            && layout(&old_unmanaged_monitors) == layout(&self.unmanaged_monitors)
+        {
+            self.monitors = old_monitors;
+            self.unmanaged_monitors = old_unmanaged_monitors;
+            return Ok(());
+        }
+        logm!(
+            LL_NORMAL,
+            "Monitors changed, now managing: {}",
+            self.monitors
+                .iter()
+                .map(|m| m.name())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+
+        let focused_workspace = self.focused_workspace;
+        let focused_client = self
+            .get_focused_workspace()
+            .ok()
+            .and_then(|workspace| workspace.focus.focused_client());
+        // the workspaces which were shown on each monitor, by monitor name
+        let open_workspaces: Vec<(String, WorkspaceId)> = old_monitors
+            .iter()
+            .filter_map(|m| Some((m.name().to_string(), m.get_open_workspace().ok()?)))
+            .collect();
+
+        let workspace_ids: Vec<WorkspaceId> = self.workspaces.iter().map(|w| w.id).collect();
+        for id in workspace_ids.iter() {
+            let setting = self
+                .config
+                .workspace_settings
+                .get(*id)
+                .map(|settings| settings.monitor.clone())
+                .unwrap_or_default();
+            let previous = self
+                .workspace_with_id(*id)
+                .filter(|_| !self.displaced_workspaces.contains(id))
+                .and_then(|workspace| old_monitors.iter().find(|m| m.id() == workspace.monitor))
+                .map(|monitor| monitor.name());
+            let (index, displaced) = monitor_after_change(previous, &setting, &self.monitors);
+            self.displaced_workspaces
+                .retain(|displaced_id| displaced_id != id);
+            if displaced {
+                self.displaced_workspaces.push(*id);
+            }
+            let screen = self.monitors[index].usable_size();
+            let monitor_id = self.monitors[index].id();
+            self.monitors[index].add_workspace(*id);
+            if let Some(workspace) = self.workspace_with_id_mut(*id) {
+                workspace.monitor = monitor_id;
+                workspace.set_screen(screen);
+            }
+        }
+
+        self.show_placed_workspaces(&open_workspaces, focused_workspace, focused_client)
+    }
+
+    /// Show the workspaces after they have been placed onto the monitors again, then set up the
+    /// focus, the bars and the layouts for the new placement.
+    ///
+    /// Each monitor shows the workspace it showed before, given by monitor name in
+    /// `open_workspaces`, if that workspace is still on it, or its first workspace otherwise.
+    fn show_placed_workspaces(
+        &mut self,
+        open_workspaces: &[(String, WorkspaceId)],
+        focused_workspace: Option<WorkspaceId>,
+        focused_client: Option<u32>,
+    ) -> WmResult {
+        self.finish_slide()?;
+        let workspace_ids: Vec<WorkspaceId> = self.workspaces.iter().map(|w| w.id).collect();
+        // show the workspace which was shown on each monitor before, if it's still there
+        for monitor in self.monitors.iter_mut() {
+            let open = open_workspaces
+                .iter()
+                .find(|(name, id)| name == monitor.name() && monitor.contains(id))
+                .map(|(_, id)| *id);
+            if let Err(e) = monitor.set_open_workspace(open) {
+                errm!("{}", e);
+            }
+            monitor.focus(false);
+        }
+        let open: Vec<WorkspaceId> = self
+            .monitors
+            .iter()
+            .filter_map(|m| m.get_open_workspace().ok())
+            .collect();
+        for id in workspace_ids {
+            if open.contains(&id) {
+                self.show_workspace(id)?;
+            } else {
+                self.hide_workspace(id)?;
+            }
+        }
+
+        let focused_workspace = focused_workspace
+            .filter(|id| open.contains(id))
+            .or_else(|| open.first().copied())
+            .ok_or_else(|| Error::Generic("monitor error: no workspace is shown.".into()))?;
+        self.focused_workspace = Some(focused_workspace);
+        let monitor = self.monitor_for_workspace_mut(focused_workspace)?;
+        monitor.focus(true);
+        monitor.set_last_focused_workspace(focused_workspace)?;
+        self.update_current_desktop()?;
+        self.update_layout_property()?;
+
+        self.recreate_bars()?;
+        let monitor_ids: Vec<u32> = self.monitors.iter().map(|m| m.id()).collect();
+        for monitor_id in monitor_ids {
+            self.fit_workspaces(monitor_id)?;
+        }
+        self.update_backgrounds()?;
+
+        self.restore_focus(Some(focused_workspace), focused_client)
+    }
+
+    /// Destroy all the bars and create them again, for example after the bar settings or the
+    /// monitors have changed.
+    fn recreate_bars(&mut self) -> WmResult {
+        self.release_tray()?;
+        // the bar update thread doesn't touch the windows while they are being destroyed
+        let mut bar_windows = self.bar_windows.lock().unwrap();
+        for bar_window in bar_windows.iter() {
+            self.connection().destroy_window(*bar_window)?;
+        }
+        bar_windows.clear();
+        drop(bar_windows);
+
+        self.setup_bars()
+    }
+
     /// Create and setup status bar windows based on the status bar settings.
+    ///
+    /// The workspaces are not resized to fit between the bars, see [`State::fit_all_workspaces`].
     pub fn setup_bars(&mut self) -> WmResult {
         // indicates, that there already is a icon tray and that all all further trays should be
         // ingored
         logm!(LL_NORMAL, "Setting up status bars...");
-        let mut _has_tray = false;
+        let mut has_tray = false;
         let mut bars = Vec::new();
+        // widgets of the bars from before a config reload, whose values are carried over
+        let old_widgets: Vec<Widget> = self.bars.iter().flat_map(|bar| bar.widgets()).collect();
         // intitial bar construction
         for bar_settings in self.config.bar_settings.clone().into_iter() {
             bars.push(Bar::new(
@@ -364,6 +1068,12 @@ impl State {
                 &bar_settings,
             )?);
         }
+        // monitors which aren't managed, or don't exist, don't get a bar
+        bars.retain(|bar| {
+            self.monitors
+                .iter()
+                .any(|monitor| monitor.id() == bar.monitor() + 1)
+        });
         // setup bars on different monitors
         for bar in bars.iter_mut() {
             let monitor = self
@@ -377,19 +1087,24 @@ impl State {
                     ))
                 })?;
             let monitor_geometry = monitor.size();
-            let bar_workspace_name_ids: Vec<(String, u32)> = self
+            let bar_workspace_name_ids: Vec<(String, String, u32)> = self
                 .config
                 .workspace_settings
                 .clone()
                 .into_iter()
-                .filter(|ws| ws.monitor.parse::<u32>().unwrap_or(0) == bar.monitor())
-                .map(|ws| (ws.name, ws.identifier))
+                .filter(|ws| {
+                    self.workspace_with_id(ws.identifier)
+                        .map(|workspace| workspace.monitor == bar.monitor() + 1)
+                        .unwrap_or(false)
+                })
+                .map(|ws| (ws.name, ws.icon, ws.identifier))
                 .collect();
 
             // tell the bar what workspaces to display
             bar.create_workspaces(bar_workspace_name_ids);
 
-            // initialize bar commands
+            // initialize bar commands, only running those which weren't there before a reload
+            bar.restore_widgets(&old_widgets);
             bar.update_widgets()?;
 
             // create bar windows and do all the necessary graphical setup
@@ -409,38 +1124,29 @@ impl State {
                     EventMask::STRUCTURE_NOTIFY
                         | EventMask::EXPOSURE
                         | EventMask::KEY_PRESS
+                        | EventMask::BUTTON_PRESS
                         | EventMask::PROPERTY_CHANGE,
                 );
 
-            if !bar.settings()?.location_top {
-                self.connection().create_window(
-                    screen.root_depth,
-                    window_id,
-                    screen.root,
-                    monitor_geometry.x,
-                    monitor_geometry.height as i16 - bar.settings()?.height as i16,
-                    monitor_geometry.width,
-                    bar.settings()?.height as _, // this should be changed, it should be calculated from the bar font
-                    0,
-                    WindowClass::INPUT_OUTPUT,
-                    screen.root_visual,
-                    &values,
-                )?;
-            } else {
-                self.connection().create_window(
-                    screen.root_depth,
-                    window_id,
-                    screen.root,
-                    monitor_geometry.x,
-                    monitor_geometry.y,
-                    monitor_geometry.width,
-                    bar.settings()?.height as _, // this should be changed, it should be calculated from the bar font
-                    0,
-                    WindowClass::INPUT_OUTPUT,
-                    screen.root_visual,
-                    &values,
-                )?;
-            }
+            let position = bar.settings()?.position;
+            // the y coordinate of a bar of the given height on this monitor
+            let bar_y = |height: i16| match position {
+                BarPosition::Top => monitor_geometry.y,
+                BarPosition::Bottom => monitor_geometry.y + monitor_geometry.height as i16 - height,
+            };
+            self.connection().create_window(
+                screen.root_depth,
+                window_id,
+                screen.root,
+                monitor_geometry.x,
+                bar_y(bar.settings()?.height as i16),
+                monitor_geometry.width,
+                bar.settings()?.height as _, // this should be changed, it should be calculated from the bar font
+                0,
+                WindowClass::INPUT_OUTPUT,
+                screen.root_visual,
+                &values,
+            )?;
             let mut visual_ffi = find_xcb_visualtype(
                 self.connection.as_ref(),
                 self.connection().setup().roots[self.screen_index].root_visual,
@@ -460,10 +1166,7 @@ impl State {
             bar.set_window_id(window_id);
             if let Ok(h) = bar.get_height() {
                 // decide the y coordiante of the bar window
-                let mut y = 0;
-                if !bar.settings()?.location_top {
-                    y = monitor_geometry.height as i32 - h as i32;
-                }
+                let y = bar_y(h as i16) as i32;
 
                 // update the bar window
                 self.connection().configure_window(
@@ -489,19 +1192,12 @@ impl State {
                 geom.height = h as _;
                 geom.y = y as _;
                 bar.set_geometry(geom);
-
-                // update every workspace to the correct screen size, after the new bar is set.
-                let connection = self.connection();
-                for workspace in self.workspaces.iter_mut() {
-                    if workspace.monitor == bar.monitor() + 1 {
-                        let g = workspace.screen().minus_bar(geom);
-                        workspace.set_screen(g);
-                        workspace.apply_layout(connection.clone(), None, self.default_colormap)?;
-                    }
-                }
             }
-            self.bar_windows.push(window_id);
+            self.bar_windows.lock().unwrap().push(window_id);
             self.connection().map_window(window_id)?;
+            if bar.has_tray() && !has_tray {
+                has_tray = self.acquire_tray(window_id)?;
+            }
             self.connection().flush()?;
 
             logm!(
@@ -523,50 +1219,254 @@ impl State {
         Ok(())
     }
 
-    /// Update and redraw all bar windows.
-    pub fn update_bars(&mut self) -> WmResult {
-        let window_name = self
-            .focused_window_name()
-            .unwrap_or_else(|_| "N/A".to_string());
-        for bar in self.bars.iter_mut() {
-            let monitors: Vec<&Monitor> = self
-                .monitors
-                .iter()
-                .filter(|mon| mon.id() == bar.monitor() + 1)
-                .collect();
-            if let Some(monitor) = monitors.first() {
-                if let Ok(ws) = monitor.get_open_workspace() {
-                    bar.update(self.focused_workspace, Some(ws), window_name.clone())?
-                } else {
-                    bar.update(self.focused_workspace, None, window_name.clone())?
-                }
-            }
-            bar.redraw()?
+    /// Become the system tray, by acquiring the `_NET_SYSTEM_TRAY_S<screen>` selection for the
+    /// given bar window. Returns false if another program already is the system tray.
+    fn acquire_tray(&mut self, window: u32) -> WmResult<bool> {
+        let connection = self.connection();
+        let name = format!("_NET_SYSTEM_TRAY_S{}", self.screen_index);
+        let selection = connection
+            .intern_atom(false, name.as_bytes())?
+            .reply()?
+            .atom;
+
+        if connection.get_selection_owner(selection)?.reply()?.owner != NONE {
+            logm!(
+                LL_NORMAL,
+                "Another system tray is already running, the icon tray will stay empty."
+            );
+            return Ok(false);
         }
 
-        Ok(())
-    }
+        connection.set_selection_owner(window, selection, CURRENT_TIME)?;
+        if connection.get_selection_owner(selection)?.reply()?.owner != window {
+            return Err(Error::Generic(
+                "system tray error: unable to acquire the system tray selection.".into(),
+            ));
+        }
 
-    /// Get a reference to the focused workspace.
-    fn get_focused_workspace(&self) -> WmResult<&Workspace> {
-        if let Some(id) = self.focused_workspace {
-            if let Some(ws) = self.workspaces.iter().find(|ws| ws.id == id) {
-                return Ok(ws);
-            }
+        if let Some(orientation) = self.atoms.get("_NET_SYSTEM_TRAY_ORIENTATION") {
+            connection.change_property32(
+                PropMode::REPLACE,
+                window,
+                orientation.id(),
+                AtomEnum::CARDINAL,
+                &[0],
+            )?;
         }
 
-        Err("focused workspace could not be found".into())
+        // let the tray icons know there is a new system tray
+        let root = self.root_window();
+        let manager = self.atoms.get("MANAGER").unwrap().id();
+        let event =
+            ClientMessageEvent::new(32, root, manager, [CURRENT_TIME, selection, window, 0, 0]);
+        connection.send_event(false, root, EventMask::STRUCTURE_NOTIFY, event)?;
+
+        self.tray_window = Some(window);
+        logm!(LL_NORMAL, "Bar window {window} is now the system tray.");
+
+        Ok(true)
     }
 
-    /// Get a mutable reference to the focused workspace.
-    fn get_focused_workspace_mut(&mut self) -> WmResult<&mut Workspace> {
-        if let Some(id) = self.focused_workspace {
-            if let Some(ws) = self.workspaces.iter_mut().find(|ws| ws.id == id) {
-                return Ok(ws);
+    /// Embed a tray icon window into the bar window owning the system tray.
+    fn dock_tray_icon(&mut self, icon: u32) -> WmResult {
+        let tray = match self.tray_window {
+            Some(tray) => tray,
+            None => return Ok(()),
+        };
+
+        if self.bars.iter().any(|bar| bar.has_tray_icon(icon)) {
+            return Ok(());
+        }
+
+        let connection = self.connection();
+        // the icon is watched only after reparenting it, so that it isn't removed from the tray
+        // right away, if it has already been mapped
+        connection.reparent_window(icon, tray, 0, 0)?;
+        connection.change_window_attributes(
+            icon,
+            &ChangeWindowAttributesAux::new().event_mask(EventMask::STRUCTURE_NOTIFY),
+        )?;
+        connection.change_save_set(SetMode::INSERT, icon)?;
+
+        let xembed = self.atoms.get("_XEMBED").unwrap().id();
+        let event = ClientMessageEvent::new(
+            32,
+            icon,
+            xembed,
+            [CURRENT_TIME, XEMBED_EMBEDDED_NOTIFY, 0, tray, 0],
+        );
+        connection.send_event(false, icon, EventMask::NO_EVENT, event)?;
+        connection.map_window(icon)?;
+
+        for bar in self.bars.iter_mut() {
+            if bar.window_id().ok() == Some(tray) {
+                bar.add_tray_icon(icon);
             }
         }
 
-        Err("focused workspace could not be found".into())
+        logm!(LL_NORMAL, "Docked tray icon {icon}.");
+        self.bars_up_to_date = false;
+
+        Ok(())
+    }
+
+    /// Remove an icon from the system tray, if it's there.
+    ///
+    /// Icons which have only been unmapped are given back to the root window.
+    pub fn undock_tray_icon(&mut self, icon: u32, destroyed: bool) -> WmResult {
+        let mut removed = false;
+        for bar in self.bars.iter_mut() {
+            removed |= bar.remove_tray_icon(icon);
+        }
+
+        if !removed {
+            return Ok(());
+        }
+
+        if !destroyed {
+            let connection = self.connection();
+            connection.change_window_attributes(
+                icon,
+                &ChangeWindowAttributesAux::new().event_mask(EventMask::NO_EVENT),
+            )?;
+            connection.reparent_window(icon, self.root_window(), 0, 0)?;
+            connection.change_save_set(SetMode::DELETE, icon)?;
+        }
+
+        logm!(LL_NORMAL, "Removed tray icon {icon}.");
+        self.bars_up_to_date = false;
+
+        Ok(())
+    }
+
+    /// Give all the tray icons back to the root window, before the bar windows are destroyed.
+    ///
+    /// The icons are hidden until their applets dock them again, after a new system tray
+    /// announces itself.
+    fn release_tray(&mut self) -> WmResult {
+        let icons: Vec<u32> = self.bars.iter().flat_map(|bar| bar.tray_icons()).collect();
+        for icon in icons {
+            self.connection().unmap_window(icon)?;
+            self.undock_tray_icon(icon, false)?;
+        }
+        self.tray_window = None;
+
+        Ok(())
+    }
+
+    /// Update and redraw all bar windows.
+    pub fn update_bars(&mut self) -> WmResult {
+        let window_name = self
+            .focused_window_name()
+            .unwrap_or_else(|_| "N/A".to_string());
+        let keyboard_layout = self
+            .config
+            .settings
+            .keyboard_layouts()
+            .get(self.keyboard_layout)
+            .cloned()
+            .unwrap_or_default();
+        let dnd_workspaces: Vec<WorkspaceId> = self
+            .workspaces
+            .iter()
+            .filter(|ws| ws.is_dnd())
+            .map(|ws| ws.id)
+            .collect();
+        for bar in self.bars.iter_mut() {
+            bar.set_keyboard_layout(&keyboard_layout);
+            bar.set_dnd_workspaces(&dnd_workspaces);
+            let monitors: Vec<&Monitor> = self
+                .monitors
+                .iter()
+                .filter(|mon| mon.id() == bar.monitor() + 1)
+                .collect();
+            if let Some(monitor) = monitors.first() {
+                if let Ok(ws) = monitor.get_open_workspace() {
+                    bar.update(self.focused_workspace, Some(ws), window_name.clone())?
+                } else {
+                    bar.update(self.focused_workspace, None, window_name.clone())?
+                }
+            }
+            bar.redraw()?;
+            for (icon, geometry) in bar.place_tray_icons() {
+                self.connection.configure_window(icon, &geometry.into())?;
+            }
+        }
+
+        self.bars_up_to_date = true;
+        self.write_bar_output(&window_name)?;
+
+        Ok(())
+    }
+
+    /// Write the state of the workspaces for external bars, if the `bar_output` setting is set.
+    fn write_bar_output(&mut self, window_name: &str) -> WmResult {
+        let target = &self.config.settings.bar_output;
+        if target.is_empty() {
+            self.bar_output = None;
+            return Ok(());
+        }
+        if self.bar_output.as_ref().map(|o| o.target()) != Some(target.as_str()) {
+            self.bar_output = Some(BarOutput::new(target));
+        }
+
+        let workspaces: Vec<WorkspaceStatus> = self
+            .workspaces
+            .iter()
+            .map(|ws| WorkspaceStatus {
+                id: ws.id,
+                name: ws.name.clone(),
+                monitor: ws.monitor,
+                open: self
+                    .monitors
+                    .iter()
+                    .any(|m| m.get_open_workspace().ok() == Some(ws.id)),
+                focused: self.focused_workspace == Some(ws.id),
+            })
+            .collect();
+
+        if let Some(output) = self.bar_output.as_mut() {
+            output.write(&workspaces, window_name)?;
+        }
+
+        Ok(())
+    }
+
+    /// Redraw the bars, unless they have already been redrawn since the last change.
+    pub fn update_bars_if_needed(&mut self) -> WmResult {
+        if !self.bars_up_to_date {
+            self.update_bars()?
+        }
+
+        Ok(())
+    }
+
+    /// Mark the bars as out of date, so that the next call to `update_bars_if_needed` redraws
+    /// them.
+    pub fn invalidate_bars(&mut self) {
+        self.bars_up_to_date = false
+    }
+
+    /// Get a reference to the focused workspace.
+    fn get_focused_workspace(&self) -> WmResult<&Workspace> {
+        if let Some(id) = self.focused_workspace {
+            if let Some(ws) = self.workspaces.iter().find(|ws| ws.id == id) {
+                return Ok(ws);
+            }
+        }
+
+        Err("focused workspace could not be found".into())
+    }
+
+    /// Get a mutable reference to the focused workspace.
+    fn get_focused_workspace_mut(&mut self) -> WmResult<&mut Workspace> {
+        if let Some(id) = self.focused_workspace {
+            if let Some(ws) = self.workspaces.iter_mut().find(|ws| ws.id == id) {
+                return Ok(ws);
+            }
+        }
+
+        Err("focused workspace could not be found".into())
     }
 
     /// Return a mutable reference to the currently open workspace on a monitor which the cursor is
@@ -603,6 +1503,8 @@ impl State {
 
     /// Focus a workspace.
     pub fn focus_workspace(&mut self, workspace_id: WorkspaceId, warp_pointer: bool) -> WmResult {
+        // a workspace still sliding off is hidden first, in case it's shown again
+        self.finish_slide()?;
         // 1. find the currently focused monitor and workspace
         //
         // 2. find the monitor of the 'to be' focused workspace
@@ -623,24 +1525,15 @@ impl State {
         // variant A
         if current_focused_monitor_id == new_focused_monitor_id {
             if let Ok(focused_workspace) = self.get_focused_workspace() {
-                for container in focused_workspace.iter_containers()? {
-                    if let Some(wid) = container.data().window_id() {
-                        self.connection().unmap_subwindows(wid)?;
-                        self.connection().unmap_window(wid)?;
-                    }
-                }
+                let focused_workspace_id = focused_workspace.id;
+                self.move_sticky_windows(focused_workspace_id, workspace_id)?;
+                self.slide_out_workspace(
+                    focused_workspace_id,
+                    workspace_id > focused_workspace_id,
+                )?;
             }
 
-            let workspace = self
-                .workspace_with_id(workspace_id)
-                .ok_or_else(|| Error::Generic("No workspace with given id exists".into()))?;
-
-            for container in workspace.iter_containers()? {
-                if let Some(wid) = container.data().window_id() {
-                    self.connection().map_window(wid)?;
-                    self.connection().map_subwindows(wid)?;
-                }
-            }
+            self.show_workspace(workspace_id)?;
 
             self.monitor_with_id_mut(current_focused_monitor_id)?
                 .set_open_workspace(Some(workspace_id))?;
@@ -662,25 +1555,12 @@ impl State {
                 self.focused_workspace = Some(workspace_id);
             } else {
                 // variant B.2.
-                if let Some(focused_workspace) = self.workspace_with_id(open_workspace_id) {
-                    for container in focused_workspace.iter_containers()? {
-                        if let Some(wid) = container.data().window_id() {
-                            self.connection().unmap_subwindows(wid)?;
-                            self.connection().unmap_window(wid)?;
-                        }
-                    }
+                if self.workspace_with_id(open_workspace_id).is_some() {
+                    self.move_sticky_windows(open_workspace_id, workspace_id)?;
+                    self.slide_out_workspace(open_workspace_id, workspace_id > open_workspace_id)?;
                 }
 
-                let workspace = self
-                    .workspace_with_id(workspace_id)
-                    .ok_or_else(|| Error::Generic("No workspace with given id exists".into()))?;
-
-                for container in workspace.iter_containers()? {
-                    if let Some(wid) = container.data().window_id() {
-                        self.connection().map_window(wid)?;
-                        self.connection().map_subwindows(wid)?;
-                    }
-                }
+                self.show_workspace(workspace_id)?;
                 let monitor = self.monitor_with_id_mut(new_focused_monitor_id)?;
                 monitor.focus(true);
                 monitor.set_open_workspace(Some(workspace_id))?;
@@ -690,27 +1570,273 @@ impl State {
             }
         }
 
+        self.monitor_with_id_mut(new_focused_monitor_id)?
+            .set_last_focused_workspace(workspace_id)?;
+        self.update_current_desktop()?;
+        self.update_layout_property()?;
+        self.update_backgrounds()?;
+        self.restack()?;
+        self.repin_windows()?;
+
         let workspace = self.get_focused_workspace()?;
         let size = workspace.screen();
 
         logm!(LL_FULL, "Focused workspace is: {}", workspace.id);
 
         if warp_pointer {
-            self.connection().warp_pointer(
-                NONE,
+            focus_model::focus_without_pointer(
+                self.connection().as_ref(),
+                &self.config.settings,
                 self.root_window(),
-                0,
-                0,
-                0,
-                0,
-                size.x + (size.width / 2) as i16,
-                size.y + (size.height / 2) as i16,
+                None,
+                size,
             )?;
         }
 
+        // Show the newly focused workspace and its focused window's title right away.
+        self.update_bars()?;
+
+        Ok(())
+    }
+
+    /// Move all the sticky windows from one workspace to another.
+    fn move_sticky_windows(&mut self, from: WorkspaceId, to: WorkspaceId) -> WmResult {
+        let new_windows_first = self.config.settings.new_windows_first();
+        let sticky_windows = self.sticky_windows.clone();
+
+        for window in sticky_windows {
+            let contains = self
+                .workspace_with_id(from)
+                .map(|workspace| workspace.contains_window(window))
+                .unwrap_or(false);
+
+            if contains {
+                let container = self
+                    .workspace_with_id_mut(from)
+                    .unwrap()
+                    .remove_and_return_window(window)?;
+                if let Some(workspace) = self.workspace_with_id_mut(to) {
+                    workspace.insert_container(container, new_windows_first)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Hide all the windows of a workspace.
+    ///
+    /// Usually, the windows are unmapped. If the workspace has the `keep_mapped` setting enabled,
+    /// the windows are moved off-screen instead, so that they keep on rendering.
+    fn hide_workspace(&self, workspace_id: WorkspaceId) -> WmResult {
+        let keep_mapped = self.workspace_keeps_mapped(workspace_id);
+        let workspace = self
+            .workspace_with_id(workspace_id)
+            .ok_or_else(|| Error::Generic("No workspace with given id exists".into()))?;
+
+        for container in workspace.iter_containers()? {
+            if let Some(wid) = container.data().window_id() {
+                if keep_mapped {
+                    self.connection().configure_window(
+                        wid,
+                        &ConfigureWindowAux::new()
+                            .x(OFF_SCREEN_POSITION)
+                            .y(OFF_SCREEN_POSITION),
+                    )?;
+                } else {
+                    self.connection().unmap_subwindows(wid)?;
+                    self.connection().unmap_window(wid)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Hide all the windows of a workspace, after sliding them off its screen, to the left or to
+    /// the right, if the `animations` setting is enabled.
+    ///
+    /// The windows are moved a frame at a time by [`State::advance_slide`], between events, so
+    /// that the event loop isn't held up. The animation is skipped when workspaces are switched
+    /// again right after the last one.
+    fn slide_out_workspace(&mut self, workspace_id: WorkspaceId, to_left: bool) -> WmResult {
+        self.finish_slide()?;
+        let duration = self
+            .config
+            .settings
+            .animation_duration
+            .min(MAX_ANIMATION_DURATION);
+        let duration = Duration::from_millis(duration as u64);
+        let recently_animated = self
+            .last_animation
+            .map(|last| last.elapsed() < duration)
+            .unwrap_or(false);
+        if !self.config.settings.animations || duration.is_zero() || recently_animated {
+            return self.hide_workspace(workspace_id);
+        }
+
+        let workspace = self
+            .workspace_with_id(workspace_id)
+            .ok_or_else(|| Error::Generic("No workspace with given id exists".into()))?;
+        let width = workspace.screen().width as i32;
+        let windows: Vec<(u32, Geometry)> = workspace
+            .iter_containers()?
+            .filter_map(|container| match container.data() {
+                ContainerType::InLayout(c) => Some((c.window_id(), c.with_borders().0)),
+                ContainerType::Floating(c) => Some((c.window_id(), c.geometry())),
+                ContainerType::Empty(_) => None,
+            })
+            .collect();
+
+        self.slide = Some(Slide {
+            workspace_id,
+            windows,
+            offset: if to_left { -width } else { width },
+            start: Instant::now(),
+            duration,
+        });
+        self.advance_slide()
+    }
+
+    /// Move the windows of the sliding workspace to where they should be by now, hiding the
+    /// workspace once they're off the screen.
+    pub fn advance_slide(&mut self) -> WmResult {
+        let (frame, frame_duration) = match &self.slide {
+            Some(slide) => (
+                slide.frame(Instant::now()),
+                slide.duration / ANIMATION_FRAMES,
+            ),
+            None => return Ok(()),
+        };
+        if frame >= ANIMATION_FRAMES {
+            return self.finish_slide();
+        }
+
+        if let Some(slide) = &self.slide {
+            let offset = slide.offset * (frame + 1) as i32 / ANIMATION_FRAMES as i32;
+            for (window, geometry) in slide.windows.iter() {
+                self.connection().configure_window(
+                    *window,
+                    &ConfigureWindowAux::new().x(geometry.x as i32 + offset),
+                )?;
+            }
+        }
+        self.wake_after(frame_duration)
+    }
+
+    /// Hide the sliding workspace right away, if there is one.
+    fn finish_slide(&mut self) -> WmResult {
+        let slide = match self.slide.take() {
+            Some(slide) => slide,
+            None => return Ok(()),
+        };
+
+        self.hide_workspace(slide.workspace_id)?;
+        // unmapped windows are shown again right where they are, so they're put back in place
+        if !self.workspace_keeps_mapped(slide.workspace_id) {
+            for (window, geometry) in slide.windows {
+                self.connection()
+                    .configure_window(window, &ConfigureWindowAux::new().x(geometry.x as i32))?;
+            }
+        }
+        self.last_animation = Some(Instant::now());
+
+        Ok(())
+    }
+
+    /// Show all the windows of a workspace, reverting what `hide_workspace` did.
+    fn show_workspace(&self, workspace_id: WorkspaceId) -> WmResult {
+        let keep_mapped = self.workspace_keeps_mapped(workspace_id);
+        let workspace = self
+            .workspace_with_id(workspace_id)
+            .ok_or_else(|| Error::Generic("No workspace with given id exists".into()))?;
+
+        for container in workspace.iter_containers()? {
+            let (wid, position) = match container.data() {
+                ContainerType::InLayout(c) => (c.window_id(), c.with_borders().0),
+                ContainerType::Floating(c) => (c.window_id(), c.geometry()),
+                ContainerType::Empty(_) => continue,
+            };
+
+            if keep_mapped {
+                self.connection().configure_window(
+                    wid,
+                    &ConfigureWindowAux::new()
+                        .x(position.x as i32)
+                        .y(position.y as i32),
+                )?;
+            }
+
+            self.connection().map_window(wid)?;
+            self.connection().map_subwindows(wid)?;
+            if self.fullscreen_windows.contains_key(&wid) {
+                self.connection().configure_window(
+                    wid,
+                    &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+                )?;
+            }
+        }
+
         Ok(())
     }
 
+    /// Given the workspace a new window is about to be placed on and its container type, honor the
+    /// `max_tiled` setting of the workspace.
+    ///
+    /// If the workspace can't take another tiled window, the window is either made floating, or
+    /// placed on the next workspace which can still tile it, depending on the `overflow` setting.
+    fn overflow_target(&self, workspace_id: WorkspaceId, container_type: u8) -> (WorkspaceId, u8) {
+        if container_type != ContainerTypeMask::TILING {
+            return (workspace_id, container_type);
+        }
+
+        let can_tile = |id: WorkspaceId| {
+            let max_tiled = self
+                .config
+                .workspace_settings
+                .get(id)
+                .map(|settings| settings.max_tiled)
+                .unwrap_or(0);
+            self.workspace_with_id(id)
+                .map(|ws| ws.can_tile(max_tiled))
+                .unwrap_or(false)
+        };
+
+        if can_tile(workspace_id) {
+            return (workspace_id, container_type);
+        }
+
+        let overflow_to_next = self
+            .config
+            .workspace_settings
+            .get(workspace_id)
+            .map(|settings| settings.overflow == "next_workspace")
+            .unwrap_or(false);
+
+        if overflow_to_next {
+            if let Some(position) = self.workspaces.iter().position(|ws| ws.id == workspace_id) {
+                let len = self.workspaces.len();
+                for offset in 1..len {
+                    let workspace = &self.workspaces[(position + offset) % len];
+                    if !workspace.is_dnd() && can_tile(workspace.id) {
+                        return (workspace.id, container_type);
+                    }
+                }
+            }
+        }
+
+        (workspace_id, ContainerTypeMask::FLOATING)
+    }
+
+    /// Should the windows of a workspace stay mapped while the workspace is not shown?
+    fn workspace_keeps_mapped(&self, workspace_id: WorkspaceId) -> bool {
+        self.config
+            .workspace_settings
+            .get(workspace_id)
+            .map(|settings| settings.keep_mapped)
+            .unwrap_or(false)
+    }
+
     /// If there is a focused monitor, return a reference to it, otherwsie return a reference
     /// to the first monitor.
     fn get_focused_or_first_monitor(&self) -> WmResult<&Monitor> {
@@ -748,7 +1874,7 @@ impl State {
 
     /// Retrun an immutable reference to the monitor which the workspace with the given id is
     /// currently on.
-    fn _monitor_for_workspace(&self, workspace_id: WorkspaceId) -> WmResult<&Monitor> {
+    fn monitor_for_workspace(&self, workspace_id: WorkspaceId) -> WmResult<&Monitor> {
         for monitor in self.monitors.iter() {
             if monitor.contains(&workspace_id) {
                 return Ok(monitor);
@@ -786,7 +1912,20 @@ impl State {
 
     /// Let a window be managed by the window manager.
     pub fn manage_window(&mut self, window: u32) -> WmResult {
-        if self.bar_windows.contains(&window) {
+        if self.bar_windows.lock().unwrap().contains(&window) {
+            return Ok(());
+        }
+
+        // docks aren't tiled, they only take their space away from the workspaces
+        if let Some(strut) = self.window_strut(window) {
+            logm!(LL_NORMAL, "Window {window} is a dock reserving {strut:?}.");
+            self.struts.retain(|(dock, _)| *dock != window);
+            self.struts.push((window, strut));
+            self.connection().map_window(window)?;
+            let monitor_ids: Vec<u32> = self.monitors.iter().map(|m| m.id()).collect();
+            for monitor_id in monitor_ids {
+                self.fit_workspaces(monitor_id)?;
+            }
             return Ok(());
         }
 
@@ -794,14 +1933,13 @@ impl State {
         let connection = self.connection();
         let geometry = self.connection().get_geometry(window)?.reply()?;
         let new_client_id = self.new_client_id();
-        let default_colormap = self.default_colormap();
 
-        let workspace = self.get_workspace_under_cursor_mut()?;
-        let id = workspace.id;
-        let workspace_container_type = workspace
-            .container_type(&config)
-            .unwrap_or(ContainerTypeMask::TILING);
-        self.focus_workspace(id, false)?;
+        // windows on monitors which aren't managed are shown, but left alone otherwise
+        if self.is_on_unmanaged_monitor(&Geometry::from(geometry)) {
+            logm!(LL_NORMAL, "Window {window} is on an unmanaged monitor.");
+            self.connection().map_window(window)?;
+            return Ok(());
+        }
 
         let pid_reply_result =
             self.atoms
@@ -810,25 +1948,115 @@ impl State {
                 .get_property(window, connection.clone(), None);
 
         // Make sure that a client is created everytime, even if getting the PID information fails.
-        let mut with_pid = false;
+        let mut process_id = None;
 
         if let Ok(pid_reply) = pid_reply_result {
             if let Some(return_value) = pid_reply.first() {
                 if let Ok(pid) = return_value.clone().try_into() {
-                    self.get_focused_workspace_mut()?.insert_client(
-                        Client::new(window, pid, geometry, new_client_id, &config),
-                        workspace_container_type,
-                    );
-                    with_pid = true;
+                    process_id = Some(pid);
                 }
             }
         }
-        if !with_pid {
-            self.get_focused_workspace_mut()?.insert_client(
-                Client::new_without_process_id(window, geometry, new_client_id, &config),
-                workspace_container_type,
-            );
+        // the first window of a process started by an action, such as `execute_on`
+        let spawn_target = process_id.and_then(|pid| {
+            let process_ids = std::iter::successors(Some(pid), |pid| parent_process_id(*pid));
+            self.pending_spawns.take(process_ids, Instant::now())
+        });
+
+        let class = self.window_class(window);
+        let mut rule = self.rule_effects(window);
+        let mut id = match self.get_workspace_under_cursor_mut() {
+            Ok(workspace) => workspace.id,
+            // the cursor can be on a monitor which isn't managed
+            Err(_) => self.get_focused_workspace()?.id,
+        };
+        // the geometry only fits the monitor of its workspace, so the window isn't sent anywhere
+        // else by the settings and rules below
+        let pinned = match spawn_target {
+            Some(SpawnTarget::Floating(workspace_id, geometry)) => {
+                rule.geometry = Some(geometry);
+                rule.float = Some(true);
+                id = workspace_id;
+                true
+            }
+            _ => false,
+        };
+        if config.settings.remember_workspace && !pinned {
+            if let Some(last_id) = class.as_ref().and_then(|c| self.last_workspaces.get(c)) {
+                if self
+                    .workspace_with_id(*last_id)
+                    .map(|ws| !ws.is_dnd())
+                    .unwrap_or(false)
+                {
+                    id = *last_id;
+                }
+            }
+        }
+        if let Some(rule_id) = rule.workspace.filter(|_| !pinned) {
+            if self
+                .workspace_with_id(rule_id)
+                .map(|ws| !ws.is_dnd())
+                .unwrap_or(false)
+            {
+                id = rule_id;
+            }
+        }
+        let workspace_container_type = match rule.floating() {
+            Some(true) => ContainerTypeMask::FLOATING,
+            Some(false) => ContainerTypeMask::TILING,
+            None => self
+                .workspace_with_id(id)
+                .ok_or_else(|| Error::Generic(format!("no workspace with id {id} found")))?
+                .container_type(&config)
+                .unwrap_or(ContainerTypeMask::TILING),
+        };
+        let (id, workspace_container_type) = if pinned {
+            (id, workspace_container_type)
+        } else {
+            self.overflow_target(id, workspace_container_type)
+        };
+        if let Some(class) = class {
+            self.last_workspaces.insert(class, id);
+        }
+        // a window which doesn't take the focus is opened on the focused workspace, and then sent
+        // to its workspace in the background
+        if rule.takes_focus() {
+            self.focus_workspace(id, false)?;
+        }
+        let opened_on = self.get_focused_workspace()?.id;
+
+        let mut client = match process_id {
+            Some(pid) => Client::new(window, pid, geometry, new_client_id, &config),
+            None => Client::new_without_process_id(window, geometry, new_client_id, &config),
+        };
+        if !self.gaps_enabled {
+            client.attributes = client.attributes.without_gaps();
+        }
+        client.size_hints = self.size_hints(window);
+        if workspace_container_type == ContainerTypeMask::FLOATING {
+            if let Some(rule_geometry) = rule.geometry {
+                client.geometry = rule_geometry;
+            } else {
+                // the window asked for the size of its contents, without the borders
+                let borders = (2 * client.border_width()).min(u16::MAX as u32) as u16;
+                client.geometry.width = client.geometry.width.saturating_add(borders);
+                client.geometry.height = client.geometry.height.saturating_add(borders);
+                client.geometry = client.with_size_hints(false);
+            }
+            client.draw_borders(self.connection(), self.default_colormap())?;
         }
+        atoms::set_frame_extents(
+            self.connection(),
+            window,
+            self.atoms.get("_NET_FRAME_EXTENTS").unwrap().id(),
+            client.border_width(),
+        )?;
+        self.get_focused_workspace_mut()?.insert_new_client(
+            client,
+            workspace_container_type,
+            &config,
+            rule.takes_focus(),
+        );
 
         let old_event_mask = self
             .connection()
@@ -869,548 +2097,2501 @@ impl State {
             ButtonIndex::M3,
             self.floating_modifier,
         )?;
+        self.grab_click_to_focus(window)?;
+
+        if let Some(pid) = process_id.filter(|_| config.settings.swallow) {
+            self.swallow(window, pid)?;
+        }
 
         self.connection()
             .reparent_window(window, self.root_window(), 0, 0)?;
 
         self.connection().map_window(window)?;
-        self.get_focused_workspace_mut()?
-            .apply_layout(connection, None, default_colormap)?;
+        self.layout_workspace(opened_on)?;
+
+        // honor the stacking states the window requested before being mapped
+        let states = self.net_wm_state(window)?;
+        let above = self.atoms.get("_NET_WM_STATE_ABOVE").unwrap().id();
+        let below = self.atoms.get("_NET_WM_STATE_BELOW").unwrap().id();
+        let sticky = self.atoms.get("_NET_WM_STATE_STICKY").unwrap().id();
+        if states.contains(&above) {
+            self.set_window_above(window, true)?;
+        } else if states.contains(&below) {
+            self.set_window_below(window, true)?;
+        }
+        if states.contains(&sticky) {
+            self.set_window_sticky(window, true)?;
+        }
+        self.restack()?;
+        self.update_client_list()?;
 
-        self.connection()
-            .set_input_focus(InputFocus::PARENT, window, CURRENT_TIME)?;
-        self.get_focused_workspace_mut()?
-            .focus
-            .set_focused_client(window);
+        if rule.takes_focus() {
+            self.connection()
+                .set_input_focus(InputFocus::PARENT, window, CURRENT_TIME)?;
+        }
+
+        let fullscreen = self.atoms.get("_NET_WM_STATE_FULLSCREEN").unwrap().id();
+        if states.contains(&fullscreen) {
+            self.set_window_fullscreen(window, true)?;
+        }
+
+        if !rule.takes_focus() {
+            self.send_to_workspace(window, id)?;
+        }
+
+        // windows of commands started by `execute_on` go onto their workspace in the background
+        if let Some(SpawnTarget::Workspace(target)) = spawn_target {
+            self.send_to_workspace(window, target)?;
+        }
 
         Ok(())
     }
 
-    /// This method is called when a window is destroyed.
-    ///
-    /// First, start by finding the window than remove it and apply the correct geometries to the
-    /// rest of the windows in the workspace.
-    pub fn unmanage_window(&mut self, window: u32) -> WmResult {
-        let connection = self.connection();
-        let default_colormap = self.default_colormap();
+    /// Is the middle of the geometry on one of the monitors which aren't managed?
+    fn is_on_unmanaged_monitor(&self, geometry: &Geometry) -> bool {
+        let (x, y) = geometry.center();
+        let on = |monitors: &[Monitor]| monitors.iter().any(|m| m.size().contains_point(x, y));
 
-        let workspace_option = self.workspace_for_window_mut(window);
-        let mut workspace_id = None;
+        on(&self.unmanaged_monitors) && !on(&self.monitors)
+    }
 
-        if let Some(workspace) = workspace_option {
-            workspace.remove_window(window)?;
-            let _ = workspace_id.insert(workspace.id);
+    /// Move a window onto another workspace, keeping the focus where it is.
+    fn send_to_workspace(&mut self, window: u32, workspace_id: WorkspaceId) -> WmResult {
+        let current = self.workspace_for_window(window).map(|w| w.id);
+        if current == Some(workspace_id) || self.workspace_with_id(workspace_id).is_none() {
+            return Ok(());
         }
 
-        // set input focus to previously focused client
-        if let Some(previous_window_id) = self
-            .get_focused_workspace_mut()?
-            .focus
-            .previously_focused_client()
-        {
-            self.connection().set_input_focus(
-                InputFocus::NONE,
-                previous_window_id,
-                CURRENT_TIME,
-            )?;
-            self.get_focused_workspace_mut()?
-                .focus
-                .remove_client(window);
+        self.move_window(window, workspace_id)?;
+        let workspace = self.get_focused_workspace_mut()?;
+        workspace.focus.remove_client(window);
+        if let Some(focused) = workspace.focus.focused_client() {
+            self.connection()
+                .set_input_focus(InputFocus::PARENT, focused, CURRENT_TIME)?;
         }
-        if let Some(workspace_id) = workspace_id {
-            if let Some(workspace) = self.workspace_with_id_mut(workspace_id) {
-                workspace.apply_layout(connection, None, default_colormap)?
+
+        Ok(())
+    }
+
+    /// Let a new window swallow a window belonging to one of its ancestor processes.
+    ///
+    /// The first tiled window of the new window's ancestor processes in the same workspace is
+    /// replaced in the layout by the new window and hidden, until the new window is destroyed.
+    fn swallow(&mut self, window: u32, process_id: u32) -> WmResult {
+        let connection = self.connection();
+        let workspace = self.get_focused_workspace_mut()?;
+        let new_id = *workspace.find_by_window_id(window)?.id();
+
+        let mut ancestor = parent_process_id(process_id);
+        while let Some(pid) = ancestor {
+            if let Ok(container) = workspace.find_by_process_id(pid) {
+                if let ContainerType::InLayout(client) = container.data() {
+                    let swallowed_window = client.window_id();
+                    let swallowed_id = *container.id();
+
+                    workspace.swap(new_id, swallowed_id)?;
+                    let swallowed = workspace.remove_and_return_window(swallowed_window)?;
+                    workspace.focus.remove_client(swallowed_window);
+                    connection.unmap_window(swallowed_window)?;
+
+                    logm!(
+                        LL_FULL,
+                        "Window {window} swallowed window {swallowed_window}",
+                    );
+                    self.swallowed_windows.push((window, swallowed));
+                    return Ok(());
+                }
             }
+            ancestor = parent_process_id(pid);
         }
 
         Ok(())
     }
 
-    /// Handle an enter window event.
+    /// Put a swallowed window back in the place of the window which swallowed it.
+    fn restore_swallowed(&mut self, swallower: u32, container: Container) -> WmResult {
+        let new_windows_first = self.config.settings.new_windows_first();
+        let connection = self.connection();
+
+        if let Some(window) = container.data().window_id() {
+            if let Some(workspace) = self.workspace_for_window_mut(swallower) {
+                let swallower_id = *workspace.find_by_window_id(swallower)?.id();
+                let restored_id = workspace.insert_container(container, new_windows_first)?;
+                workspace.swap(swallower_id, restored_id)?;
+                workspace.focus.set_focused_client(window);
+                connection.map_window(window)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// This method is called when a window is destroyed.
     ///
-    /// This method is responsible for switching input focus to the newly entered window.
-    /// In the future, this will also handle the decorators, WM properties and other necessary
-    /// things.
-    pub fn handle_enter_event(&mut self, window: u32) -> WmResult {
-        if window == self.root_window() {
-            return Ok(());
+    /// First, start by finding the window than remove it and apply the correct geometries to the
+    /// rest of the windows in the workspace.
+    pub fn unmanage_window(&mut self, window: u32) -> WmResult {
+        self.above_windows.retain(|w| *w != window);
+        self.below_windows.retain(|w| *w != window);
+        self.sticky_windows.retain(|w| *w != window);
+        self.pinned_windows.retain(|(w, _)| *w != window);
+        self.focus_history.remove_client(window);
+        self.swallowed_windows
+            .retain(|(_, container)| container.data().window_id() != Some(window));
+        if let Some(index) = self
+            .swallowed_windows
+            .iter()
+            .position(|(swallower, _)| *swallower == window)
+        {
+            let (_, container) = self.swallowed_windows.remove(index);
+            self.restore_swallowed(window, container)?;
         }
-        let workspace = self.workspace_for_window(window).ok_or_else(|| {
-            Error::Generic(format!("The window {window} is not in any workspace!"))
-        })?;
 
-        let cont = workspace.find_by_window_id(window)?;
+        let workspace_option = self.workspace_for_window_mut(window);
+        let mut workspace_id = None;
 
-        if cont.is_floating() {
-            self.connection().configure_window(
-                window,
-                &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
-            )?;
+        if let Some(workspace) = workspace_option {
+            workspace.remove_window(window)?;
+            let _ = workspace_id.insert(workspace.id);
         }
+        self.update_client_list()?;
 
-        self.focus_workspace(workspace.id, false)?;
-        self.get_focused_workspace_mut()?
+        // set input focus to previously focused client
+        if let Some(previous_window_id) = self
+            .get_focused_workspace_mut()?
             .focus
-            .set_focused_client(window);
+            .previously_focused_client()
+        {
+            self.connection().set_input_focus(
+                InputFocus::NONE,
+                previous_window_id,
+                CURRENT_TIME,
+            )?;
+            self.get_focused_workspace_mut()?
+                .focus
+                .remove_client(window);
+        }
+        if let Some(workspace_id) = workspace_id {
+            self.layout_workspace(workspace_id)?;
+        }
 
-        self.connection()
-            .set_input_focus(InputFocus::NONE, window, CURRENT_TIME)?;
+        if self.fullscreen_windows.remove(&window).is_some() {
+            self.restack_bars()?;
+        }
+        // a closed fullscreen window returns the focus to the workspace it came from
+        if let Some(origin) = self.fullscreen_origins.remove(&window) {
+            if workspace_id.is_some()
+                && workspace_id == self.focused_workspace
+                && self.workspace_with_id(origin).is_some()
+            {
+                self.focus_workspace(origin, true)?;
+            }
+        }
 
         Ok(())
     }
 
-    /// Handle a key press event.
-    pub fn handle_key_press(&mut self, ev: &KeyPressEvent) -> WmResult {
-        let action_option = self.key_manager.on_key_press(ev)?;
-        if let Some(action) = action_option {
-            self.do_action(action)?
+    /// Apply the layout of a workspace, or, with `coalesce_layouts` enabled, wait until all the
+    /// pending events are handled and apply it then, by `flush_pending_layouts`.
+    fn layout_workspace(&mut self, workspace_id: WorkspaceId) -> WmResult {
+        // the workspace might have just become empty, or stopped being empty
+        self.update_backgrounds()?;
+        if self.config.settings.coalesce_layouts {
+            if !self.pending_layouts.contains(&workspace_id) {
+                self.pending_layouts.push(workspace_id);
+            }
+            return Ok(());
         }
 
-        Ok(())
-    }
+        let connection = self.connection();
+        let default_colormap = self.default_colormap();
+        if let Some(workspace) = self.workspace_with_id_mut(workspace_id) {
+            workspace.apply_layout(connection, None, default_colormap)?;
+        }
 
-    /// Handle a key release event.
-    pub fn handle_key_release(&mut self, ev: &KeyReleaseEvent) -> WmResult {
-        self.key_manager.on_key_release(ev)?;
         Ok(())
     }
 
-    /// Handle a button press event.
-    ///
-    /// We check which button on the mouse was pressed, if it was the left button(ev.detail = 1), we know that the
-    /// user wants to move this client around, we set the `is_dragging` filed to true. If, on the
-    /// other hand, the right button(ev.detail = 3) was pressed, we know the user wants to resize
-    /// the window and we set the `is_resizing` flag to to true.
-    pub fn handle_button_press(
-        &mut self,
-        ev: &x11rb::protocol::xproto::ButtonPressEvent,
-    ) -> WmResult {
-        let workspace = self.workspace_for_window_mut(ev.event).ok_or_else(|| {
-            Error::Generic(format!(
-                "workspace error: unable to find workspace for window id {}",
-                ev.event
-            ))
-        })?;
-
-        let container = workspace.find_by_window_id_mut(ev.event)?;
+    /// Apply the layouts of the workspaces which were changed while handling the last batch of
+    /// events.
+    pub fn flush_pending_layouts(&mut self) -> WmResult {
+        let connection = self.connection();
+        let default_colormap = self.default_colormap();
 
-        if !container.is_floating() {
-            return Ok(());
-        } else {
-            container.change_last_position((ev.root_x, ev.root_y));
-            match ev.detail {
-                1 => self.is_dragging = true,
-                3 => self.is_resizing = true,
-                _ => (),
-            };
+        for workspace_id in std::mem::take(&mut self.pending_layouts) {
+            if let Some(workspace) = self.workspace_with_id_mut(workspace_id) {
+                workspace.apply_layout(connection.clone(), None, default_colormap)?;
+            }
         }
 
         Ok(())
     }
 
-    /// Handle a button release event.
+    /// Handle a client message event.
     ///
-    /// Very similar to button press, we check the `ev.detail` field of the `ButtonReleaseEvent`,
-    /// and either finish the dragging process, updating the window's position for the final time,
-    /// or we finish the resizing process, updating the window's width and height for the final
-    /// time.
-    pub fn handle_button_release(
-        &mut self,
-        ev: &x11rb::protocol::xproto::ButtonReleaseEvent,
-    ) -> WmResult {
-        let default_colormap = self.default_colormap();
-        let connection = self.connection();
-        let workspace = self.workspace_for_window_mut(ev.event).ok_or_else(|| {
-            Error::Generic(format!(
-                "workspace error: unable to find workspace for window id {}",
-                ev.event
-            ))
-        })?;
+    /// Currently, only `_NET_SYSTEM_TRAY_OPCODE` messages asking the system tray to dock an icon
+    /// and `_NET_WM_STATE` messages requesting the `_NET_WM_STATE_ABOVE`, `_NET_WM_STATE_BELOW`,
+    /// `_NET_WM_STATE_STICKY` and `_NET_WM_STATE_FULLSCREEN` states are handled.
+    pub fn handle_client_message(&mut self, ev: &ClientMessageEvent) -> WmResult {
+        let opcode = self.atoms.get("_NET_SYSTEM_TRAY_OPCODE").unwrap().id();
+        if ev.type_ == opcode && ev.format == 32 && Some(ev.window) == self.tray_window {
+            let data = ev.data.as_data32();
+            if data[1] == SYSTEM_TRAY_REQUEST_DOCK {
+                self.dock_tray_icon(data[2])?;
+            }
+            return Ok(());
+        }
 
-        let container = workspace.find_by_window_id_mut(ev.event)?;
+        // pagers switch workspaces by the index of the desktop
+        if ev.type_ == self.atoms.get("_NET_CURRENT_DESKTOP").unwrap().id() && ev.format == 32 {
+            let index = ev.data.as_data32()[0] as usize;
+            let workspace_id = self
+                .workspaces
+                .get(index)
+                .map(|workspace| workspace.id)
+                .ok_or_else(|| {
+                    Error::Generic(format!(
+                        "workspace error: there is no desktop with the index {index}."
+                    ))
+                })?;
+            return self.focus_workspace(workspace_id, true);
+        }
 
-        if !container.is_floating() {
+        if ev.type_ != self.atoms.get("_NET_WM_STATE").unwrap().id() || ev.format != 32 {
             return Ok(());
-        } else {
-            match ev.detail {
-                1 => {
-                    let last_event_position = container.last_position().unwrap();
-                    let diff = (
-                        last_event_position.0 as i16 - ev.root_x,
-                        last_event_position.1 as i16 - ev.root_y,
-                    );
-                    if let crate::wm::container::ContainerType::Floating(c) = container.data_mut() {
-                        c.geometry.x -= diff.0;
-                        c.geometry.y -= diff.1;
-                        c.draw_borders(connection, default_colormap)?;
-                    }
-                    self.is_dragging = false
+        }
+
+        if self.workspace_for_window(ev.window).is_none() {
+            return Ok(());
+        }
+
+        let data = ev.data.as_data32();
+        let above = self.atoms.get("_NET_WM_STATE_ABOVE").unwrap().id();
+        let below = self.atoms.get("_NET_WM_STATE_BELOW").unwrap().id();
+        let sticky = self.atoms.get("_NET_WM_STATE_STICKY").unwrap().id();
+        let fullscreen = self.atoms.get("_NET_WM_STATE_FULLSCREEN").unwrap().id();
+
+        // whether the state should be enabled, given if it's currently enabled
+        let enable = |enabled: bool| match data[0] {
+            NET_WM_STATE_REMOVE => Some(false),
+            NET_WM_STATE_ADD => Some(true),
+            NET_WM_STATE_TOGGLE => Some(!enabled),
+            _ => None,
+        };
+
+        for property in [data[1], data[2]] {
+            if property == above {
+                if let Some(e) = enable(self.above_windows.contains(&ev.window)) {
+                    self.set_window_above(ev.window, e)?;
                 }
-                3 => {
-                    let last_event_position = container.last_position().unwrap();
-                    let diff = (
-                        last_event_position.0 as i16 - ev.root_x,
-                        last_event_position.1 as i16 - ev.root_y,
-                    );
-                    let geom = container.data().geometry();
-                    let (w, h) = (geom.width as i16 - diff.0, geom.height as i16 - diff.1);
-                    if (w as u16) < MIN_WIDTH || (h as u16) < MIN_HEIGHT {
-                        self.is_resizing = false;
-                        return Ok(());
-                    }
-                    if let crate::wm::container::ContainerType::Floating(c) = container.data_mut() {
-                        c.geometry.width = w as u16;
-                        c.geometry.height = h as u16;
-                        c.draw_borders(connection, default_colormap)?;
-                    }
-                    self.is_resizing = false
+            } else if property == below {
+                if let Some(e) = enable(self.below_windows.contains(&ev.window)) {
+                    self.set_window_below(ev.window, e)?;
+                }
+            } else if property == sticky {
+                if let Some(e) = enable(self.sticky_windows.contains(&ev.window)) {
+                    self.set_window_sticky(ev.window, e)?;
+                }
+            } else if property == fullscreen {
+                if let Some(e) = enable(self.fullscreen_windows.contains_key(&ev.window)) {
+                    self.set_window_fullscreen(ev.window, e)?;
                 }
-
-                _ => (),
             }
         }
+
+        self.restack()?;
+        self.connection().flush()?;
+
         Ok(())
     }
 
-    /// Handle a motion notify event.
-    ///
-    /// After checking which flag is active, we either move or resize the window.
-    pub fn handle_motion_notify(
-        &mut self,
-        ev: &x11rb::protocol::xproto::MotionNotifyEvent,
-    ) -> WmResult {
-        let default_colormap = self.default_colormap();
+    /// Float a window if it is currently tiled.
+    fn float_window(&mut self, window: u32) -> WmResult {
         let connection = self.connection();
-        let dragging = self.is_dragging;
-        let resizing = self.is_resizing;
-        let workspace = self.workspace_for_window_mut(ev.event).ok_or_else(|| {
-            Error::Generic(format!(
-                "workspace error: unable to find workspace for window id {}",
-                ev.event
-            ))
-        })?;
-
-        let container = workspace.find_by_window_id_mut(ev.event)?;
-
-        if !container.is_floating() {
-            return Ok(());
-        } else if dragging {
-            let last_event_position = container.last_position().unwrap();
-            let diff = (
-                (last_event_position.0 as i16 - ev.root_x) as f32 * DRAG_SPEED_COEFFICIENT,
-                (last_event_position.1 as i16 - ev.root_y) as f32 * DRAG_SPEED_COEFFICIENT,
-            );
-            if let crate::wm::container::ContainerType::Floating(c) = container.data_mut() {
-                c.geometry.x -= diff.0 as i16;
-                c.geometry.y -= diff.1 as i16;
-
-                c.draw_borders(connection, default_colormap)?;
-            }
-            container.change_last_position((ev.root_x, ev.root_y))
-        } else if resizing {
-            let last_event_position = container.last_position().unwrap();
-            let diff = (
-                last_event_position.0 as i16 - ev.root_x,
-                last_event_position.1 as i16 - ev.root_y,
-            );
-            let geom = container.data().geometry();
-            let (w, h) = (geom.width as i16 - diff.0, geom.height as i16 - diff.1);
-            if (w as u16) < MIN_WIDTH || (h as u16) < MIN_HEIGHT {
-                return Ok(());
-            }
-            if let crate::wm::container::ContainerType::Floating(c) = container.data_mut() {
-                c.geometry.width = w as u16;
-                c.geometry.height = h as u16;
-                c.draw_borders(connection, default_colormap)?;
+        let default_colormap = self.default_colormap();
+        if let Some(workspace) = self.workspace_for_window_mut(window) {
+            if workspace.find_by_window_id(window)?.is_in_layout() {
+                workspace.float_window(window)?;
+                workspace.apply_layout(connection, None, default_colormap)?;
             }
-            container.change_last_position((ev.root_x - diff.0, ev.root_y - diff.1));
         }
 
         Ok(())
     }
 
-    /// Handle a focus in event.
-    pub fn handle_focus_in(&mut self, ev: &FocusInEvent) -> WmResult {
-        logm!(LL_NORMAL, "focus in in {}", ev.event);
-        let connection = self.connection();
-        if let Some(workspace) = self.workspace_for_window(ev.event) {
-            if let Some(focused) = workspace.focus.focused_client() {
-                connection.set_input_focus(InputFocus::PARENT, focused, CURRENT_TIME)?;
-            }
+    /// Keep a window above all the other windows, or stop doing so.
+    ///
+    /// Tiled windows are floated when they are put above the other windows.
+    fn set_window_above(&mut self, window: u32, enable: bool) -> WmResult {
+        self.above_windows.retain(|w| *w != window);
+        if enable {
+            self.below_windows.retain(|w| *w != window);
+            self.above_windows.push(window);
+            self.float_window(window)?;
         }
 
-        Ok(())
+        self.update_net_wm_state(window)
     }
 
-    /// Handle the execution of a given action.
-    fn do_action(&mut self, action: Action) -> WmResult {
-        match action {
-            Action::Noop => {}
-            Action::Kill => self.action_kill()?,
-            Action::Goto(workspace) => self.action_goto(workspace as u32)?,
-            Action::Move(workspace) => self.action_move(workspace as u32)?,
-            Action::Execute(command) => self.action_execute(command)?,
-            Action::Focus(direction) => self.action_focus(direction)?,
-            Action::ChangeLayout(layout) => self.action_change_layout(layout)?,
-            Action::CycleLayout => self.action_cycle_layout()?,
-            Action::ToggleFloat => self.action_toggle_float()?,
-            Action::Swap(direction) => self.action_swap(direction)?,
-            Action::ReloadConfig => self.action_reload_config()?,
+    /// Make a window cover its whole monitor, or restore it to where it was before.
+    ///
+    /// Windows matched by the `fullscreen_workspace_classes` setting are also moved onto the
+    /// `fullscreen_workspace` and back.
+    fn set_window_fullscreen(&mut self, window: u32, enable: bool) -> WmResult {
+        if self.workspace_for_window(window).is_none()
+            || enable == self.fullscreen_windows.contains_key(&window)
+        {
+            return Ok(());
         }
 
-        Ok(())
+        if enable {
+            self.move_to_fullscreen_workspace(window, true)?;
+            self.cover_monitor(window)?;
+        } else {
+            self.uncover_monitor(window)?;
+            self.move_to_fullscreen_workspace(window, false)?;
+        }
+        self.restack()?;
+
+        self.update_net_wm_state(window)
     }
 
-    /// Execute a command.
-    fn action_execute(&mut self, command: String) -> WmResult {
-        // TODO: get rid of this on release
-        #[cfg(debug_assertions)]
-        let _ = std::process::Command::new("/bin/bash")
-            .env("DISPLAY", ":1")
-            .arg("-c")
-            .args(
-                command
-                    .split(' ')
-                    .map(|m| m.to_string())
-                    .collect::<Vec<String>>(),
-            )
-            .spawn()?;
-
-        #[cfg(not(debug_assertions))]
-        let _ = std::process::Command::new("/bin/bash")
-            .arg("-c")
-            .args(
-                command
-                    .split(' ')
-                    .map(|m| m.to_string())
-                    .collect::<Vec<String>>(),
-            )
-            .spawn()?;
+    /// Toggle the fullscreen state of the focused window.
+    fn action_toggle_fullscreen(&mut self) -> WmResult {
+        if let Some(window) = self.get_focused_workspace()?.focus.focused_client() {
+            let enable = !self.fullscreen_windows.contains_key(&window);
+            self.set_window_fullscreen(window, enable)?;
+            self.connection().flush()?;
+        }
 
         Ok(())
     }
 
-    /// Attempt to kill a client.
-    fn action_kill(&mut self) -> WmResult {
-        if let Some(window) = self.get_focused_workspace_mut()?.focus.focused_client() {
-            if let Some(protocols_atom) = self.atoms.get("WM_PROTOCOLS") {
-                let return_values = protocols_atom.get_property(window, self.connection(), None)?;
-                if let Some(delete_atom) = self.atoms.get("WM_DELETE_WINDOW") {
-                    let atom = delete_atom.id();
-                    for return_value in return_values.iter() {
-                        if let PropertyReturnValue::Number(x) = return_value {
-                            if atom == *x {
-                                let c = self.connection();
-                                atoms::send_client_message(
-                                    c,
-                                    window,
-                                    atom,
-                                    32,
-                                    &atom.to_be_bytes(),
-                                )?;
-                                self.connection.flush()?;
-                                logm!(
-                                    LL_NORMAL,
-                                    "Killed window {window} using WM_DELETE_WINDOW message.",
-                                );
-                                return Ok(());
-                            }
-                        }
-                    }
+    /// Resize a window to cover its whole monitor, without borders, taking it out of the layout
+    /// for the time being.
+    fn cover_monitor(&mut self, window: u32) -> WmResult {
+        let connection = self.connection();
+        let workspace = self
+            .workspace_for_window(window)
+            .ok_or_else(|| Error::Generic(format!("no workspace contains window {window}")))?;
+        let monitor = workspace.monitor;
+        let workspace_id = workspace.id;
+        let screen = self
+            .monitors
+            .iter()
+            .find(|m| m.id() == monitor)
+            .map(|m| m.size())
+            .unwrap_or_else(|| workspace.screen());
+
+        let workspace = self.workspace_with_id_mut(workspace_id).unwrap();
+        let container = workspace.find_by_window_id(window)?;
+        let saved = SavedGeometry {
+            geometry: container.data().geometry(),
+            tiled: container.is_in_layout(),
+        };
+        if saved.tiled {
+            workspace.float_window(window)?;
+        }
+        workspace
+            .find_by_window_id_mut(window)?
+            .data_mut()
+            .set_geometry(screen);
+        self.fullscreen_windows.insert(window, saved);
+
+        connection.configure_window(
+            window,
+            &ConfigureWindowAux::from(screen).stack_mode(StackMode::ABOVE),
+        )?;
+        self.layout_workspace(workspace_id)
+    }
+
+    /// Put a fullscreen window back to where it was, tiling it again, if it was tiled before.
+    fn uncover_monitor(&mut self, window: u32) -> WmResult {
+        let connection = self.connection();
+        let default_colormap = self.default_colormap();
+        let keep_tiled_slot = self.config.settings.keep_tiled_slot;
+        let saved = match self.fullscreen_windows.remove(&window) {
+            Some(saved) => saved,
+            None => return Ok(()),
+        };
+        let workspace = match self.workspace_for_window_mut(window) {
+            Some(workspace) => workspace,
+            None => return Ok(()),
+        };
+        let workspace_id = workspace.id;
+
+        let container = workspace.find_by_window_id_mut(window)?;
+        container.data_mut().set_geometry(saved.geometry);
+        if saved.tiled {
+            workspace.tile_window(window, keep_tiled_slot)?;
+        } else if let ContainerType::Floating(c) = container.data() {
+            c.draw_borders(connection, default_colormap)?;
+        }
+
+        self.layout_workspace(workspace_id)
+    }
+
+    /// Move a window entering the fullscreen state onto the `fullscreen_workspace` and focus it
+    /// there, or move it back onto the workspace it came from when it leaves the fullscreen state.
+    ///
+    /// Windows which aren't matched by the `fullscreen_workspace_classes` setting are left alone.
+    fn move_to_fullscreen_workspace(&mut self, window: u32, enable: bool) -> WmResult {
+        let current = match self.workspace_for_window(window) {
+            Some(workspace) => workspace.id,
+            None => return Ok(()),
+        };
+
+        let target = if enable {
+            if self.fullscreen_origins.contains_key(&window) {
+                return Ok(());
+            }
+            let class = self.window_class(window);
+            let target = match self
+                .config
+                .settings
+                .fullscreen_workspace_for(class.as_deref())
+            {
+                Some(target) => target,
+                None => return Ok(()),
+            };
+            let target_dnd = match self.workspace_with_id(target) {
+                Some(workspace) => workspace.is_dnd(),
+                None => {
+                    return Err(
+                        format!("fullscreen error: no workspace with id {target} found").into(),
+                    )
                 }
-            } else {
-                let workspace = self.workspace_for_window(window).unwrap();
-                if let Ok(cont) = workspace.find_by_window_id(window) {
-                    if let Some(pid) = cont.data().process_id() {
-                        std::process::Command::new("/bin/sh")
-                            .args(vec!["-c", "kill", &format!("{pid}")])
-                            .spawn()?;
-                        logm!(LL_NORMAL, "Killed window {window} using _NET_WM_PID.",);
-                        return Ok(());
-                    };
-                };
+            };
+            if target == current || target_dnd {
+                return Ok(());
+            }
+
+            self.fullscreen_origins.insert(window, current);
+            target
+        } else {
+            match self.fullscreen_origins.remove(&window) {
+                Some(origin) if self.workspace_with_id(origin).is_some() => origin,
+                _ => return Ok(()),
             }
-            return Err(format!("Unable to kill window {window}").into());
+        };
+
+        self.move_window(window, target)?;
+        self.focus_workspace(target, false)?;
+        let size = self
+            .get_focused_workspace()?
+            .find_by_window_id(window)?
+            .data()
+            .geometry();
+        self.focus_window(window, size)
+    }
+
+    /// Keep a window below all the other windows, or stop doing so.
+    fn set_window_below(&mut self, window: u32, enable: bool) -> WmResult {
+        self.below_windows.retain(|w| *w != window);
+        if enable {
+            self.above_windows.retain(|w| *w != window);
+            self.below_windows.push(window);
         }
 
-        Err("Unable to kill window. Window not found!".into())
+        self.update_net_wm_state(window)
     }
 
-    /// Focus a window given a direction.
-    fn action_focus(&mut self, direction: Direction) -> WmResult {
+    /// Show a window on all workspaces of its monitor, or stop doing so.
+    ///
+    /// Sticky windows are always floating.
+    fn set_window_sticky(&mut self, window: u32, enable: bool) -> WmResult {
+        self.sticky_windows.retain(|w| *w != window);
+        if enable {
+            self.sticky_windows.push(window);
+            self.float_window(window)?;
+        }
+
+        self.update_net_wm_state(window)
+    }
+
+    /// Move the pinned windows back into their corners, if they are not there anymore.
+    ///
+    /// Windows which are already in place are left alone, so that calling this after every change
+    /// doesn't generate any new events.
+    pub fn repin_windows(&mut self) -> WmResult {
         let connection = self.connection();
         let default_colormap = self.default_colormap();
-        if let Some(window) = self.get_focused_workspace_mut()?.focus.focused_client() {
-            let workspace = self.get_focused_workspace_mut()?;
-            let container = workspace.find_by_window_id(window)?;
-            let container_id = container.id();
-            let layout = *workspace.current_layout();
+        let width = self.config.settings.pin_width.min(u16::MAX as u32) as u16;
+        let height = self.config.settings.pin_height.min(u16::MAX as u32) as u16;
+        let mut moved = false;
+
+        for (window, corner) in self.pinned_windows.clone() {
+            if let Some(workspace) = self.workspace_for_window_mut(window) {
+                let geometry = corner.geometry(workspace.screen(), width, height);
+                if let ContainerType::Floating(c) =
+                    workspace.find_by_window_id_mut(window)?.data_mut()
+                {
+                    if c.geometry != geometry {
+                        c.geometry = geometry;
+                        c.draw_borders(connection.clone(), default_colormap)?;
+                        moved = true;
+                    }
+                }
+            }
+        }
 
-            let container_to_focus_option = match direction {
-                Direction::Next => Some(workspace.next_container(*container_id)),
-                Direction::Previous => Some(workspace.previous_container(*container_id)),
+        if moved {
+            self.restack()?;
+        }
+
+        Ok(())
+    }
+
+    /// Restack windows which requested to be kept above or below the others.
+    fn restack(&self) -> WmResult {
+        for window in self.below_windows.iter() {
+            self.connection().configure_window(
+                *window,
+                &ConfigureWindowAux::new().stack_mode(StackMode::BELOW),
+            )?;
+        }
+
+        for window in self.background_windows.values() {
+            self.connection().configure_window(
+                *window,
+                &ConfigureWindowAux::new().stack_mode(StackMode::BELOW),
+            )?;
+        }
+
+        for window in self.above_windows.iter() {
+            self.connection().configure_window(
+                *window,
+                &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+            )?;
+        }
+
+        // fullscreen windows cover everything else, the bars are then put above or below them
+        for window in self.fullscreen_windows.keys() {
+            self.connection().configure_window(
+                *window,
+                &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+            )?;
+        }
+
+        self.restack_bars()
+    }
+
+    /// Keep the bars above the other windows, except for the bars on monitors showing a
+    /// fullscreen window, which are kept below it, unless `bar_on_top_of_fullscreen` is set.
+    fn restack_bars(&self) -> WmResult {
+        for bar in self.bars.iter() {
+            let window = match bar.window_id() {
+                Ok(window) => window,
+                Err(_) => continue,
             };
 
-            if let Some(container_to_focus) = container_to_focus_option {
-                let container = container_to_focus?.data();
-                let size = container.geometry();
-                if let Some(window_to_focus) = container.window_id() {
-                    workspace.focus.set_focused_client(window_to_focus);
-                    if matches!(layout, LayoutType::Stacking) {
-                        workspace.apply_layout(connection.clone(), None, default_colormap)?;
-                    }
-                    connection.set_input_focus(
-                        InputFocus::PARENT,
-                        window_to_focus,
-                        x11rb::CURRENT_TIME,
-                    )?;
-                    self.connection().warp_pointer(
-                        NONE,
-                        self.root_window(),
-                        0,
-                        0,
-                        0,
+            let aux = match self.visible_fullscreen_window(bar.monitor() + 1) {
+                Some(fullscreen) if !self.config.settings.bar_on_top_of_fullscreen => {
+                    ConfigureWindowAux::new()
+                        .sibling(fullscreen)
+                        .stack_mode(StackMode::BELOW)
+                }
+                _ => ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+            };
+            self.connection().configure_window(window, &aux)?;
+        }
+
+        Ok(())
+    }
+
+    /// Show the `background` color of the workspace open on each monitor, if the workspace has one
+    /// and no windows. The color is shown by a window covering the monitor below all the other
+    /// windows, so that every monitor can have its own color.
+    fn update_backgrounds(&mut self) -> WmResult {
+        let connection = self.connection();
+        let monitors: Vec<(MonitorId, Geometry, Option<WorkspaceId>)> = self
+            .monitors
+            .iter()
+            .map(|m| (m.id(), m.size(), m.get_open_workspace().ok()))
+            .collect();
+
+        for (monitor_id, geometry, open) in monitors {
+            let color = open
+                .filter(|id| {
+                    self.workspace_with_id(*id)
+                        .map(|w| !w.has_windows())
+                        .unwrap_or(false)
+                })
+                .and_then(|id| self.config.workspace_settings.get(id))
+                .filter(|settings| !settings.background.is_empty())
+                .map(|settings| convert_color(&settings.background));
+
+            let color = match (color, self.background_windows.get(&monitor_id)) {
+                (Some(color), _) => color,
+                (None, Some(window)) => {
+                    connection.unmap_window(*window)?;
+                    continue;
+                }
+                (None, None) => continue,
+            };
+
+            let window = match self.background_windows.get(&monitor_id) {
+                Some(window) => *window,
+                None => {
+                    let window = connection.generate_id()?;
+                    let screen = self.root_screen();
+                    connection.create_window(
+                        screen.root_depth,
+                        window,
+                        screen.root,
+                        geometry.x,
+                        geometry.y,
+                        geometry.width,
+                        geometry.height,
                         0,
-                        size.x + (size.width / 2) as i16,
-                        size.y + (size.height / 2) as i16,
+                        WindowClass::INPUT_OUTPUT,
+                        screen.root_visual,
+                        &CreateWindowAux::new(),
                     )?;
+                    self.background_windows.insert(monitor_id, window);
+                    window
+                }
+            };
+
+            connection.change_window_attributes(
+                window,
+                &ChangeWindowAttributesAux::new().background_pixel(color),
+            )?;
+            connection.configure_window(
+                window,
+                &ConfigureWindowAux::from(geometry).stack_mode(StackMode::BELOW),
+            )?;
+            connection.map_window(window)?;
+            connection.clear_area(false, window, 0, 0, 0, 0)?;
+        }
+
+        Ok(())
+    }
+
+    /// Return a fullscreen window on the workspace which is open on the given monitor.
+    fn visible_fullscreen_window(&self, monitor_id: u32) -> Option<u32> {
+        let open = self
+            .monitors
+            .iter()
+            .find(|m| m.id() == monitor_id)?
+            .get_open_workspace()
+            .ok()?;
+
+        self.fullscreen_windows.keys().copied().find(|window| {
+            self.workspace_for_window(*window)
+                .map(|w| w.id == open)
+                .unwrap_or(false)
+        })
+    }
+
+    /// Return the list of atoms in the `_NET_WM_STATE` property of a window.
+    fn net_wm_state(&self, window: u32) -> WmResult<Vec<u32>> {
+        let states = self
+            .atoms
+            .get("_NET_WM_STATE")
+            .unwrap()
+            .get_property(window, self.connection(), Some(32))?
+            .into_iter()
+            .filter_map(|value| value.try_into().ok())
+            .collect();
+
+        Ok(states)
+    }
+
+    /// Publish the number and the names of the workspaces in the `_NET_NUMBER_OF_DESKTOPS` and
+    /// `_NET_DESKTOP_NAMES` properties of the root window.
+    ///
+    /// Desktops are numbered by the position of their workspace in the config, counting from 0, as
+    /// workspace identifiers don't have to be contiguous.
+    fn update_desktops(&self) -> WmResult {
+        let root = self.root_window();
+        let names: Vec<u8> = self
+            .workspaces
+            .iter()
+            .flat_map(|workspace| workspace.name.bytes().chain(std::iter::once(0)))
+            .collect();
+
+        self.connection().change_property32(
+            PropMode::REPLACE,
+            root,
+            self.atoms.get("_NET_NUMBER_OF_DESKTOPS").unwrap().id(),
+            AtomEnum::CARDINAL,
+            &[self.workspaces.len() as u32],
+        )?;
+        self.connection().change_property8(
+            PropMode::REPLACE,
+            root,
+            self.atoms.get("_NET_DESKTOP_NAMES").unwrap().id(),
+            self.atoms.get("UTF8_STRING").unwrap().id(),
+            &names,
+        )?;
+
+        Ok(())
+    }
+
+    /// Publish the index of the focused workspace in the `_NET_CURRENT_DESKTOP` property of the
+    /// root window, see [`State::update_desktops`].
+    fn update_current_desktop(&self) -> WmResult {
+        let index = self
+            .workspaces
+            .iter()
+            .position(|workspace| Some(workspace.id) == self.focused_workspace);
+
+        if let Some(index) = index {
+            self.connection().change_property32(
+                PropMode::REPLACE,
+                self.root_window(),
+                self.atoms.get("_NET_CURRENT_DESKTOP").unwrap().id(),
+                AtomEnum::CARDINAL,
+                &[index as u32],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Publish the name of the focused workspace's layout in the `_CRUBWM_LAYOUT` property of the
+    /// root window.
+    fn update_layout_property(&self) -> WmResult {
+        let layout = *self.get_focused_workspace()?.current_layout();
+        self.connection().change_property8(
+            PropMode::REPLACE,
+            self.root_window(),
+            self.atoms.get("_CRUBWM_LAYOUT").unwrap().id(),
+            AtomEnum::STRING,
+            layout.name().as_bytes(),
+        )?;
+
+        Ok(())
+    }
+
+    /// Is this a change of the `_CRUBWM_LAYOUT` property of the root window?
+    pub fn is_layout_property(&self, window: u32, atom: u32) -> bool {
+        window == self.root_window() && atom == self.atoms.get("_CRUBWM_LAYOUT").unwrap().id()
+    }
+
+    /// Change the focused workspace's layout to the one written to the `_CRUBWM_LAYOUT` property
+    /// of the root window. An invalid name is replaced by the name of the current layout.
+    pub fn update_layout_from_property(&mut self) -> WmResult {
+        let value = self.atoms.get("_CRUBWM_LAYOUT").unwrap().get_property(
+            self.root_window(),
+            self.connection(),
+            None,
+        )?;
+        let name: String = match value.first() {
+            Some(first) => first.clone().try_into()?,
+            None => return Ok(()),
+        };
+
+        let layout = match LayoutType::try_from(name.as_str()) {
+            Ok(layout) => layout,
+            Err(e) => {
+                self.update_layout_property()?;
+                return Err(e);
+            }
+        };
+
+        // our own writes to the property come back here as well
+        if layout as u64 == *self.get_focused_workspace()?.current_layout() as u64 {
+            return Ok(());
+        }
+
+        self.action_change_layout(name)
+    }
+
+    /// Publish the managed windows of all the workspaces in the `_NET_CLIENT_LIST` property of the
+    /// root window, from the bottom of the stack to the top.
+    fn update_client_list(&self) -> WmResult {
+        let root = self.root_window();
+        let clients: Vec<u32> = self
+            .connection()
+            .query_tree(root)?
+            .reply()?
+            .children
+            .into_iter()
+            .filter(|window| self.workspace_for_window(*window).is_some())
+            .collect();
+
+        self.connection().change_property32(
+            PropMode::REPLACE,
+            root,
+            self.atoms.get("_NET_CLIENT_LIST").unwrap().id(),
+            AtomEnum::WINDOW,
+            &clients,
+        )?;
+
+        Ok(())
+    }
+
+    /// Publish the focused client in the `_NET_ACTIVE_WINDOW` property of the root window, or
+    /// `NONE` if no client is focused. The property is only changed when the focus has changed
+    /// since it was last published.
+    pub fn update_active_window(&mut self) -> WmResult {
+        let active = self
+            .get_focused_workspace()
+            .ok()
+            .and_then(|workspace| workspace.focus.focused_client())
+            .unwrap_or(NONE);
+        if self.active_window == Some(active) {
+            return Ok(());
+        }
+
+        self.connection().change_property32(
+            PropMode::REPLACE,
+            self.root_window(),
+            self.atoms.get("_NET_ACTIVE_WINDOW").unwrap().id(),
+            AtomEnum::WINDOW,
+            &[active],
+        )?;
+        self.active_window = Some(active);
+
+        Ok(())
+    }
+
+    /// Reflect the states managed by the window manager in the `_NET_WM_STATE` property of a
+    /// window.
+    fn update_net_wm_state(&self, window: u32) -> WmResult {
+        let mut states = Vec::new();
+        if self.above_windows.contains(&window) {
+            states.push(self.atoms.get("_NET_WM_STATE_ABOVE").unwrap().id());
+        }
+        if self.below_windows.contains(&window) {
+            states.push(self.atoms.get("_NET_WM_STATE_BELOW").unwrap().id());
+        }
+        if self.sticky_windows.contains(&window) {
+            states.push(self.atoms.get("_NET_WM_STATE_STICKY").unwrap().id());
+        }
+        if self.fullscreen_windows.contains_key(&window) {
+            states.push(self.atoms.get("_NET_WM_STATE_FULLSCREEN").unwrap().id());
+        }
+
+        self.connection().change_property32(
+            PropMode::REPLACE,
+            window,
+            self.atoms.get("_NET_WM_STATE").unwrap().id(),
+            AtomEnum::ATOM,
+            &states,
+        )?;
+
+        Ok(())
+    }
+
+    /// Handle an enter window event.
+    ///
+    /// This method is responsible for switching input focus to the newly entered window.
+    /// In the future, this will also handle the decorators, WM properties and other necessary
+    /// things.
+    ///
+    /// Only crossings caused by the pointer moving change the focus. Crossings caused by grabs,
+    /// crossings between a window and its subwindows and crossings caused by a window being
+    /// moved under the pointer, for example by a layout change, are ignored.
+    pub fn handle_enter_event(
+        &mut self,
+        ev: &x11rb::protocol::xproto::EnterNotifyEvent,
+    ) -> WmResult {
+        let window = ev.event;
+        if window == self.root_window()
+            || !focus_model::focused_by_pointer(&self.config.settings, PointerFocus::Enter)
+            || !self.is_managed_window(window)
+        {
+            return Ok(());
+        }
+
+        if ev.mode != NotifyMode::NORMAL
+            || matches!(
+                ev.detail,
+                NotifyDetail::INFERIOR | NotifyDetail::VIRTUAL | NotifyDetail::NONLINEAR_VIRTUAL
+            )
+        {
+            return Ok(());
+        }
+
+        let position = (ev.root_x, ev.root_y);
+        if self.pointer_position.replace(position) == Some(position) {
+            logm!(
+                LL_FULL,
+                "Ignoring enter notify for window {window}, the pointer has not moved."
+            );
+            return Ok(());
+        }
+
+        self.focus_pointer_window(window)
+    }
+
+    /// Focus a window the pointer entered or clicked, along with its workspace. Floating windows
+    /// are raised as well.
+    fn focus_pointer_window(&mut self, window: u32) -> WmResult {
+        let workspace = self.workspace_for_window(window).ok_or_else(|| {
+            Error::Generic(format!("The window {window} is not in any workspace!"))
+        })?;
+
+        let cont = workspace.find_by_window_id(window)?;
+
+        if cont.is_floating() {
+            self.connection().configure_window(
+                window,
+                &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+            )?;
+            self.restack()?;
+        }
+
+        self.focus_workspace(workspace.id, false)?;
+        self.get_focused_workspace_mut()?
+            .focus
+            .set_focused_client(window);
+
+        self.connection()
+            .set_input_focus(InputFocus::NONE, window, CURRENT_TIME)?;
+
+        Ok(())
+    }
+
+    /// With the `click` focus model, grab plain left clicks on a window, so that clicking the
+    /// window focuses it. The click is passed on to the window afterwards.
+    fn grab_click_to_focus(&self, window: u32) -> WmResult {
+        if focus_model::focused_by_pointer(&self.config.settings, PointerFocus::Click) {
+            self.connection().grab_button(
+                false,
+                window,
+                u32::from(EventMask::BUTTON_PRESS) as u16,
+                GrabMode::SYNC,
+                GrabMode::ASYNC,
+                NONE,
+                NONE,
+                ButtonIndex::M1,
+                0u16,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Handle a key press event.
+    ///
+    /// The actions of a keybind are run in order. An action which fails is logged and the rest of
+    /// the actions are still run.
+    pub fn handle_key_press(&mut self, ev: &KeyPressEvent) -> WmResult {
+        self.pointer_position = Some((ev.root_x, ev.root_y));
+        let actions_option = self.key_manager.on_key_press(ev)?;
+        if let Some(actions) = actions_option {
+            for action in actions {
+                if let Err(e) = self.do_action(action) {
+                    errm!("{}", e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle a key release event.
+    pub fn handle_key_release(&mut self, ev: &KeyReleaseEvent) -> WmResult {
+        self.key_manager.on_key_release(ev)?;
+        Ok(())
+    }
+
+    /// Handle a button press event.
+    ///
+    /// We check which button on the mouse was pressed, if it was the left button(ev.detail = 1), we know that the
+    /// user wants to move this client around, we set the `is_dragging` filed to true. If, on the
+    /// other hand, the right button(ev.detail = 3) was pressed, we know the user wants to resize
+    /// the window and we set the `is_resizing` flag to to true. Dragging a tiled window with the
+    /// left button sets the `is_reordering` flag, the window is swapped with the tile it's dropped
+    /// onto.
+    pub fn handle_button_press(
+        &mut self,
+        ev: &x11rb::protocol::xproto::ButtonPressEvent,
+    ) -> WmResult {
+        self.pointer_position = Some((ev.root_x, ev.root_y));
+        if let Some(bar) = self
+            .bars
+            .iter()
+            .find(|bar| bar.window_id().ok() == Some(ev.event))
+        {
+            // clicking a workspace on the bar switches to it
+            return match bar.workspace_at(ev.event_x.into()) {
+                Some(workspace_id) if ev.detail == 1 => self.focus_workspace(workspace_id, false),
+                _ => Ok(()),
+            };
+        }
+        if focus_model::focused_by_pointer(&self.config.settings, PointerFocus::Click)
+            && ev.detail == 1
+            && ev.state & self.floating_modifier == 0
+        {
+            self.connection()
+                .allow_events(Allow::REPLAY_POINTER, ev.time)?;
+            if !self.is_managed_window(ev.event) {
+                return Ok(());
+            }
+            return self.focus_pointer_window(ev.event);
+        }
+        if !self.is_managed_window(ev.event) {
+            return Ok(());
+        }
+
+        let workspace = self.workspace_for_window_mut(ev.event).ok_or_else(|| {
+            Error::Generic(format!(
+                "workspace error: unable to find workspace for window id {}",
+                ev.event
+            ))
+        })?;
+
+        let container = workspace.find_by_window_id_mut(ev.event)?;
+
+        if !container.is_floating() {
+            if ev.detail == 1 && container.is_in_layout() {
+                self.is_reordering = true;
+            }
+        } else {
+            container.change_last_position((ev.root_x, ev.root_y));
+            match ev.detail {
+                1 => self.is_dragging = true,
+                3 => self.is_resizing = true,
+                _ => (),
+            };
+        }
+
+        if self.is_dragging || self.is_resizing || self.is_reordering {
+            self.grab_abort_keys(true)?;
+        }
+
+        Ok(())
+    }
+
+    /// Handle a button release event.
+    ///
+    /// Very similar to button press, we check the `ev.detail` field of the `ButtonReleaseEvent`,
+    /// and either finish the dragging process, updating the window's position for the final time,
+    /// or we finish the resizing process, updating the window's width and height for the final
+    /// time.
+    pub fn handle_button_release(
+        &mut self,
+        ev: &x11rb::protocol::xproto::ButtonReleaseEvent,
+    ) -> WmResult {
+        // releasing the button holding a window ends the pointer operation
+        let ended = match ev.detail {
+            1 => self.is_dragging || self.is_reordering,
+            3 => self.is_resizing,
+            _ => false,
+        };
+        if ended {
+            self.grab_abort_keys(false)?;
+        }
+        if self.is_reordering && ev.detail == 1 {
+            return self.drop_tile(ev.event, ev.root_x, ev.root_y);
+        }
+        if !self.is_managed_window(ev.event) {
+            self.is_dragging = false;
+            self.is_resizing = false;
+            return Ok(());
+        }
+        // the operation has been aborted
+        if !self.is_dragging && !self.is_resizing {
+            return Ok(());
+        }
+
+        let default_colormap = self.default_colormap();
+        let connection = self.connection();
+        let workspace = self.workspace_for_window_mut(ev.event).ok_or_else(|| {
+            Error::Generic(format!(
+                "workspace error: unable to find workspace for window id {}",
+                ev.event
+            ))
+        })?;
+
+        let container = workspace.find_by_window_id_mut(ev.event)?;
+
+        if !container.is_floating() {
+            return Ok(());
+        } else {
+            match ev.detail {
+                1 => {
+                    let last_event_position = container.last_position().unwrap();
+                    let diff = (
+                        last_event_position.0 as i16 - ev.root_x,
+                        last_event_position.1 as i16 - ev.root_y,
+                    );
+                    if let crate::wm::container::ContainerType::Floating(c) = container.data_mut() {
+                        c.geometry.x -= diff.0;
+                        c.geometry.y -= diff.1;
+                        c.draw_borders(connection, default_colormap)?;
+                    }
+                    self.is_dragging = false
+                }
+                3 => {
+                    let last_event_position = container.last_position().unwrap();
+                    let diff = (
+                        last_event_position.0 as i16 - ev.root_x,
+                        last_event_position.1 as i16 - ev.root_y,
+                    );
+                    let geom = container.data().geometry();
+                    let (w, h) = (geom.width as i16 - diff.0, geom.height as i16 - diff.1);
+                    if (w as u16) < MIN_WIDTH || (h as u16) < MIN_HEIGHT {
+                        self.is_resizing = false;
+                        return Ok(());
+                    }
+                    if let crate::wm::container::ContainerType::Floating(c) = container.data_mut() {
+                        c.geometry.width = w as u16;
+                        c.geometry.height = h as u16;
+                        c.draw_borders(connection, default_colormap)?;
+                    }
+                    self.is_resizing = false
+                }
+
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle a motion notify event.
+    ///
+    /// After checking which flag is active, we either move or resize the window.
+    pub fn handle_motion_notify(
+        &mut self,
+        ev: &x11rb::protocol::xproto::MotionNotifyEvent,
+    ) -> WmResult {
+        self.pointer_position = Some((ev.root_x, ev.root_y));
+        if self.is_reordering {
+            return self.update_drop_indicator(ev.event, ev.root_x, ev.root_y);
+        }
+        if !self.is_managed_window(ev.event) {
+            return Ok(());
+        }
+
+        let default_colormap = self.default_colormap();
+        let connection = self.connection();
+        let dragging = self.is_dragging;
+        let resizing = self.is_resizing;
+        let workspace = self.workspace_for_window_mut(ev.event).ok_or_else(|| {
+            Error::Generic(format!(
+                "workspace error: unable to find workspace for window id {}",
+                ev.event
+            ))
+        })?;
+
+        let container = workspace.find_by_window_id_mut(ev.event)?;
+
+        if !container.is_floating() {
+            return Ok(());
+        } else if dragging {
+            let last_event_position = container.last_position().unwrap();
+            let diff = (
+                (last_event_position.0 as i16 - ev.root_x) as f32 * DRAG_SPEED_COEFFICIENT,
+                (last_event_position.1 as i16 - ev.root_y) as f32 * DRAG_SPEED_COEFFICIENT,
+            );
+            if let crate::wm::container::ContainerType::Floating(c) = container.data_mut() {
+                c.geometry.x -= diff.0 as i16;
+                c.geometry.y -= diff.1 as i16;
+
+                c.draw_borders(connection, default_colormap)?;
+            }
+            container.change_last_position((ev.root_x, ev.root_y))
+        } else if resizing {
+            let last_event_position = container.last_position().unwrap();
+            let diff = (
+                last_event_position.0 as i16 - ev.root_x,
+                last_event_position.1 as i16 - ev.root_y,
+            );
+            let geom = container.data().geometry();
+            let (w, h) = (geom.width as i16 - diff.0, geom.height as i16 - diff.1);
+            if (w as u16) < MIN_WIDTH || (h as u16) < MIN_HEIGHT {
+                return Ok(());
+            }
+            if let crate::wm::container::ContainerType::Floating(c) = container.data_mut() {
+                c.geometry.width = w as u16;
+                c.geometry.height = h as u16;
+                c.draw_borders(connection, default_colormap)?;
+            }
+            container.change_last_position((ev.root_x - diff.0, ev.root_y - diff.1));
+        }
+
+        Ok(())
+    }
+
+    /// Return the tile under the pointer, which the dragged tiled `window` would be dropped onto.
+    ///
+    /// Returns `None` if the pointer is over the dragged window itself or over empty space.
+    fn drop_target(&self, window: u32, x: i16, y: i16) -> Option<(ContainerId, Geometry)> {
+        let workspace = self.workspace_for_window(window)?;
+
+        workspace
+            .tile_at(x, y)
+            .filter(|c| c.data().window_id() != Some(window))
+            .map(|c| (*c.id(), c.data().geometry()))
+    }
+
+    /// Outline the tile the dragged tiled `window` would be dropped onto, hiding the outline if
+    /// there's no such tile.
+    fn update_drop_indicator(&mut self, window: u32, x: i16, y: i16) -> WmResult {
+        let connection = self.connection();
+        let target = self.drop_target(window, x, y);
+
+        let indicator = match (self.drop_indicator, target) {
+            (Some(indicator), None) => {
+                connection.unmap_window(indicator)?;
+                return Ok(());
+            }
+            (None, None) => return Ok(()),
+            (Some(indicator), Some(_)) => indicator,
+            (None, Some(_)) => {
+                let indicator = connection.generate_id()?;
+                let screen = &connection.setup().roots[self.screen_index];
+                let color = self.config.settings.convert_border_color();
+                connection.create_window(
+                    screen.root_depth,
+                    indicator,
+                    screen.root,
+                    0,
+                    0,
+                    1,
+                    1,
+                    0,
+                    WindowClass::INPUT_OUTPUT,
+                    screen.root_visual,
+                    &CreateWindowAux::new()
+                        .background_pixel(color)
+                        .override_redirect(1),
+                )?;
+                self.drop_indicator = Some(indicator);
+                indicator
+            }
+        };
+
+        if let Some((_, geometry)) = target {
+            connection.configure_window(
+                indicator,
+                &ConfigureWindowAux::new()
+                    .x(geometry.x as i32)
+                    .y(geometry.y as i32)
+                    .width(geometry.width.max(1) as u32)
+                    .height(geometry.height.max(1) as u32)
+                    .stack_mode(StackMode::ABOVE),
+            )?;
+            connection.map_window(indicator)?;
+        }
+
+        Ok(())
+    }
+
+    /// Finish dragging the tiled `window`, swapping it with the tile it was dropped onto.
+    /// Dropping it onto empty space or onto itself does nothing.
+    fn drop_tile(&mut self, window: u32, x: i16, y: i16) -> WmResult {
+        self.is_reordering = false;
+        let connection = self.connection();
+        let default_colormap = self.default_colormap();
+        if let Some(indicator) = self.drop_indicator {
+            connection.unmap_window(indicator)?;
+        }
+
+        let target = self.drop_target(window, x, y);
+        if let (Some((target, _)), Some(workspace)) =
+            (target, self.workspace_for_window_mut(window))
+        {
+            let dragged = *workspace.find_by_window_id(window)?.id();
+            workspace.swap(dragged, target)?;
+            workspace.apply_layout(connection, None, default_colormap)?;
+            self.restack()?;
+        }
+
+        Ok(())
+    }
+
+    /// Cancel every transient state, such as a window being dragged or resized with the pointer,
+    /// leaving everything as it is at the moment.
+    ///
+    /// Features introducing a transient state should end it here, so that the `abort` action
+    /// gets them all back to normal.
+    fn cancel_transient_state(&mut self) -> WmResult {
+        logm!(LL_FULL, "Cancelling all transient states.");
+        self.key_manager.reset();
+
+        self.is_dragging = false;
+        self.is_resizing = false;
+        if self.is_reordering {
+            self.is_reordering = false;
+            if let Some(indicator) = self.drop_indicator {
+                self.connection().unmap_window(indicator)?;
+            }
+        }
+
+        self.grab_abort_keys(false)
+    }
+
+    /// Grab the keys of the `abort` keybinds with any modifiers not used by other keybinds, or
+    /// release them. They are only grabbed while there is something to abort, so that the other
+    /// clients get them otherwise.
+    fn grab_abort_keys(&mut self, grab: bool) -> WmResult {
+        if grab == self.abort_keys_grabbed {
+            return Ok(());
+        }
+
+        let root = self.root_window();
+        for (mask, code) in self.key_manager.abort_grabs() {
+            if grab {
+                self.connection().grab_key(
+                    true,
+                    root,
+                    mask,
+                    code,
+                    GrabMode::ASYNC,
+                    GrabMode::ASYNC,
+                )?;
+            } else {
+                self.connection().ungrab_key(code, root, mask)?;
+            }
+        }
+        self.abort_keys_grabbed = grab;
+
+        Ok(())
+    }
+
+    /// Handle a focus in event.
+    pub fn handle_focus_in(&mut self, ev: &FocusInEvent) -> WmResult {
+        if !self.is_managed_window(ev.event) {
+            return Ok(());
+        }
+        logm!(LL_NORMAL, "focus in in {}", ev.event);
+        let connection = self.connection();
+        if let Some(workspace) = self.workspace_for_window(ev.event) {
+            if let Some(focused) = workspace.focus.focused_client() {
+                connection.set_input_focus(InputFocus::PARENT, focused, CURRENT_TIME)?;
+                self.focus_history.set_focused_client(focused);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle the execution of a given action.
+    fn do_action(&mut self, action: Action) -> WmResult {
+        match action {
+            Action::Noop => {}
+            Action::Kill => self.action_kill()?,
+            Action::KillUnresponsive => self.action_kill_unresponsive()?,
+            Action::Goto(workspace) => self.action_goto(workspace as u32)?,
+            Action::Move(workspace) => self.action_move(workspace as u32)?,
+            Action::Execute(command) => self.action_execute(command)?,
+            Action::ExecuteOn(workspace, command) => {
+                self.action_execute_on(workspace as u32, command)?
+            }
+            Action::Focus(direction) => self.action_focus(direction)?,
+            Action::ChangeLayout(layout) => self.action_change_layout(layout)?,
+            Action::CycleLayout => self.action_cycle_layout()?,
+            Action::IncreaseMasterRatio => self.action_change_master_ratio(MASTER_RATIO_STEP)?,
+            Action::DecreaseMasterRatio => self.action_change_master_ratio(-MASTER_RATIO_STEP)?,
+            Action::Nmaster(delta) => self.action_change_nmaster(delta)?,
+            Action::ToggleFloat => self.action_toggle_float()?,
+            Action::ToggleSticky => self.action_toggle_sticky()?,
+            Action::Swap(direction) => self.action_swap(direction)?,
+            Action::ReloadConfig => self.action_reload_config()?,
+            Action::BarHeight(delta) => self.action_bar_height(delta)?,
+            Action::MoveToMonitor(direction) => self.action_move_to_monitor(direction, false)?,
+            Action::MoveToMonitorFollow(direction) => {
+                self.action_move_to_monitor(direction, true)?
+            }
+            Action::Pin(corner) => self.action_pin(corner)?,
+            Action::FloatResize(w, h) => self.action_float_resize(w, h)?,
+            Action::ToggleGaps => self.action_toggle_gaps()?,
+            Action::SwitchLayout => self.action_switch_layout()?,
+            Action::FocusMode => self.action_focus_mode()?,
+            Action::FocusFloating(direction) => self.action_focus_filtered(direction, true)?,
+            Action::FocusTiled(direction) => self.action_focus_filtered(direction, false)?,
+            Action::FocusMonitor(direction) => self.action_focus_monitor(direction)?,
+            Action::FocusLastWindow => self.action_focus_last_window()?,
+            Action::ToggleFullscreen => self.action_toggle_fullscreen()?,
+            Action::Raise => self.action_restack_floating(StackMode::ABOVE)?,
+            Action::Lower => self.action_restack_floating(StackMode::BELOW)?,
+            Action::NextWorkspace => self.action_adjacent_workspace(true)?,
+            Action::PrevWorkspace => self.action_adjacent_workspace(false)?,
+            Action::Abort => self.cancel_transient_state()?,
+            Action::ToggleDnd => self.action_toggle_dnd()?,
+            Action::MoveFloating(x, y) => self.action_adjust_floating(x, y, 0, 0)?,
+            Action::ResizeFloating(w, h) => self.action_adjust_floating(0, 0, w, h)?,
+            Action::CenterFloating => self.action_center_floating()?,
+            Action::RebalanceMonitors => self.action_rebalance_monitors()?,
+            Action::Gather(class) => self.action_gather(&class)?,
+            Action::SpawnFloatTerminal => self.action_spawn_float_terminal()?,
+        }
+
+        Ok(())
+    }
+
+    /// Execute a command.
+    fn action_execute(&mut self, command: String) -> WmResult {
+        spawn_command(&command)?;
+
+        Ok(())
+    }
+
+    /// Execute a command and put its first window onto a workspace, without switching to it.
+    fn action_execute_on(&mut self, workspace_id: WorkspaceId, command: String) -> WmResult {
+        let workspace_id = self.resolve_workspace_id(workspace_id)?;
+        if self.workspace_with_id(workspace_id).is_none() {
+            return Err(format!("execute error: no workspace with id {workspace_id} found").into());
+        }
+
+        let child = spawn_command(&command)?;
+        self.pending_spawns.add(
+            child.id(),
+            SpawnTarget::Workspace(workspace_id),
+            Instant::now(),
+        );
+        logm!(
+            LL_FULL,
+            "Waiting for a window of process {} to put onto workspace {workspace_id}.",
+            child.id()
+        );
+
+        Ok(())
+    }
+
+    /// Start the terminal from the settings, as a floating window centered at the pointer, or on
+    /// the monitor the pointer is on, depending on `float_terminal_at_cursor`.
+    fn action_spawn_float_terminal(&mut self) -> WmResult {
+        let settings = &self.config.settings;
+        let pointer = self
+            .connection()
+            .query_pointer(self.root_window())?
+            .reply()?;
+        let (x, y) = (pointer.root_x, pointer.root_y);
+        let monitor = match self.monitors.iter().find(|m| m.size().contains_point(x, y)) {
+            Some(monitor) => monitor,
+            None => self.get_focused_or_first_monitor()?,
+        };
+        let (workspace_id, area) = (monitor.get_open_workspace()?, monitor.usable_size());
+        let center = if settings.float_terminal_at_cursor {
+            (x, y)
+        } else {
+            area.center()
+        };
+        let geometry = Geometry::centered_within(
+            settings.float_terminal_width.min(u16::MAX as u32) as u16,
+            settings.float_terminal_height.min(u16::MAX as u32) as u16,
+            center,
+            area,
+        );
+
+        let child = spawn_command(&settings.terminal)?;
+        self.pending_spawns.add(
+            child.id(),
+            SpawnTarget::Floating(workspace_id, geometry),
+            Instant::now(),
+        );
+        logm!(
+            LL_FULL,
+            "Waiting for a window of process {} to float at {geometry:?}.",
+            child.id()
+        );
+
+        Ok(())
+    }
+
+    /// Attempt to close the focused client.
+    ///
+    /// Clients which support the `WM_DELETE_WINDOW` protocol are asked to close themselves. Other
+    /// clients are killed, using the process id from `_NET_WM_PID`, or disconnected from the X
+    /// server, if their process isn't known.
+    fn action_kill(&mut self) -> WmResult {
+        let window = self
+            .get_focused_workspace()?
+            .focus
+            .focused_client()
+            .ok_or_else(|| Error::Generic("Unable to kill window. Window not found!".into()))?;
+
+        if self.send_delete_window(window)? {
+            logm!(
+                LL_NORMAL,
+                "Killed window {window} using WM_DELETE_WINDOW message.",
+            );
+            return Ok(());
+        }
+
+        let process_id = self
+            .workspace_for_window(window)
+            .and_then(|workspace| workspace.find_by_window_id(window).ok())
+            .and_then(|container| container.data().process_id());
+        if let Some(pid) = process_id {
+            signal_process(pid, libc::SIGTERM)?;
+            logm!(LL_NORMAL, "Killed window {window} using _NET_WM_PID.",);
+        } else {
+            self.connection().kill_client(window)?;
+            logm!(LL_NORMAL, "Killed window {window} using XKillClient.",);
+        }
+
+        Ok(())
+    }
+
+    /// Focus a window given a direction.
+    fn action_focus(&mut self, direction: Direction) -> WmResult {
+        if let Some(window) = self.get_focused_workspace_mut()?.focus.focused_client() {
+            let workspace = self.get_focused_workspace_mut()?;
+            let container = workspace.find_by_window_id(window)?;
+            let container_id = container.id();
+
+            // spatial directions don't wrap around, the focus stays put at the edge of the screen
+            let container_to_focus_option = match direction {
+                Direction::Next => Some(workspace.next_container(*container_id)?),
+                Direction::Previous => Some(workspace.previous_container(*container_id)?),
+                _ => workspace.nearest_container(*container_id, direction)?,
+            };
+
+            if let Some(container_to_focus) = container_to_focus_option {
+                let container = container_to_focus.data();
+                let size = container.geometry();
+                if let Some(window_to_focus) = container.window_id() {
+                    self.focus_window(window_to_focus, size)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Shift focus to the next or previous floating, or tiled, window of the focused workspace.
+    /// A newly focused floating window is raised.
+    fn action_focus_filtered(&mut self, direction: Direction, floating: bool) -> WmResult {
+        let filter = |c: &Container| {
+            if floating {
+                c.is_floating()
+            } else {
+                c.is_in_layout()
+            }
+        };
+        let workspace = self.get_focused_workspace()?;
+        let focused_container = workspace
+            .focus
+            .focused_client()
+            .and_then(|window| workspace.find_by_window_id(window).ok());
+
+        let container_to_focus = match (focused_container, direction) {
+            (Some(container), Direction::Next) => {
+                workspace.next_container_filtered(*container.id(), filter)?
+            }
+            (Some(container), Direction::Previous) => {
+                workspace.previous_container_filtered(*container.id(), filter)?
+            }
+            (None, _) => workspace.iter_containers()?.find(|c| filter(c)),
+            // Spatial directions are rejected when parsing the action.
+            _ => None,
+        };
+
+        let (window, size) = match container_to_focus {
+            Some(container) => match container.data().window_id() {
+                Some(window) => (window, container.data().geometry()),
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+
+        self.focus_window(window, size)?;
+        if floating {
+            self.connection().configure_window(
+                window,
+                &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+            )?;
+            self.restack()?;
+        }
+
+        Ok(())
+    }
+
+    /// Focus a window of the focused workspace and warp the pointer to the middle of it.
+    fn focus_window(&mut self, window: u32, size: Geometry) -> WmResult {
+        let connection = self.connection();
+        let default_colormap = self.default_colormap();
+        let workspace = self.get_focused_workspace_mut()?;
+        let layout = *workspace.current_layout();
+
+        workspace.focus.set_focused_client(window);
+        if matches!(layout, LayoutType::Stacking) {
+            workspace.apply_layout(connection.clone(), None, default_colormap)?;
+        }
+        focus_model::focus_without_pointer(
+            connection.as_ref(),
+            &self.config.settings,
+            self.root_window(),
+            Some(window),
+            size,
+        )?;
+
+        Ok(())
+    }
+
+    /// Return the index of the next or previous monitor, relative to the focused one, or `None` if
+    /// there's only one monitor.
+    fn neighbouring_monitor(&self, direction: Direction) -> WmResult<Option<usize>> {
+        let monitor_count = self.monitors.len();
+        if monitor_count < 2 {
+            return Ok(None);
+        }
+
+        let monitor_id = self.get_focused_or_first_monitor()?.id();
+        let index = self
+            .monitors
+            .iter()
+            .position(|monitor| monitor.id() == monitor_id)
+            .unwrap_or(0);
+
+        Ok(Some(match direction {
+            Direction::Previous => (index + monitor_count - 1) % monitor_count,
+            _ => (index + 1) % monitor_count,
+        }))
+    }
+
+    /// Focus the next or previous monitor, restoring the workspace which was last focused on it,
+    /// along with that workspace's focused window.
+    fn action_focus_monitor(&mut self, direction: Direction) -> WmResult {
+        let new_index = match self.neighbouring_monitor(direction)? {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+        let workspace_id = self.monitors[new_index].workspace_to_focus()?;
+
+        self.focus_workspace(workspace_id, true)?;
+        let workspace = self.get_focused_workspace()?;
+        if let Some(window) = workspace.focus.focused_client() {
+            let size = workspace.find_by_window_id(window)?.data().geometry();
+            self.focus_window(window, size)?;
+        }
+
+        Ok(())
+    }
+
+    /// Focus the previously focused window, switching to its workspace if needed.
+    fn action_focus_last_window(&mut self) -> WmResult {
+        let current = self
+            .get_focused_workspace()
+            .ok()
+            .and_then(|workspace| workspace.focus.focused_client());
+        let workspaces = &self.workspaces;
+        let window = match self.focus_history.last_client_except(current, |window| {
+            workspaces
+                .iter()
+                .any(|workspace| workspace.contains_window(window))
+        }) {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+
+        if let Some(workspace_id) = self.workspace_for_window(window).map(|w| w.id) {
+            if self.focused_workspace != Some(workspace_id) {
+                self.focus_workspace(workspace_id, false)?;
+            }
+        }
+
+        let size = self
+            .get_focused_workspace()?
+            .find_by_window_id(window)?
+            .data()
+            .geometry();
+        self.focus_window(window, size)
+    }
+
+    /// Go to a specified workspace.
+    fn action_goto(&mut self, workspace_id: WorkspaceId) -> WmResult {
+        let workspace_id = self.resolve_workspace_id(workspace_id)?;
+        self.focus_workspace(workspace_id, true)?;
+
+        Ok(())
+    }
+
+    /// Move the focused floating window and change its size by a number of pixels. The window
+    /// isn't made smaller than `MIN_WIDTH` and `MIN_HEIGHT`.
+    fn action_adjust_floating(&mut self, dx: i16, dy: i16, dw: i16, dh: i16) -> WmResult {
+        let connection = self.connection();
+        let default_colormap = self.default_colormap();
+        let focused_client_id = match self.get_focused_workspace_mut()?.focus.focused_client() {
+            Some(c) => c,
+            None => return Err("clinet focus error: there is no client currently in focus.".into()),
+        };
+        let workspace = self.get_focused_workspace_mut()?;
+        let container = workspace.find_by_window_id_mut(focused_client_id)?;
+
+        let client = match container.data_mut() {
+            ContainerType::Floating(c) => c,
+            _ => return Err(
+                "floating error: the focused window is tiled, float it with toggle_float first."
+                    .into(),
+            ),
+        };
+        let resize = |length: u16, by: i16, min: u16| {
+            if by < 0 {
+                length
+                    .saturating_sub(by.unsigned_abs())
+                    .max(min.min(length))
+            } else {
+                length.saturating_add(by as u16)
+            }
+        };
+        client.geometry = Geometry {
+            x: client.geometry.x.saturating_add(dx),
+            y: client.geometry.y.saturating_add(dy),
+            width: resize(client.geometry.width, dw, MIN_WIDTH),
+            height: resize(client.geometry.height, dh, MIN_HEIGHT),
+        };
+        client.draw_borders(connection.clone(), default_colormap)?;
+        connection.flush()?;
+
+        Ok(())
+    }
+
+    /// Move the focused floating window to the middle of the monitor its middle is on, or of the
+    /// monitor of its workspace, if it's off-screen.
+    fn action_center_floating(&mut self) -> WmResult {
+        let connection = self.connection();
+        let default_colormap = self.default_colormap();
+        let focused_client_id = match self.get_focused_workspace_mut()?.focus.focused_client() {
+            Some(c) => c,
+            None => return Err("clinet focus error: there is no client currently in focus.".into()),
+        };
+        let workspace = self.get_focused_workspace()?;
+        let geometry = match workspace.find_by_window_id(focused_client_id)?.data() {
+            ContainerType::Floating(c) => c.geometry(),
+            _ => {
+                return Err(
+                    "floating error: the focused window is tiled, only floating windows can be centered."
+                        .into(),
+                )
+            }
+        };
+        let (x, y) = geometry.center();
+        let monitor = match self.monitors.iter().find(|m| m.size().contains_point(x, y)) {
+            Some(monitor) => monitor,
+            None => self.monitor_for_workspace(workspace.id)?,
+        };
+        let (center_x, center_y) = monitor.size().center();
+
+        let workspace = self.get_focused_workspace_mut()?;
+        if let ContainerType::Floating(c) = workspace
+            .find_by_window_id_mut(focused_client_id)?
+            .data_mut()
+        {
+            c.geometry.x = (center_x as i32 - c.geometry.width as i32 / 2) as i16;
+            c.geometry.y = (center_y as i32 - c.geometry.height as i32 / 2) as i16;
+            c.draw_borders(connection.clone(), default_colormap)?;
+        }
+        connection.flush()?;
+
+        Ok(())
+    }
+
+    /// Turn "do not disturb" mode on or off for the focused workspace.
+    fn action_toggle_dnd(&mut self) -> WmResult {
+        let workspace = self.get_focused_workspace_mut()?;
+        workspace.toggle_dnd();
+        logm!(
+            LL_NORMAL,
+            "Do not disturb mode on workspace {} is {}.",
+            workspace.id,
+            if workspace.is_dnd() { "on" } else { "off" }
+        );
+        self.invalidate_bars();
+
+        Ok(())
+    }
+
+    /// Focus the workspace after, or before, the focused one among the workspaces of its monitor,
+    /// wrapping around at the ends.
+    fn action_adjacent_workspace(&mut self, forward: bool) -> WmResult {
+        let current = match self.focused_workspace {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        let config = self.config.clone();
+        let skip_cycle = |id| {
+            config
+                .workspace_settings
+                .get(id)
+                .map(|settings| settings.skip_cycle)
+                .unwrap_or(false)
+        };
+
+        if let Some(id) = self
+            .monitor_for_workspace_mut(current)?
+            .adjacent_workspace(current, forward, skip_cycle)
+        {
+            self.focus_workspace(id, true)?;
+        }
+
+        Ok(())
+    }
+
+    /// Turn a workspace number from a `goto` or `move` action into a workspace id.
+    ///
+    /// With `per_monitor_workspaces` enabled, the number is the position of the workspace among the
+    /// workspaces of the focused monitor, otherwise it is the workspace id itself.
+    fn resolve_workspace_id(&self, number: WorkspaceId) -> WmResult<WorkspaceId> {
+        if !self.config.settings.per_monitor_workspaces {
+            return Ok(number);
+        }
+
+        let monitor = self.get_focused_or_first_monitor()?;
+        monitor.nth_workspace(number as usize).ok_or_else(|| {
+            Error::Generic(format!(
+                "workspace error: monitor {} has no workspace number {number}",
+                monitor.id()
+            ))
+        })
+    }
+
+    /// Move the focused window onto a specified workspace.
+    fn action_move(&mut self, workspace_id: WorkspaceId) -> WmResult {
+        // get currently focused client id, retrieve it from its workspace, find the other
+        // workspace and move the client to that second workspace
+        let workspace_id = self.resolve_workspace_id(workspace_id)?;
+        let focused_client = self
+            .get_focused_workspace_mut()?
+            .focus
+            .focused_client()
+            .ok_or_else(|| Error::Generic("move error: no focused client".into()))?;
+        let workspace_id = self
+            .workspace_with_id(workspace_id)
+            .ok_or_else(|| {
+                Error::Generic(format!(
+                    "move error: no workspace with id {workspace_id} found"
+                ))
+            })?
+            .id;
+
+        self.move_window(focused_client, workspace_id)
+    }
+
+    /// Put every workspace back onto the monitor from its settings, spreading the workspaces which
+    /// aren't set to a monitor evenly across the monitors, for example after the workspaces have
+    /// piled up on a single monitor.
+    fn action_rebalance_monitors(&mut self) -> WmResult {
+        let focused_workspace = self.focused_workspace;
+        let focused_client = self
+            .get_focused_workspace()
+            .ok()
+            .and_then(|workspace| workspace.focus.focused_client());
+        let open_workspaces: Vec<(String, WorkspaceId)> = self
+            .monitors
+            .iter()
+            .filter_map(|m| Some((m.name().to_string(), m.get_open_workspace().ok()?)))
+            .collect();
+
+        let workspace_ids: Vec<WorkspaceId> = self.workspaces.iter().map(|w| w.id).collect();
+        let indices = rebalance_workspaces(&self.config, &workspace_ids, &mut self.monitors);
+        self.displaced_workspaces.clear();
+        for (id, index) in workspace_ids.into_iter().zip(indices) {
+            let monitor_id = self.monitors[index].id();
+            let screen = self.monitors[index].usable_size();
+            if let Some(workspace) = self.workspace_with_id_mut(id) {
+                workspace.monitor = monitor_id;
+                workspace.set_screen(screen);
+            }
+        }
+
+        self.show_placed_workspaces(&open_workspaces, focused_workspace, focused_client)
+    }
+
+    /// Move every window whose `WM_CLASS` class is `class` onto the focused workspace, for
+    /// example after the windows of an application got scattered across the workspaces.
+    ///
+    /// Windows which already are on the focused workspace stay where they are.
+    fn action_gather(&mut self, class: &str) -> WmResult {
+        let focused_workspace = self.get_focused_workspace()?.id;
+        let mut windows = Vec::new();
+        for workspace in self.workspaces.iter() {
+            if workspace.id == focused_workspace {
+                continue;
+            }
+            for container in workspace.iter_containers()? {
+                if let Some(wid) = container.data().window_id() {
+                    windows.push(wid);
+                }
+            }
+        }
+
+        for window in windows {
+            if self.window_class(window).as_deref() == Some(class) {
+                self.move_window(window, focused_workspace)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Move the focused window onto the open workspace of the next or previous monitor.
+    ///
+    /// With `follow` set, the other monitor and the moved window are focused afterwards.
+    fn action_move_to_monitor(&mut self, direction: Direction, follow: bool) -> WmResult {
+        let focused_client = self
+            .get_focused_workspace()?
+            .focus
+            .focused_client()
+            .ok_or_else(|| Error::Generic("move error: no focused client".into()))?;
+        let new_index = match self.neighbouring_monitor(direction)? {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+        let workspace_id = self.monitors[new_index].get_open_workspace()?;
+
+        self.move_window(focused_client, workspace_id)?;
+
+        if follow {
+            self.focus_workspace(workspace_id, false)?;
+            let workspace = self.get_focused_workspace_mut()?;
+            workspace.focus.set_focused_client(focused_client);
+            let size = workspace
+                .find_by_window_id(focused_client)?
+                .data()
+                .geometry();
+
+            focus_model::focus_without_pointer(
+                self.connection().as_ref(),
+                &self.config.settings,
+                self.root_window(),
+                Some(focused_client),
+                size,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Move a window from its workspace onto another workspace.
+    ///
+    /// Floating windows keep their position relative to the workspace's screen, tiled windows are
+    /// tiled again by the layout of the new workspace.
+    fn move_window(&mut self, focused_client: u32, workspace_id: WorkspaceId) -> WmResult {
+        let connection = self.connection();
+        let default_colormap = self.default_colormap();
+
+        self.connection().unmap_subwindows(focused_client)?;
+        self.connection().unmap_window(focused_client)?;
+
+        let old_workspace = self
+            .workspace_for_window_mut(focused_client)
+            .ok_or_else(|| {
+                Error::Generic(format!(
+                    "move error: the window {focused_client} is not in any workspace"
+                ))
+            })?;
+        let old_screen = old_workspace.screen();
+        let mut container = old_workspace.remove_and_return_window(focused_client)?;
+        old_workspace.apply_layout(connection.clone(), None, default_colormap)?;
+
+        let new_screen = self
+            .workspace_with_id(workspace_id)
+            .ok_or_else(|| {
+                Error::Generic(format!(
+                    "move error: no workspace with id {workspace_id} found"
+                ))
+            })?
+            .screen();
+        if let ContainerType::Floating(c) = container.data_mut() {
+            c.geometry.x += new_screen.x - old_screen.x;
+            c.geometry.y += new_screen.y - old_screen.y;
+            connection.configure_window(
+                focused_client,
+                &ConfigureWindowAux::new()
+                    .x(c.geometry.x as i32)
+                    .y(c.geometry.y as i32),
+            )?;
+        }
+
+        if let Some(class) = self.window_class(focused_client) {
+            self.last_workspaces.insert(class, workspace_id);
+        }
+
+        let new_windows_first = self.config.settings.new_windows_first();
+        let other_workspace = self.workspace_with_id_mut(workspace_id).unwrap();
+        other_workspace.insert_container(container, new_windows_first)?;
+        other_workspace.apply_layout(connection, None, default_colormap)?;
+
+        let monitor = self.monitor_for_workspace_mut(workspace_id)?;
+        if monitor.get_open_workspace()? == workspace_id {
+            self.connection().map_window(focused_client)?;
+            self.connection().map_subwindows(focused_client)?;
+        }
+
+        Ok(())
+    }
+
+    /// Change to a specified layout.
+    fn action_change_layout(&mut self, layout: String) -> WmResult {
+        let connection = self.connection();
+        let default_colormap = self.default_colormap();
+
+        self.get_focused_workspace_mut()?.change_layout(layout)?;
+        self.get_focused_workspace_mut()?
+            .apply_layout(connection, None, default_colormap)?;
+        self.update_layout_property()?;
+
+        Ok(())
+    }
+
+    /// Switch to the next layout.
+    fn action_cycle_layout(&mut self) -> WmResult {
+        let default_colormap = self.default_colormap();
+        let connection = self.connection();
+        let workspace = self.get_focused_workspace_mut()?;
+
+        workspace.cycle_layout()?;
+        workspace.apply_layout(connection, None, default_colormap)?;
+        self.update_layout_property()?;
+        Ok(())
+    }
+
+    /// Show only the focused client on the focused workspace, or go back to the previous layout.
+    fn action_focus_mode(&mut self) -> WmResult {
+        let default_colormap = self.default_colormap();
+        let connection = self.connection();
+        let workspace = self.get_focused_workspace_mut()?;
+
+        workspace.toggle_focus_mode();
+        workspace.apply_layout(connection, None, default_colormap)?;
+        Ok(())
+    }
+
+    /// Make the focused window float, or if floating, tile it.
+    fn action_toggle_float(&mut self) -> WmResult {
+        let connection = self.connection();
+        let focused_client_id = match self.get_focused_workspace_mut()?.focus.focused_client() {
+            Some(c) => c,
+            None => return Err("clinet focus error: there is no client currently in focus.".into()),
+        };
+        let default_colormap = self.default_colormap();
+        let atoms = self.atoms();
+        let keep_tiled_slot = self.config.settings.keep_tiled_slot;
+        let workspace = self.get_focused_workspace_mut()?;
+
+        let container = workspace.find_by_window_id_mut(focused_client_id)?;
+        let window = container.data().window_id().unwrap();
+
+        if container.is_in_layout() {
+            // TODO: should be taking WM_SIZE_HINTS from some properties cache.
+            if let Ok(PropertyReturnValue::WmSizeHints(hints)) = atoms
+                .get("WM_NORMAL_HINTS")
+                .unwrap()
+                .get_property(
+                    container.data().window_id().unwrap(),
+                    connection.clone(),
+                    None,
+                )?
+                .first()
+                .ok_or_else(|| {
+                    Error::Generic("NORMAL_HINTS not set for the focused window.".into())
+                })
+            {
+                let new_geom = hints.into();
+                logm!(
+                    LL_FULL,
+                    "WM_NORMAL_HINTS for window {window} are: {:?}",
+                    hints,
+                );
+                container.data_mut().set_geometry(new_geom);
+                connection.configure_window(
+                    window,
+                    &ConfigureWindowAux::new()
+                        .x(new_geom.x as i32)
+                        .y(new_geom.y as i32)
+                        .width(new_geom.width as u32)
+                        .height(new_geom.height as u32),
+                )?;
+            }
+            workspace.float_window(window)?;
+        } else {
+            workspace.tile_window(window, keep_tiled_slot)?;
+            connection.configure_window(
+                focused_client_id,
+                &ConfigureWindowAux::new().stack_mode(StackMode::BELOW),
+            )?;
+        }
+
+        workspace.apply_layout(connection.clone(), None, default_colormap)?;
+        connection.set_input_focus(InputFocus::PARENT, focused_client_id, CURRENT_TIME)?;
+        connection.flush()?;
+
+        Ok(())
+    }
+
+    /// Raise or lower the focused window, if it's floating. Windows kept above or below the others
+    /// stay where they are.
+    fn action_restack_floating(&mut self, stack_mode: StackMode) -> WmResult {
+        let workspace = self.get_focused_workspace()?;
+        let window = match workspace.focus.focused_client() {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+        if !workspace.find_by_window_id(window)?.is_floating() {
+            return Ok(());
+        }
+
+        let connection = self.connection();
+        connection.configure_window(window, &ConfigureWindowAux::new().stack_mode(stack_mode))?;
+        self.restack()?;
+        connection.set_input_focus(InputFocus::PARENT, window, CURRENT_TIME)?;
+        connection.flush()?;
+
+        Ok(())
+    }
+
+    /// Ask the focused window to close and force it to, if it doesn't close in time.
+    ///
+    /// `WM_DELETE_WINDOW` is sent first, if the window supports it. The remaining steps are taken
+    /// by `escalate_pending_kills`, once `kill_timeout` passes.
+    fn action_kill_unresponsive(&mut self) -> WmResult {
+        let window = self
+            .get_focused_workspace()?
+            .focus
+            .focused_client()
+            .ok_or_else(|| Error::Generic("kill error: no focused client".into()))?;
+        if self.pending_kills.iter().any(|kill| kill.window == window) {
+            return Ok(());
+        }
+
+        let process_id = self
+            .workspace_for_window(window)
+            .and_then(|workspace| workspace.find_by_window_id(window).ok())
+            .and_then(|container| container.data().process_id());
+        let timeout = Duration::from_millis(self.config.settings.kill_timeout as u64);
+
+        let deadline = if self.send_delete_window(window)? {
+            logm!(
+                LL_NORMAL,
+                "Asked window {window} to close using WM_DELETE_WINDOW, waiting {}ms.",
+                timeout.as_millis()
+            );
+            self.wake_after(timeout)?;
+            Instant::now() + timeout
+        } else {
+            logm!(
+                LL_NORMAL,
+                "Window {window} doesn't support WM_DELETE_WINDOW, escalating right away.",
+            );
+            Instant::now()
+        };
+
+        self.pending_kills.push(PendingKill {
+            window,
+            process_id,
+            step: KillStep::Delete,
+            deadline,
+        });
+
+        Ok(())
+    }
+
+    /// Send `WM_DELETE_WINDOW` to a window, returning whether the window supports it.
+    fn send_delete_window(&self, window: u32) -> WmResult<bool> {
+        let (wm_protocols, protocols) = match self.atoms.get("WM_PROTOCOLS") {
+            Some(protocols) => (
+                protocols.id(),
+                protocols.get_property(window, self.connection(), None)?,
+            ),
+            None => return Ok(false),
+        };
+        let delete = match self.atoms.get("WM_DELETE_WINDOW") {
+            Some(delete) => delete.id(),
+            None => return Ok(false),
+        };
+
+        if !atoms::supports_protocol(&protocols, delete) {
+            return Ok(false);
+        }
+
+        self.connection().send_event(
+            false,
+            window,
+            EventMask::NO_EVENT,
+            atoms::protocol_message(window, wm_protocols, delete),
+        )?;
+        self.connection.flush()?;
+
+        Ok(true)
+    }
+
+    /// Wake the event loop up after `timeout`, by sending an empty client message to the root
+    /// window.
+    fn wake_after(&self, timeout: Duration) -> WmResult {
+        let connection = self.connection();
+        let root = self.root_window();
+
+        std::thread::spawn(move || {
+            std::thread::sleep(timeout);
+            let event = ClientMessageEvent::new(32, root, AtomEnum::NONE, [0u32; 5]);
+            // the main loop takes care of reporting a lost connection
+            if connection
+                .send_event(false, root, EventMask::SUBSTRUCTURE_REDIRECT, event)
+                .is_ok()
+            {
+                let _ = connection.flush();
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Handle a bell, by flashing the border of the focused window with the `visual_bell` color,
+    /// if it is set. The border color is restored by `end_visual_bell`.
+    pub fn handle_bell(&mut self) -> WmResult {
+        if self.config.settings.visual_bell.is_empty() {
+            return Ok(());
+        }
+        let window = match self
+            .get_focused_workspace()
+            .ok()
+            .and_then(|workspace| workspace.focus.focused_client())
+        {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+
+        // a bell ringing on another window ends the flash of the previous one right away
+        if let Some((flashing, _)) = self.visual_bell {
+            if flashing != window {
+                self.restore_border(flashing)?;
+            }
+        }
+        self.connection().change_window_attributes(
+            window,
+            &ChangeWindowAttributesAux::new()
+                .border_pixel(convert_color(&self.config.settings.visual_bell)),
+        )?;
+
+        let duration = Duration::from_millis(self.config.settings.visual_bell_duration as u64);
+        self.visual_bell = Some((window, Instant::now() + duration));
+        self.wake_after(duration)
+    }
+
+    /// Restore the border color of the window flashed by the visual bell, once its time has come.
+    pub fn end_visual_bell(&mut self) -> WmResult {
+        match self.visual_bell {
+            Some((window, until)) if Instant::now() >= until => {
+                self.visual_bell = None;
+                self.restore_border(window)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Draw the border of a window again, with the colors from the settings. Windows which have
+    /// been closed in the meantime are ignored.
+    fn restore_border(&self, window: u32) -> WmResult {
+        let container = match self.workspace_for_window(window) {
+            Some(workspace) => workspace.find_by_window_id(window)?,
+            None => return Ok(()),
+        };
+
+        match container.data() {
+            ContainerType::InLayout(c) | ContainerType::Floating(c) => {
+                c.draw_borders(self.connection(), self.default_colormap())
+            }
+            ContainerType::Empty(_) => Ok(()),
+        }
+    }
+
+    /// Take the next step in closing the windows of the `kill_unresponsive` action, whose time
+    /// has come. Windows which have been closed in the meantime are forgotten.
+    pub fn escalate_pending_kills(&mut self) -> WmResult {
+        if self.pending_kills.is_empty() {
+            return Ok(());
+        }
+
+        let timeout = Duration::from_millis(self.config.settings.kill_timeout as u64);
+        let now = Instant::now();
+        let mut pending_kills = std::mem::take(&mut self.pending_kills);
+        pending_kills.retain(|kill| self.workspace_for_window(kill.window).is_some());
+
+        for kill in pending_kills.iter_mut() {
+            let window = kill.window;
+            match (kill.escalate(now, timeout), kill.process_id) {
+                (Some(KillStep::Terminate), Some(pid)) => {
+                    logm!(
+                        LL_NORMAL,
+                        "Window {window} didn't close, sending SIGTERM to process {pid}.",
+                    );
+                    signal_process(pid, libc::SIGTERM)?;
+                    self.wake_after(timeout)?;
+                }
+                (Some(KillStep::Kill), Some(pid)) => {
+                    logm!(
+                        LL_NORMAL,
+                        "Window {window} didn't close, sending SIGKILL to process {pid}.",
+                    );
+                    signal_process(pid, libc::SIGKILL)?;
+                }
+                (Some(KillStep::Kill), None) => {
+                    logm!(
+                        LL_NORMAL,
+                        "Window {window} didn't close and its process is unknown, disconnecting its client.",
+                    );
+                    self.connection().kill_client(window)?;
                 }
+                _ => (),
             }
         }
 
+        pending_kills.retain(|kill| kill.step != KillStep::Kill);
+        self.pending_kills = pending_kills;
+
         Ok(())
     }
 
-    /// Go to a specified workspace.
-    fn action_goto(&mut self, workspace_id: WorkspaceId) -> WmResult {
-        self.focus_workspace(workspace_id, true)?;
+    /// Grow or shrink the master window of the focused workspace by `delta` of the screen's width.
+    fn action_change_master_ratio(&mut self, delta: f32) -> WmResult {
+        let connection = self.connection();
+        let default_colormap = self.default_colormap();
+        let workspace = self.get_focused_workspace_mut()?;
+
+        workspace.set_master_ratio(workspace.master_ratio() + delta);
+        workspace.apply_layout(connection, None, default_colormap)?;
 
         Ok(())
     }
 
-    /// Move the focused window onto a specified workspace.
-    fn action_move(&mut self, workspace_id: WorkspaceId) -> WmResult {
-        // get currently focused client id, retrieve it from its workspace, find the other
-        // workspace and move the client to that second workspace
+    /// Grow or shrink the master area of the focused workspace by a number of windows.
+    fn action_change_nmaster(&mut self, delta: i32) -> WmResult {
         let connection = self.connection();
-        let focused_client = self
-            .get_focused_workspace_mut()?
-            .focus
-            .focused_client()
-            .ok_or_else(|| Error::Generic("move error: no focused client".into()))?;
-        let workspace_id = self
-            .workspace_with_id(workspace_id)
-            .ok_or_else(|| {
-                Error::Generic(format!(
-                    "move error: no workspace with id {workspace_id} found"
-                ))
-            })?
-            .id;
         let default_colormap = self.default_colormap();
+        let workspace = self.get_focused_workspace_mut()?;
 
-        self.connection().unmap_subwindows(focused_client)?;
-        self.connection().unmap_window(focused_client)?;
+        let nmaster = (workspace.nmaster() as i64 + delta as i64).max(0) as usize;
+        workspace.set_nmaster(nmaster);
+        workspace.apply_layout(connection, None, default_colormap)?;
 
-        let focused_workspace = self.get_focused_workspace_mut()?;
-        let container = focused_workspace.remove_and_return_window(focused_client)?;
-        self.get_focused_workspace_mut()?.apply_layout(
-            connection.clone(),
-            None,
-            default_colormap,
-        )?;
+        Ok(())
+    }
 
-        let other_workspace = self.workspace_with_id_mut(workspace_id).unwrap();
-        other_workspace.insert_container(container)?;
-        other_workspace.apply_layout(connection, None, default_colormap)?;
+    /// Switch between the configured gaps and no gaps at all, for every client. Borders are kept
+    /// as they are.
+    fn action_toggle_gaps(&mut self) -> WmResult {
+        self.gaps_enabled = !self.gaps_enabled;
+        let gaps_enabled = self.gaps_enabled;
+        let config = self.config.clone();
 
-        let monitor = self.monitor_for_workspace_mut(workspace_id)?;
-        if monitor.get_open_workspace()? == workspace_id {
-            self.connection().map_window(focused_client)?;
-            self.connection().map_subwindows(focused_client)?;
+        for workspace in self.workspaces.iter_mut() {
+            for container in workspace.containers_mut().iter_mut() {
+                if let ContainerType::InLayout(client) | ContainerType::Floating(client) =
+                    container.data_mut()
+                {
+                    client.change_config(&config);
+                    if !gaps_enabled {
+                        client.attributes = client.attributes.without_gaps();
+                    }
+                }
+            }
+        }
+
+        // the outer gap is taken out of the workspaces' screens
+        self.fit_all_workspaces()
+    }
+
+    /// Switch to the next keyboard layout from the `keyboard_layouts` setting.
+    ///
+    /// The other layouts are passed to `setxkbmap` as well, after the active one, so that keys
+    /// which only exist in them, such as latin letters used by keybinds, keep on working.
+    fn action_switch_layout(&mut self) -> WmResult {
+        let layouts = self.config.settings.keyboard_layouts();
+        if layouts.is_empty() {
+            return Err("keyboard layout error: the keyboard_layouts setting is empty.".into());
         }
 
+        let next = (self.keyboard_layout + 1) % layouts.len();
+        let mut rotated = layouts.clone();
+        rotated.rotate_left(next);
+
+        let status = std::process::Command::new("setxkbmap")
+            .args(["-layout", &rotated.join(",")])
+            .status()?;
+        if !status.success() {
+            return Err(format!(
+                "keyboard layout error: setxkbmap failed to switch to layout {}, {status}",
+                layouts[next]
+            )
+            .into());
+        }
+
+        logm!(LL_NORMAL, "Switched keyboard layout to {}", layouts[next]);
+        self.keyboard_layout = next;
+
         Ok(())
     }
 
-    /// Change to a specified layout.
-    fn action_change_layout(&mut self, layout: String) -> WmResult {
-        let connection = self.connection();
-        let default_colormap = self.default_colormap();
+    /// Make the focused window sticky, or if sticky, stop it from being sticky.
+    fn action_toggle_sticky(&mut self) -> WmResult {
+        let focused_client_id = match self.get_focused_workspace_mut()?.focus.focused_client() {
+            Some(c) => c,
+            None => return Err("clinet focus error: there is no client currently in focus.".into()),
+        };
 
-        self.get_focused_workspace_mut()?.change_layout(layout)?;
-        self.get_focused_workspace_mut()?
-            .apply_layout(connection, None, default_colormap)?;
+        let sticky = self.sticky_windows.contains(&focused_client_id);
+        self.set_window_sticky(focused_client_id, !sticky)?;
+        self.restack()?;
+        self.connection().flush()?;
 
         Ok(())
     }
 
-    /// Switch to the next layout.
-    fn action_cycle_layout(&mut self) -> WmResult {
-        let default_colormap = self.default_colormap();
-        let connection = self.connection();
-        let workspace = self.get_focused_workspace_mut()?;
+    /// Pin the focused window to a corner of the screen, or unpin it.
+    ///
+    /// Pinned windows are floating and kept above all the other windows.
+    fn action_pin(&mut self, corner: Option<Corner>) -> WmResult {
+        let focused_client_id = match self.get_focused_workspace_mut()?.focus.focused_client() {
+            Some(c) => c,
+            None => return Err("clinet focus error: there is no client currently in focus.".into()),
+        };
+
+        self.pinned_windows.retain(|(w, _)| *w != focused_client_id);
+        if let Some(corner) = corner {
+            self.pinned_windows.push((focused_client_id, corner));
+        }
+
+        self.set_window_above(focused_client_id, corner.is_some())?;
+        self.restack()?;
+        self.repin_windows()?;
+        self.connection().flush()?;
 
-        workspace.cycle_layout()?;
-        workspace.apply_layout(connection, None, default_colormap)?;
         Ok(())
     }
 
-    /// Make the focused window float, or if floating, tile it.
-    fn action_toggle_float(&mut self) -> WmResult {
+    /// Float the focused window, size it to fractions of the workspace's screen and center it.
+    fn action_float_resize(&mut self, width_fraction: f32, height_fraction: f32) -> WmResult {
         let connection = self.connection();
+        let default_colormap = self.default_colormap();
         let focused_client_id = match self.get_focused_workspace_mut()?.focus.focused_client() {
             Some(c) => c,
             None => return Err("clinet focus error: there is no client currently in focus.".into()),
         };
-        let default_colormap = self.default_colormap();
-        let atoms = self.atoms();
         let workspace = self.get_focused_workspace_mut()?;
+        let screen = workspace.screen();
+
+        let width = ((screen.width as f32 * width_fraction) as u16)
+            .max(MIN_WIDTH)
+            .min(screen.width);
+        let height = ((screen.height as f32 * height_fraction) as u16)
+            .max(MIN_HEIGHT)
+            .min(screen.height);
+        let geometry = Geometry {
+            x: screen.x + ((screen.width - width) / 2) as i16,
+            y: screen.y + ((screen.height - height) / 2) as i16,
+            width,
+            height,
+        };
 
+        if workspace
+            .find_by_window_id(focused_client_id)?
+            .is_in_layout()
+        {
+            workspace.float_window(focused_client_id)?;
+        }
         let container = workspace.find_by_window_id_mut(focused_client_id)?;
-        let window = container.data().window_id().unwrap();
-
-        if container.is_in_layout() {
-            // TODO: should be taking WM_SIZE_HINTS from some properties cache.
-            if let Ok(PropertyReturnValue::WmSizeHints(hints)) = atoms
-                .get("WM_NORMAL_HINTS")
-                .unwrap()
-                .get_property(
-                    container.data().window_id().unwrap(),
-                    connection.clone(),
-                    None,
-                )?
-                .first()
-                .ok_or_else(|| {
-                    Error::Generic("NORMAL_HINTS not set for the focused window.".into())
-                })
-            {
-                let new_geom = hints.into();
-                logm!(
-                    LL_FULL,
-                    "WM_NORMAL_HINTS for window {window} are: {:?}",
-                    hints,
-                );
-                container.data_mut().set_geometry(new_geom);
-                connection.configure_window(
-                    window,
-                    &ConfigureWindowAux::new()
-                        .x(new_geom.x as i32)
-                        .y(new_geom.y as i32)
-                        .width(new_geom.width as u32)
-                        .height(new_geom.height as u32),
-                )?;
-            }
-            container.change_to_floating()?
-        } else {
-            container.change_to_layout()?;
-            connection.configure_window(
-                focused_client_id,
-                &ConfigureWindowAux::new().stack_mode(StackMode::BELOW),
-            )?;
+        if let ContainerType::Floating(c) = container.data_mut() {
+            c.geometry = geometry;
+            c.draw_borders(connection.clone(), default_colormap)?;
         }
-
         workspace.apply_layout(connection.clone(), None, default_colormap)?;
-        connection.set_input_focus(InputFocus::PARENT, focused_client_id, CURRENT_TIME)?;
+
+        connection.configure_window(
+            focused_client_id,
+            &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+        )?;
+        self.restack()?;
         connection.flush()?;
 
         Ok(())
@@ -1426,12 +4607,13 @@ impl State {
             let container_id = container.id();
 
             let container_to_focus_option = match direction {
-                Direction::Next => Some(workspace.next_container(*container_id)),
-                Direction::Previous => Some(workspace.previous_container(*container_id)),
+                Direction::Next => Some(workspace.next_container(*container_id)?),
+                Direction::Previous => Some(workspace.previous_container(*container_id)?),
+                _ => workspace.nearest_container(*container_id, direction)?,
             };
 
             if let Some(container_to_focus) = container_to_focus_option {
-                let swap_with = container_to_focus?.id();
+                let swap_with = container_to_focus.id();
                 workspace.swap(*container_id, *swap_with)?;
                 workspace.apply_layout(connection, None, default_colormap)?;
             }
@@ -1440,36 +4622,198 @@ impl State {
         Ok(())
     }
 
+    /// Change the height of the bar on the focused monitor by `delta` pixels.
+    ///
+    /// The height never goes below the height of the bar's text. The workspaces on the monitor
+    /// are resized to fit the new bar.
+    fn action_bar_height(&mut self, delta: i32) -> WmResult {
+        let monitor = self.get_focused_or_first_monitor()?;
+        let monitor_id = monitor.id();
+        let monitor_geometry = monitor.size();
+        let connection = self.connection();
+
+        let bar = self
+            .bars
+            .iter_mut()
+            .find(|bar| bar.monitor() + 1 == monitor_id)
+            .ok_or_else(|| {
+                Error::Generic(format!("Status bar error: No bar on monitor {monitor_id}."))
+            })?;
+        let window_id = bar.window_id()?;
+        let mut geometry = bar.geometry()?;
+        let min_height = bar.get_height()?.max(1) as i32;
+        let height = (geometry.height as i32 + delta)
+            .max(min_height)
+            .min(monitor_geometry.height as i32);
+
+        geometry.height = height as u16;
+        if bar.settings()?.position == BarPosition::Bottom {
+            geometry.y = monitor_geometry.y + monitor_geometry.height as i16 - height as i16;
+        }
+
+        connection.configure_window(
+            window_id,
+            &ConfigureWindowAux::new()
+                .height(height as u32)
+                .y(geometry.y as i32),
+        )?;
+        bar.surface()?.set_size(geometry.width as i32, height)?;
+        bar.set_geometry(geometry);
+
+        self.fit_workspaces(monitor_id)?;
+        self.update_bars()?;
+        connection.flush()?;
+
+        Ok(())
+    }
+
+    /// Resize every workspace on a monitor to fit between the monitor's bars and the space
+    /// reserved by docks.
+    fn fit_workspaces(&mut self, monitor_id: u32) -> WmResult {
+        let screen = match self.workspace_area(monitor_id)? {
+            Some(screen) => screen,
+            None => return Ok(()),
+        };
+
+        let mut to_layout = Vec::new();
+        for workspace in self.workspaces.iter_mut() {
+            if workspace.monitor == monitor_id {
+                workspace.set_screen(screen);
+                to_layout.push(workspace.id);
+            }
+        }
+        for workspace_id in to_layout {
+            self.layout_workspace(workspace_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Resize the workspaces of every monitor, see [`State::fit_workspaces`].
+    pub fn fit_all_workspaces(&mut self) -> WmResult {
+        let monitor_ids: Vec<u32> = self.monitors.iter().map(|m| m.id()).collect();
+        for monitor_id in monitor_ids {
+            self.fit_workspaces(monitor_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Return the area of a monitor the workspaces on it can use, which is what's left after the
+    /// monitor's padding, its bars, the space reserved by docks and the outer gap are taken away.
+    fn workspace_area(&self, monitor_id: u32) -> WmResult<Option<Geometry>> {
+        let monitor_geometry = match self.monitors.iter().find(|m| m.id() == monitor_id) {
+            Some(monitor) => monitor.usable_size(),
+            None => return Ok(None),
+        };
+        let bar_geometries = self
+            .bars
+            .iter()
+            .filter(|bar| bar.monitor() + 1 == monitor_id)
+            .map(|bar| bar.geometry())
+            .collect::<WmResult<Vec<Geometry>>>()?;
+        let (root_width, root_height) = (
+            self.root_screen().width_in_pixels,
+            self.root_screen().height_in_pixels,
+        );
+
+        let screen = bar_geometries
+            .iter()
+            .fold(monitor_geometry, |screen, bar| screen.minus_bar(*bar));
+        let screen = self.struts.iter().fold(screen, |screen, (_, strut)| {
+            screen.minus_strut(strut, root_width, root_height)
+        });
+
+        if self.gaps_enabled {
+            Ok(Some(screen.shrunk_by(self.config.settings.outer_gap_size)))
+        } else {
+            Ok(Some(screen))
+        }
+    }
+
+    /// Read the space a window reserves on the edges of the screen, if it does.
+    fn window_strut(&self, window: u32) -> Option<Strut> {
+        ["_NET_WM_STRUT_PARTIAL", "_NET_WM_STRUT"]
+            .into_iter()
+            .filter_map(|name| self.atoms.get(name))
+            .find_map(|atom| {
+                let values: Vec<u32> = atom
+                    .get_property(window, self.connection(), None)
+                    .ok()?
+                    .into_iter()
+                    .filter_map(|value| value.try_into().ok())
+                    .collect();
+                Strut::from_values(&values)
+            })
+    }
+
+    /// Stop reserving the space of a dock window, after it has been unmapped or destroyed.
+    pub fn remove_strut(&mut self, window: u32) -> WmResult {
+        let count = self.struts.len();
+        self.struts.retain(|(dock, _)| *dock != window);
+        if count == self.struts.len() {
+            return Ok(());
+        }
+
+        logm!(LL_NORMAL, "Dock window {window} no longer reserves space.");
+        let monitor_ids: Vec<u32> = self.monitors.iter().map(|m| m.id()).collect();
+        for monitor_id in monitor_ids {
+            self.fit_workspaces(monitor_id)?;
+        }
+
+        Ok(())
+    }
+
     /// Reload the window manager's config file.
     ///
     /// This reloads all the keybinds, window manager settings and bar settings.
     fn action_reload_config(&mut self) -> WmResult {
-        // TODO: Take a look at how monitor changes should be handled
         let path = &self.config.path;
-        let config = ConfigParser::parse_with_path(path.as_str())?;
+        let config =
+            ConfigParser::parse_with_profile(path.as_str(), self.config.profile.as_deref())?;
+        for warning in &config.warnings {
+            errm!("config warning: {warning}");
+        }
+        for duplicate in config.keybinds.duplicates().unwrap_or_default() {
+            errm!("config warning: {duplicate}");
+        }
+        // remember the focus, so that it can be restored once everything is set up again
+        let focused_workspace = self.focused_workspace;
+        let focused_client = self
+            .get_focused_workspace()
+            .ok()
+            .and_then(|workspace| workspace.focus.focused_client());
         let mask: u32 =
             (EventMask::BUTTON_PRESS | EventMask::BUTTON_RELEASE | EventMask::BUTTON_MOTION).into();
         self.config = Rc::new(config);
         let connection = self.connection();
         let root_window = self.root_window();
 
-        let geoms: Vec<(u32, Geometry)> = self
-            .monitors
-            .iter()
-            .map(|monitor| (monitor.id(), monitor.size()))
-            .collect();
-
+        // the padding of the monitors may have changed
+        for monitor in self.monitors.iter_mut() {
+            let padding = self
+                .config
+                .monitor_settings
+                .get(monitor.name())
+                .map(|settings| settings.padding)
+                .unwrap_or_default();
+            monitor.set_padding(padding);
+        }
         let windows = connection.query_tree(root_window)?.reply()?.children;
         let mut to_remove = Vec::new();
+        // windows whose buttons have been grabbed again, which need the click to focus grab too
+        let mut regrabbed = Vec::new();
 
         // redo keybinds
         self.init_keyman(self.config.keybinds.clone())?;
-        // regrab keys for all clients, reapply client attributes and reapply layouts
+        let frame_extents = self.atoms.get("_NET_FRAME_EXTENTS").unwrap().id();
+        // regrab keys for all clients and reapply client attributes
         for workspace in self.workspaces.iter_mut() {
-            for geom in geoms.iter() {
-                if workspace.monitor == geom.0 {
-                    workspace.set_screen(geom.1)
-                }
+            workspace.set_master_fill_when_alone(self.config.settings.master_fill_when_alone);
+            if let Some(settings) = self.config.workspace_settings.get(workspace.id) {
+                workspace.set_stack_orientation(StackOrientation::try_from(
+                    settings.stack_orientation.as_str(),
+                )?);
             }
             let id = workspace.id;
             for container in workspace.containers_mut().iter_mut() {
@@ -1501,32 +4845,248 @@ impl State {
                         ButtonIndex::M3,
                         self.floating_modifier,
                     )?;
+                    regrabbed.push(window_id);
                 }
-                match container.data_mut() {
-                    ContainerType::InLayout(client) => client.change_config(&self.config),
-                    ContainerType::Floating(client) => client.change_config(&self.config),
-
-                    _ => (),
+                if let ContainerType::InLayout(client) | ContainerType::Floating(client) =
+                    container.data_mut()
+                {
+                    client.change_config(&self.config);
+                    if !self.gaps_enabled {
+                        client.attributes = client.attributes.without_gaps();
+                    }
+                    atoms::set_frame_extents(
+                        connection.clone(),
+                        client.window_id(),
+                        frame_extents,
+                        client.border_width(),
+                    )?;
                 }
             }
-
-            workspace.apply_layout(connection.clone(), None, self.default_colormap)?;
         }
 
+        for window in regrabbed {
+            self.grab_click_to_focus(window)?;
+        }
         for each in to_remove {
             self.workspace_with_id_mut(each.0)
                 .unwrap()
                 .remove_window(each.1)?;
         }
 
-        // reapply bar settings
-        for bar_window in self.bar_windows.iter() {
-            connection.destroy_window(*bar_window)?;
+        // reapply bar settings, then fit the workspaces between the bars, docks and the outer gap
+        self.recreate_bars()?;
+        self.fit_all_workspaces()?;
+        self.update_backgrounds()?;
+
+        self.restore_focus(focused_workspace, focused_client)
+    }
+
+    /// Focus a workspace and a window again, after they might have lost the focus, for example by
+    /// reloading the config. Workspaces and windows which no longer exist are skipped.
+    fn restore_focus(&mut self, workspace: Option<WorkspaceId>, window: Option<u32>) -> WmResult {
+        let (workspace, window) = focus_to_restore(&self.workspaces, workspace, window);
+        if let Some(id) = workspace {
+            if self.focused_workspace != Some(id) {
+                self.focus_workspace(id, false)?;
+            }
         }
 
-        self.bar_windows.clear();
-        self.setup_bars()?;
+        if let Some(window) = window {
+            if let Some(workspace) = self.workspace_for_window_mut(window) {
+                workspace.focus.set_focused_client(window);
+            }
+            self.connection()
+                .set_input_focus(InputFocus::PARENT, window, CURRENT_TIME)?;
+        }
 
-        Ok(())
+        self.restack()
+    }
+}
+
+/// Return the workspace and the window which were focused before a config reload or a change of
+/// the monitors, and which are still there to be focused again.
+fn focus_to_restore(
+    workspaces: &[Workspace],
+    workspace: Option<WorkspaceId>,
+    window: Option<u32>,
+) -> (Option<WorkspaceId>, Option<u32>) {
+    let workspace = workspace.filter(|id| workspaces.iter().any(|ws| ws.id == *id));
+    let window = window.filter(|w| workspaces.iter().any(|ws| ws.contains_window(*w)));
+
+    (workspace, window)
+}
+
+/// Run a command using `/bin/bash -c`, returning the started process.
+///
+/// The whole command is passed to the shell as a single argument, so that the shell takes care of
+/// its quoting.
+fn spawn_command(command: &str) -> WmResult<std::process::Child> {
+    let child = std::process::Command::new("/bin/bash")
+        .arg("-c")
+        .arg(command)
+        .spawn()?;
+
+    Ok(child)
+}
+
+/// Send a signal to a process.
+fn signal_process(pid: u32, signal: i32) -> WmResult {
+    if unsafe { libc::kill(pid as libc::pid_t, signal) } == -1 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(())
+}
+
+/// Return the id of the parent process of a process, read from `/proc/<pid>/stat`.
+fn parent_process_id(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // the second field is the process name in parentheses, which can contain spaces
+    let (_, rest) = stat.rsplit_once(')')?;
+    let ppid = rest.split_whitespace().nth(1)?.parse::<u32>().ok()?;
+
+    if ppid == 0 {
+        None
+    } else {
+        Some(ppid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slide_frames() {
+        let start = Instant::now();
+        let slide = Slide {
+            workspace_id: 1,
+            windows: Vec::new(),
+            offset: -1920,
+            start,
+            duration: Duration::from_millis(160),
+        };
+
+        assert_eq!(slide.frame(start), 0);
+        assert_eq!(slide.frame(start + Duration::from_millis(20)), 1);
+        assert_eq!(slide.frame(start + Duration::from_millis(159)), 7);
+        assert_eq!(
+            slide.frame(start + Duration::from_millis(160)),
+            ANIMATION_FRAMES
+        );
+    }
+
+    #[test]
+    fn reload_keeps_focus() {
+        let config = Config::default();
+        let screen = Geometry {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let mut workspaces: Workspaces = (1..=3)
+            .map(|id| Workspace::new(id.to_string(), id, 0, 0, screen, 1))
+            .collect();
+        for window in [10u32, 11] {
+            let client = Client::new_without_process_id(window, screen, window as u64, &config);
+            workspaces[1].insert_client(client, ContainerTypeMask::TILING, true);
+        }
+
+        // workspace 2 and its window 11 were focused before the reload
+        assert_eq!(
+            focus_to_restore(&workspaces, Some(2), Some(11)),
+            (Some(2), Some(11))
+        );
+
+        // a window which went away during the reload isn't focused again
+        workspaces[1].remove_window(11).unwrap();
+        assert_eq!(
+            focus_to_restore(&workspaces, Some(2), Some(11)),
+            (Some(2), None)
+        );
+        assert_eq!(
+            focus_to_restore(&workspaces, Some(4), Some(10)),
+            (None, Some(10))
+        );
+        assert_eq!(focus_to_restore(&workspaces, None, None), (None, None));
+    }
+
+    #[test]
+    fn spawn_command_quoting() {
+        let path = std::env::temp_dir().join(format!("crubwm-spawn-{}", std::process::id()));
+        let command = format!(
+            "printf '%s|' 'hi  there' \"a b\" c > '{}'",
+            path.to_str().unwrap()
+        );
+
+        assert!(spawn_command(&command).unwrap().wait().unwrap().success());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hi  there|a b|c|");
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn pending_spawn_lifecycle() {
+        let start = Instant::now();
+        let mut spawns = PendingSpawns::default();
+        spawns.add(100, SpawnTarget::Workspace(3), start);
+        spawns.add(200, SpawnTarget::Workspace(4), start);
+
+        // the window's process is a child of the started process
+        assert_eq!(
+            spawns.take([150, 100], start),
+            Some(SpawnTarget::Workspace(3))
+        );
+        // the spawn is only used for the first window
+        assert_eq!(spawns.take([100], start), None);
+        assert_eq!(spawns.take([300], start), None);
+
+        // stale spawns are dropped
+        assert_eq!(spawns.take([200], start + SPAWN_TIMEOUT), None);
+        assert!(spawns.0.is_empty());
+    }
+
+    #[test]
+    fn pending_kill_escalation() {
+        let timeout = Duration::from_millis(100);
+        let start = Instant::now();
+        let mut kill = PendingKill {
+            window: 1,
+            process_id: Some(42),
+            step: KillStep::Delete,
+            deadline: start + timeout,
+        };
+
+        assert_eq!(kill.escalate(start, timeout), None);
+        assert_eq!(
+            kill.escalate(start + timeout, timeout),
+            Some(KillStep::Terminate)
+        );
+        assert_eq!(kill.escalate(start + timeout, timeout), None);
+        assert_eq!(
+            kill.escalate(start + timeout * 2, timeout),
+            Some(KillStep::Kill)
+        );
+        assert_eq!(kill.escalate(start + timeout * 3, timeout), None);
+
+        let mut kill = PendingKill {
+            window: 2,
+            process_id: None,
+            step: KillStep::Delete,
+            deadline: start,
+        };
+        assert_eq!(kill.escalate(start, timeout), Some(KillStep::Kill));
+    }
+
+    #[test]
+    fn test_parent_process_id() {
+        let pid = std::process::id();
+
+        assert_eq!(
+            parent_process_id(pid),
+            Some(std::os::unix::process::parent_id())
+        );
+        assert_eq!(parent_process_id(0), None);
     }
 }
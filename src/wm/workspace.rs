@@ -1,8 +1,12 @@
 use std::sync::Arc;
 
+use super::actions::Direction;
 use super::focus_stack::FocusStack;
 use super::geometry::Geometry;
-use super::layouts::{Layout, LayoutType};
+use super::layouts::{
+    Layout, LayoutType, MasterSettings, StackOrientation, DEFAULT_MASTER_RATIO, MAX_MASTER_RATIO,
+    MIN_MASTER_RATIO,
+};
 use super::monitors::MonitorId;
 use crate::config::Config;
 use crate::errors::WmResult;
@@ -13,6 +17,18 @@ use super::container::{Client, Container, ContainerId, ContainerList, ContainerT
 pub struct Workspace {
     containers: ContainerList,
     layout: LayoutType,
+    /// Layout to go back to when focus mode is turned off, `Some` while focus mode is on.
+    previous_layout: Option<LayoutType>,
+    /// The part of the screen's width taken by the master window in the master-stack layout.
+    master_ratio: f32,
+    /// Number of windows in the master area of the master-stack layout.
+    nmaster: usize,
+    /// Does a lone window fill the whole screen in the master-stack layout?
+    master_fill_when_alone: bool,
+    /// How the windows next to the master are laid out in the master-stack layout.
+    stack_orientation: StackOrientation,
+    /// Is the workspace in "do not disturb" mode, keeping windows routed from elsewhere away?
+    dnd: bool,
     allowed_layouts_mask: u64,
     screen_size: Geometry,
     pub name: String,
@@ -34,6 +50,12 @@ impl Workspace {
         Self {
             containers: ContainerList::new(id),
             layout: LayoutType::default(),
+            previous_layout: None,
+            master_ratio: DEFAULT_MASTER_RATIO,
+            nmaster: 1,
+            master_fill_when_alone: true,
+            stack_orientation: StackOrientation::default(),
+            dnd: false,
             allowed_layouts_mask,
             name,
             id,
@@ -55,21 +77,61 @@ impl Workspace {
         )
     }
 
+    /// Can another tiled window be placed on this workspace, given its `max_tiled` setting?
+    pub fn can_tile(&self, max_tiled: u32) -> bool {
+        max_tiled == 0 || self.containers.in_layout_count() < max_tiled as usize
+    }
+
     /// Change the current workspace layout, given a string identifying the new layout.
     pub fn change_layout(&mut self, layout_string: String) -> WmResult {
         let layout = LayoutType::try_from(layout_string.as_str())?;
         if layout as u64 & self.allowed_layouts_mask == 1 {
-            self.layout = layout
+            self.layout = layout;
+            self.previous_layout = None;
         }
 
         Ok(())
     }
 
+    /// Return the part of the screen's width taken by the master window.
+    pub fn master_ratio(&self) -> f32 {
+        self.master_ratio
+    }
+
+    /// Set the part of the screen's width taken by the master window, clamped between
+    /// `MIN_MASTER_RATIO` and `MAX_MASTER_RATIO`.
+    pub fn set_master_ratio(&mut self, ratio: f32) {
+        self.master_ratio = ratio.clamp(MIN_MASTER_RATIO, MAX_MASTER_RATIO);
+    }
+
+    /// Return the number of windows in the master area of the master-stack layout.
+    pub fn nmaster(&self) -> usize {
+        self.nmaster
+    }
+
+    /// Set the number of windows in the master area, clamped between one and the number of tiled
+    /// windows on the workspace.
+    pub fn set_nmaster(&mut self, nmaster: usize) {
+        self.nmaster = nmaster.clamp(1, self.containers.in_layout_count().max(1));
+    }
+
+    /// Set whether a lone window fills the whole screen in the master-stack layout, or only takes
+    /// the master's part of it.
+    pub fn set_master_fill_when_alone(&mut self, fill: bool) {
+        self.master_fill_when_alone = fill;
+    }
+
+    /// Set whether the stack of the master-stack layout is split vertically or horizontally.
+    pub fn set_stack_orientation(&mut self, orientation: StackOrientation) {
+        self.stack_orientation = orientation;
+    }
+
     /// Switch to the next layout from the allowed layout mask.
     pub fn cycle_layout(&mut self) -> WmResult {
         if self.allowed_layouts_mask == 0 {
             return Err("workspace error: no layouts are available for this workspace.".into());
         }
+        self.previous_layout = None;
         let n = self.layout as u64;
         let mut active_layotus = Vec::new();
 
@@ -96,10 +158,47 @@ impl Workspace {
         Ok(())
     }
 
+    /// Turn focus mode on or off.
+    ///
+    /// In focus mode, only the focused client is shown, using the stacking layout. Turning focus
+    /// mode off restores the layout which was used before. Changing the layout turns focus mode
+    /// off as well.
+    pub fn toggle_focus_mode(&mut self) {
+        match self.previous_layout.take() {
+            Some(layout) => self.layout = layout,
+            None => {
+                self.previous_layout = Some(self.layout);
+                self.layout = LayoutType::Stacking;
+            }
+        }
+    }
+
+    /// Is focus mode on?
+    pub fn in_focus_mode(&self) -> bool {
+        self.previous_layout.is_some()
+    }
+
     pub fn current_layout(&self) -> &LayoutType {
         &self.layout
     }
 
+    /// Turn "do not disturb" mode on or off.
+    pub fn toggle_dnd(&mut self) {
+        self.dnd = !self.dnd;
+    }
+
+    /// Is "do not disturb" mode on?
+    pub fn is_dnd(&self) -> bool {
+        self.dnd
+    }
+
+    /// Does the workspace hold any windows?
+    pub fn has_windows(&self) -> bool {
+        self.containers
+            .iter()
+            .any(|c| c.data().window_id().is_some())
+    }
+
     /// Contains a client with the given window id?
     pub fn contains_window(&self, wid: u32) -> bool {
         self.containers.id_for_window(wid).is_ok()
@@ -119,9 +218,68 @@ impl Workspace {
         self.find_mut(id)
     }
 
+    /// Given a process id, return an immutable reference to the `Container` which holds a client
+    /// that belongs to this process.
+    pub fn find_by_process_id(&self, pid: u32) -> WmResult<&Container> {
+        let id = self.containers.id_for_process(pid)?;
+        self.find(id)
+    }
+
     /// Insert a client into the workspace, given a `Client` and the container type mask.
-    pub fn insert_client(&mut self, c: Client, t: u8) -> ContainerId {
-        self.containers.insert_back(c, t)
+    ///
+    /// If `first` is true, the client is inserted in front of all the other clients, otherwise it
+    /// is appended after them.
+    pub fn insert_client(&mut self, c: Client, t: u8, first: bool) -> ContainerId {
+        if first {
+            self.containers.insert_front(c, t)
+        } else {
+            self.containers.insert_back(c, t)
+        }
+    }
+
+    /// Insert a client into the workspace right before or right after the client with the given
+    /// window id. If there is no such client, the client is appended after all the other clients.
+    pub fn insert_client_next_to(
+        &mut self,
+        c: Client,
+        t: u8,
+        window: u32,
+        after: bool,
+    ) -> ContainerId {
+        match self.containers.id_for_window(window) {
+            Ok(id) => self
+                .containers
+                .insert_next_to(c, t, id, after)
+                .unwrap_or_else(|_| self.containers.insert_back(c, t)),
+            Err(_) => self.containers.insert_back(c, t),
+        }
+    }
+
+    /// Insert a newly opened client, where the `spawn_side` and `tiling_direction` settings want
+    /// it, focusing it if `take_focus` is set.
+    pub fn insert_new_client(
+        &mut self,
+        c: Client,
+        t: u8,
+        config: &Config,
+        take_focus: bool,
+    ) -> ContainerId {
+        let window = c.window_id();
+        let id = match (
+            config.settings.spawn_after_focused(),
+            self.focus.focused_client(),
+        ) {
+            (Some(after), Some(focused)) if self.contains_window(focused) => {
+                self.insert_client_next_to(c, t, focused, after)
+            }
+            _ => self.insert_client(c, t, config.settings.new_windows_first()),
+        };
+
+        if take_focus {
+            self.focus.set_focused_client(window);
+        }
+
+        id
     }
 
     /// Insert multiple clients into the workspace, given an `Iterator` over `Client`s and an
@@ -157,6 +315,12 @@ impl Workspace {
             connection,
             default_colormap,
             focused_option,
+            MasterSettings {
+                count: self.nmaster,
+                ratio: self.master_ratio,
+                fill_when_alone: self.master_fill_when_alone,
+                stack_orientation: self.stack_orientation,
+            },
         )
     }
 
@@ -194,6 +358,40 @@ impl Workspace {
         self.containers.previous_for_id(c)
     }
 
+    /// Return a reference to the next container after the given one which matches `filter`,
+    /// wrapping around. Returns `None` if no other container matches.
+    pub fn next_container_filtered(
+        &self,
+        c: ContainerId,
+        filter: impl Fn(&Container) -> bool,
+    ) -> WmResult<Option<&Container>> {
+        self.containers.next_matching_for_id(c, true, filter)
+    }
+
+    /// Return a reference to the previous container before the given one which matches `filter`,
+    /// wrapping around. Returns `None` if no other container matches.
+    pub fn previous_container_filtered(
+        &self,
+        c: ContainerId,
+        filter: impl Fn(&Container) -> bool,
+    ) -> WmResult<Option<&Container>> {
+        self.containers.next_matching_for_id(c, false, filter)
+    }
+
+    /// Attempt to return a reference to the closest in-layout container in a spatial direction.
+    pub fn nearest_container(
+        &self,
+        c: ContainerId,
+        direction: Direction,
+    ) -> WmResult<Option<&Container>> {
+        self.containers.nearest_in_direction(c, direction)
+    }
+
+    /// Return a reference to the tiled container under the point at `x` and `y`, if there is one.
+    pub fn tile_at(&self, x: i16, y: i16) -> Option<&Container> {
+        self.containers.in_layout_at(x, y)
+    }
+
     /// Attempt to find a `Container` given its `ContainerId`, returning an immutable reference to
     /// it.
     pub fn find<I: Into<ContainerId>>(&self, id: I) -> WmResult<&Container> {
@@ -215,9 +413,14 @@ impl Workspace {
     /// Take an already instantiated `Container`, inserting it into the `ContainerList`.
     ///
     /// A new `ContainerId` is generated for the container. This is used for moving `Container`s
-    /// between workspaces.
-    pub fn insert_container(&mut self, container: Container) -> WmResult<ContainerId> {
-        self.containers.container_insert_back(container)
+    /// between workspaces. Just like with `insert_client`, `first` decides on which end of the
+    /// `ContainerList` the container ends up.
+    pub fn insert_container(&mut self, container: Container, first: bool) -> WmResult<ContainerId> {
+        if first {
+            self.containers.container_insert_front(container)
+        } else {
+            self.containers.container_insert_back(container)
+        }
     }
 
     /// Get the size of the workspace in pixels.
@@ -229,6 +432,19 @@ impl Workspace {
         self.screen_size = screen_size
     }
 
+    /// Float the client with the given window id, remembering its position in the layout.
+    pub fn float_window(&mut self, wid: u32) -> WmResult {
+        let id = self.containers.id_for_window(wid)?;
+        self.containers.float(id)
+    }
+
+    /// Tile the floating client with the given window id again. With `keep_slot`, it returns to
+    /// the position in the layout it had before it was floated.
+    pub fn tile_window(&mut self, wid: u32, keep_slot: bool) -> WmResult {
+        let id = self.containers.id_for_window(wid)?;
+        self.containers.tile(id, keep_slot)
+    }
+
     /// Swap two containers.
     pub fn swap<I: Into<ContainerId>>(&mut self, a: I, b: I) -> WmResult {
         self.containers.swap(a, b)?;
@@ -243,3 +459,211 @@ impl Workspace {
 pub type WorkspaceId = u32;
 
 pub type Workspaces = Vec<Workspace>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parsers::ConfigParser, wm::layouts::LayoutMask};
+
+    #[test]
+    fn newest_window_first() {
+        let config = Config::default();
+        let screen = Geometry {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let mut workspace = Workspace::new("1".into(), 1, 0, 0, screen, 1);
+
+        for window in 1..=3u32 {
+            let client = Client::new_without_process_id(window, screen, window as u64, &config);
+            workspace.insert_client(client, ContainerTypeMask::TILING, true);
+        }
+
+        // The equal tiling layouts place containers in list order, so the first container gets the
+        // leftmost tile.
+        let order: Vec<Option<u32>> = workspace
+            .iter_containers()
+            .unwrap()
+            .map(|c| c.data().window_id())
+            .collect();
+        assert_eq!(order, vec![Some(3), Some(2), Some(1)]);
+    }
+
+    #[test]
+    fn new_client_focus() {
+        let config = Config::default();
+        let screen = Geometry {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let mut workspace = Workspace::new("1".into(), 1, 0, 0, screen, 1);
+        let client =
+            |window: u32| Client::new_without_process_id(window, screen, window as u64, &config);
+
+        workspace.insert_new_client(client(1), ContainerTypeMask::TILING, &config, true);
+        assert_eq!(workspace.focus.focused_client(), Some(1));
+
+        // a window opened because of a `no_focus` rule leaves the focus where it was
+        let rule_config = ConfigParser::parse_str("rule class mpv no_focus true").unwrap();
+        let rule = rule_config.rules.effects_for(None, Some("mpv"), None);
+        workspace.insert_new_client(
+            client(2),
+            ContainerTypeMask::TILING,
+            &config,
+            rule.takes_focus(),
+        );
+        assert_eq!(workspace.focus.focused_client(), Some(1));
+        assert!(workspace.contains_window(2));
+
+        workspace.insert_new_client(client(3), ContainerTypeMask::FLOATING, &config, true);
+        assert_eq!(workspace.focus.focused_client(), Some(3));
+    }
+
+    #[test]
+    fn focus_mode() {
+        let screen = Geometry {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let mut workspace = Workspace::new("1".into(), 1, LayoutMask::ALL, 0, screen, 1);
+        workspace.cycle_layout().unwrap();
+        assert!(matches!(
+            workspace.current_layout(),
+            LayoutType::TilingEqualVertical
+        ));
+
+        workspace.toggle_focus_mode();
+        assert!(workspace.in_focus_mode());
+        assert!(matches!(workspace.current_layout(), LayoutType::Stacking));
+
+        workspace.toggle_focus_mode();
+        assert!(!workspace.in_focus_mode());
+        assert!(matches!(
+            workspace.current_layout(),
+            LayoutType::TilingEqualVertical
+        ));
+
+        // changing the layout leaves focus mode
+        workspace.toggle_focus_mode();
+        workspace.cycle_layout().unwrap();
+        assert!(!workspace.in_focus_mode());
+    }
+
+    #[test]
+    fn filtered_focus_order() {
+        let config = Config::default();
+        let screen = Geometry {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let mut workspace = Workspace::new("1".into(), 1, 0, 0, screen, 1);
+
+        // windows 1 and 3 are tiled, 2 and 4 are floating
+        let mut ids = Vec::new();
+        for window in 1..=4u32 {
+            let client = Client::new_without_process_id(window, screen, window as u64, &config);
+            let container_type = if window % 2 == 0 {
+                ContainerTypeMask::FLOATING
+            } else {
+                ContainerTypeMask::TILING
+            };
+            ids.push(workspace.insert_client(client, container_type, false));
+        }
+        let window = |c: Option<&Container>| c.and_then(|c| c.data().window_id());
+        let floating = |c: &Container| c.is_floating();
+        let tiled = |c: &Container| c.is_in_layout();
+
+        let next = workspace.next_container_filtered(ids[1], floating).unwrap();
+        assert_eq!(window(next), Some(4));
+        let next = workspace.next_container_filtered(ids[3], floating).unwrap();
+        assert_eq!(window(next), Some(2));
+        let previous = workspace
+            .previous_container_filtered(ids[1], floating)
+            .unwrap();
+        assert_eq!(window(previous), Some(4));
+
+        // starting from a client of the other kind
+        let next = workspace.next_container_filtered(ids[0], floating).unwrap();
+        assert_eq!(window(next), Some(2));
+        let next = workspace.next_container_filtered(ids[2], tiled).unwrap();
+        assert_eq!(window(next), Some(1));
+        let previous = workspace
+            .previous_container_filtered(ids[0], tiled)
+            .unwrap();
+        assert_eq!(window(previous), Some(3));
+
+        workspace.remove_window(3).unwrap();
+        assert!(workspace
+            .next_container_filtered(ids[0], tiled)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn max_tiled() {
+        let config = Config::default();
+        let screen = Geometry {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let mut workspace = Workspace::new("1".into(), 1, 0, 0, screen, 1);
+
+        for window in 1..=2u32 {
+            let client = Client::new_without_process_id(window, screen, window as u64, &config);
+            workspace.insert_client(client, ContainerTypeMask::TILING, true);
+        }
+        let client = Client::new_without_process_id(3u32, screen, 3u64, &config);
+        workspace.insert_client(client, ContainerTypeMask::FLOATING, true);
+
+        // Floating windows don't count towards the limit.
+        assert!(workspace.can_tile(0));
+        assert!(workspace.can_tile(3));
+        assert!(!workspace.can_tile(2));
+        assert!(!workspace.can_tile(1));
+    }
+
+    #[test]
+    fn master_ratio() {
+        let mut workspace = Workspace::new("1".into(), 1, 0, 0, Geometry::default(), 1);
+        assert_eq!(workspace.master_ratio(), 0.5);
+
+        workspace.set_master_ratio(0.6);
+        assert_eq!(workspace.master_ratio(), 0.6);
+        workspace.set_master_ratio(0.95);
+        assert_eq!(workspace.master_ratio(), 0.9);
+        workspace.set_master_ratio(-1.0);
+        assert_eq!(workspace.master_ratio(), 0.1);
+    }
+
+    #[test]
+    fn nmaster() {
+        let config = Config::default();
+        let mut workspace = Workspace::new("1".into(), 1, 0, 0, Geometry::default(), 1);
+        assert_eq!(workspace.nmaster(), 1);
+
+        workspace.set_nmaster(2);
+        assert_eq!(workspace.nmaster(), 1);
+
+        for window in 1..=4u32 {
+            let client =
+                Client::new_without_process_id(window, Geometry::default(), window as u64, &config);
+            workspace.insert_client(client, ContainerTypeMask::TILING, true);
+        }
+        workspace.set_nmaster(2);
+        assert_eq!(workspace.nmaster(), 2);
+        workspace.set_nmaster(9);
+        assert_eq!(workspace.nmaster(), 4);
+        workspace.set_nmaster(0);
+        assert_eq!(workspace.nmaster(), 1);
+    }
+}